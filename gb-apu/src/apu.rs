@@ -1,37 +1,84 @@
 use std::sync::{Arc, Mutex};
 
+use crate::control::{
+    high_pass::HighPass,
+    resampler::Resampler,
+    scope::{Scope, ScopePoint},
+    write_log::WriteLog,
+};
 use crate::{
     channel::sound_channel::SoundChannel, control::frame_sequencer::FrameSequencer, ChannelType,
     MASK_UNUSED_BITS_70,
 };
-use crate::{NB_CYCLES_512_HZ, SAMPLE_RATES, T_CYCLE_FREQUENCY};
+use crate::{NB_CYCLES_512_HZ, OUTPUT_CHANNELS, SAMPLE_RATES, SOUND_CHANNELS};
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use cpal::{
     BuildStreamError, Device, SampleFormat, SampleRate, Stream, StreamConfig, StreamError,
     SupportedBufferSize, SupportedStreamConfig,
 };
+use gb_bus::io_reg_constant::{
+    NR10, NR14, NR21, NR24, NR30, NR34, NR41, NR44, NR50, NR51, NR52, WAVE_RAM_0, WAVE_RAM_F,
+};
 use gb_bus::{Address, Bus, Error, FileOperation, IORegArea, Source};
 use gb_clock::{Tick, Ticker};
 
+/// Number of registers from NR10 to the end of the wave RAM
+const REGISTERS: usize = (WAVE_RAM_F - NR10 + 1) as usize;
+
+/// Copy of the state of the channels, the output is not part of it
+#[derive(Clone)]
+pub struct ApuSnapshot {
+    cycle_counter: u32,
+    enabled: bool,
+    sound_channels: Vec<SoundChannel>,
+    frame_sequencer: FrameSequencer,
+    master_bits: u8,
+    panning_bits: u8,
+    written: [u8; REGISTERS],
+}
+
 pub struct Apu {
     cycle_counter: u32,
-    nb_cycles_per_sample: u32,
+    resampler: Resampler,
+    high_pass: HighPass,
     enabled: bool,
+    /// Interleaved stereo frames, the left sample first
     buffer: Arc<Mutex<Vec<f32>>>,
     sound_channels: Vec<SoundChannel>,
     frame_sequencer: FrameSequencer,
+    /// NR50, the volumes of the left (SO2) and right (SO1) outputs
     master_bits: u8,
-    master_volume: u8,
+    /// NR51, the outputs each channel is sent to
     panning_bits: u8,
     stream: Option<Stream>,
     output_volume: f32,
+    sample_rate: SampleRate,
+    /// Frames produced since the last call to [Apu::take_recorded], kept while recording
+    recorded: Option<Vec<[f32; OUTPUT_CHANNELS]>>,
+    /// Channels left out of the mix
+    muted: [bool; SOUND_CHANNELS],
+    /// Channels heard alone in the mix when any is soloed
+    soloed: [bool; SOUND_CHANNELS],
+    /// Latest outputs of the channels, kept while the debugger shows them
+    scope: Option<Scope>,
+    /// T-cycles since the power on, the replayed cycles excluded
+    cycles: u64,
+    /// Last value written to each register from NR10, the wave RAM included
+    written: [u8; REGISTERS],
+    /// Writes to the registers, kept while they are logged
+    write_log: Option<WriteLog>,
+    /// The cycles are executed again after a rewind, they were already heard
+    replaying: bool,
 }
 
 impl Apu {
+    /// Create the APU, the samples are produced for `stream`.
+    /// Without a stream the channels still run but their output is dropped
     pub fn new(
         input_buffer: Arc<Mutex<Vec<f32>>>,
         stream: Option<Stream>,
         sample_rate: SampleRate,
+        cgb_mode: bool,
     ) -> Apu {
         // Channels order in vector is important !
         let sound_channels = vec![
@@ -43,30 +90,184 @@ impl Apu {
 
         Self {
             cycle_counter: 0,
-            nb_cycles_per_sample: T_CYCLE_FREQUENCY / sample_rate.0,
+            resampler: Resampler::new(sample_rate.0),
+            high_pass: HighPass::new(cgb_mode, sample_rate.0),
             enabled: false,
             buffer: input_buffer,
             sound_channels,
             frame_sequencer: FrameSequencer::default(),
             master_bits: 0,
-            master_volume: 0,
             panning_bits: 0,
             stream,
             output_volume: 0.7,
+            sample_rate,
+            recorded: None,
+            muted: [false; SOUND_CHANNELS],
+            soloed: [false; SOUND_CHANNELS],
+            scope: None,
+            cycles: 0,
+            written: [0; REGISTERS],
+            write_log: None,
+            replaying: false,
+        }
+    }
+
+    pub fn snapshot(&self) -> ApuSnapshot {
+        ApuSnapshot {
+            cycle_counter: self.cycle_counter,
+            enabled: self.enabled,
+            sound_channels: self.sound_channels.clone(),
+            frame_sequencer: self.frame_sequencer.clone(),
+            master_bits: self.master_bits,
+            panning_bits: self.panning_bits,
+            written: self.written,
+        }
+    }
+
+    /// Put the channels back in the state of the snapshot, the output goes on
+    pub fn restore(&mut self, snapshot: &ApuSnapshot) {
+        self.cycle_counter = snapshot.cycle_counter;
+        self.enabled = snapshot.enabled;
+        self.sound_channels = snapshot.sound_channels.clone();
+        self.frame_sequencer = snapshot.frame_sequencer.clone();
+        self.master_bits = snapshot.master_bits;
+        self.panning_bits = snapshot.panning_bits;
+        self.written = snapshot.written;
+    }
+
+    /// While replaying the channels run and their registers are written as usual,
+    /// but no sample is produced, recorded or shown and the writes are not logged
+    pub fn set_replaying(&mut self, replaying: bool) {
+        self.replaying = replaying;
+    }
+
+    /// Return the sound channels, in the order of their registers
+    pub fn channels(&self) -> &[SoundChannel] {
+        &self.sound_channels
+    }
+
+    pub fn is_muted(&self, channel: usize) -> bool {
+        self.muted[channel]
+    }
+
+    pub fn set_muted(&mut self, channel: usize, muted: bool) {
+        self.muted[channel] = muted;
+    }
+
+    pub fn is_soloed(&self, channel: usize) -> bool {
+        self.soloed[channel]
+    }
+
+    pub fn set_soloed(&mut self, channel: usize, soloed: bool) {
+        self.soloed[channel] = soloed;
+    }
+
+    /// Return whether `channel` is part of the mix, the soloed channels silence the others
+    fn is_audible(&self, channel: usize) -> bool {
+        if self.soloed.contains(&true) {
+            self.soloed[channel]
+        } else {
+            !self.muted[channel]
+        }
+    }
+
+    /// Start or stop keeping the latest outputs of the channels
+    pub fn set_scope(&mut self, enabled: bool) {
+        if enabled != self.scope.is_some() {
+            self.scope = enabled.then(Scope::default);
+        }
+    }
+
+    pub fn scope(&self) -> Option<&Scope> {
+        self.scope.as_ref()
+    }
+
+    /// Start to log the writes to the registers, the log begins with the state of the apu
+    pub fn start_write_log(&mut self) {
+        self.write_log = Some(WriteLog::new(self.cycles, &self.state_writes()));
+    }
+
+    /// Stop the log of the writes and return it
+    pub fn stop_write_log(&mut self) -> Option<WriteLog> {
+        let mut log = self.write_log.take()?;
+        log.stop(self.cycles);
+        Some(log)
+    }
+
+    /// Return the writes restoring the current state of the registers,
+    /// the playing channels are triggered again
+    fn state_writes(&self) -> Vec<(u8, u8)> {
+        let register = |address: u16| (address - NR10) as u8;
+        let mut writes = vec![(
+            register(NR52),
+            self.get_power_channels_statuses_byte() & 0x80,
+        )];
+        if self.enabled {
+            writes.push((register(NR50), self.written[usize::from(register(NR50))]));
+            writes.push((register(NR51), self.written[usize::from(register(NR51))]));
+        }
+        // the wave RAM is only written while the DAC of the wave channel is off
+        writes.push((register(NR30), 0));
+        writes.extend(
+            (register(WAVE_RAM_0)..=register(WAVE_RAM_F))
+                .map(|offset| (offset, self.written[usize::from(offset)])),
+        );
+        if self.enabled {
+            let channels = [(NR10, NR14), (NR21, NR24), (NR30, NR34), (NR41, NR44)];
+            for (index, (first, last)) in channels.into_iter().enumerate() {
+                let trigger = register(last);
+                for offset in register(first)..trigger {
+                    writes.push((offset, self.written[usize::from(offset)]));
+                }
+                let mut control = self.written[usize::from(trigger)] & 0x7f;
+                if self.sound_channels[index].enabled {
+                    control |= 0x80;
+                }
+                writes.push((trigger, control));
+            }
         }
+        writes
+    }
+
+    /// Return the rate of the produced samples
+    pub fn sample_rate(&self) -> SampleRate {
+        self.sample_rate
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recorded.is_some()
+    }
+
+    /// Keep the produced frames until they are taken, even without an output stream.
+    /// The recorded frames are not affected by the output volume
+    pub fn set_recording(&mut self, recording: bool) {
+        if recording != self.is_recording() {
+            self.recorded = recording.then(Vec::new);
+        }
+    }
+
+    /// Return the frames recorded since the last call
+    pub fn take_recorded(&mut self) -> Vec<[f32; OUTPUT_CHANNELS]> {
+        self.recorded
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
     }
 
     pub fn output_volume(&mut self) -> &mut f32 {
         &mut self.output_volume
     }
 
-    pub fn init_audio_output(input_buffer: Arc<Mutex<Vec<f32>>>) -> (Stream, SampleRate) {
-        let required_buffer_size = input_buffer.lock().unwrap().capacity();
+    /// Open the audio output, `input_buffer` hold interleaved stereo frames
+    /// and its capacity set the size of the buffer of the output
+    pub fn init_audio_output(
+        input_buffer: Arc<Mutex<Vec<f32>>>,
+    ) -> Result<(Stream, SampleRate), String> {
+        let required_buffer_size = input_buffer.lock().unwrap().capacity() / OUTPUT_CHANNELS;
 
         let host = cpal::default_host();
         let (device, supported_config) =
-            Apu::get_supported_device(host, required_buffer_size as u32, SAMPLE_RATES)
-                .expect("cannot get a valid device / config");
+            Apu::get_supported_device(host, required_buffer_size as u32, SAMPLE_RATES)?;
 
         let err_fn = |err| log::error!("an error occurred on the output audio stream: {}", err);
         let sample_format = supported_config.sample_format();
@@ -74,10 +275,12 @@ impl Apu {
         config.buffer_size = cpal::BufferSize::Fixed(required_buffer_size as u32);
         log::debug!("configured config: {:?}", config);
 
-        let stream =
-            Apu::build_output_stream(device, sample_format, &config, input_buffer, err_fn).unwrap();
-        stream.play().unwrap();
-        (stream, config.sample_rate)
+        let stream = Apu::build_output_stream(device, sample_format, &config, input_buffer, err_fn)
+            .map_err(|e| format!("cannot build the output stream: {}", e))?;
+        stream
+            .play()
+            .map_err(|e| format!("cannot play the output stream: {}", e))?;
+        Ok((stream, config.sample_rate))
     }
 
     fn get_supported_device(
@@ -87,7 +290,7 @@ impl Apu {
     ) -> Result<(Device, SupportedStreamConfig), String> {
         let devices = host
             .output_devices()
-            .expect("cannot retrieve any output device");
+            .map_err(|e| format!("cannot retrieve any output device: {}", e))?;
         let devices = devices.filter_map(|device| {
             let supported_output_configs = device.supported_output_configs();
             match supported_output_configs {
@@ -159,13 +362,15 @@ impl Apu {
     {
         let channels = config.channels as usize;
 
-        // callback used to get the next sample
+        // callback used to get the next stereo frame
         let mut next_value = move || {
             let mut buffer = input_buffer.lock().unwrap();
-            if buffer.len() > 0 {
-                buffer.remove(0)
+            if buffer.len() >= OUTPUT_CHANNELS {
+                let frame = [buffer[0], buffer[1]];
+                buffer.drain(..OUTPUT_CHANNELS);
+                frame
             } else {
-                0.0
+                [0.0; OUTPUT_CHANNELS]
             }
         };
 
@@ -197,40 +402,85 @@ impl Apu {
     fn write_data<T, N>(output: &mut [T], channels: usize, next_value: &mut N)
     where
         T: cpal::Sample,
-        N: FnMut() -> f32,
+        N: FnMut() -> [f32; OUTPUT_CHANNELS],
         N: Send + 'static,
     {
         for frame in output.chunks_mut(channels) {
-            let value = next_value();
-            let value: T = cpal::Sample::from::<f32>(&value);
-            for sample in frame.iter_mut() {
-                *sample = value;
+            let [left, right] = next_value();
+            if let [sample] = frame {
+                *sample = cpal::Sample::from::<f32>(&((left + right) / 2.0));
+                continue;
+            }
+            // the extra speakers get the side of their index
+            for (i, sample) in frame.iter_mut().enumerate() {
+                let value = if i % 2 == 0 { left } else { right };
+                *sample = cpal::Sample::from::<f32>(&value);
             }
         }
     }
 
+    /// Return true when the samples are played by an output stream
+    pub fn has_output(&self) -> bool {
+        self.stream.is_some()
+    }
+
     pub fn is_buffer_full(&self) -> bool {
         let buffer = self.buffer.lock().unwrap();
         buffer.len() == buffer.capacity()
     }
 
-    fn add_sample(&mut self) {
-        let sample = if self.enabled {
-            self.mix() * 0.3 * (self.master_volume as f32) * self.output_volume
-        } else {
-            0.0
-        };
-        self.buffer.lock().unwrap().push(sample);
+    fn add_sample(&mut self, frame: [f32; OUTPUT_CHANNELS]) {
+        let dacs_enabled = self.enabled
+            && self
+                .sound_channels
+                .iter()
+                .any(|channel| channel.dac_enabled);
+        let frame = self.high_pass.filter(frame, dacs_enabled);
+        if let Some(recorded) = self.recorded.as_mut() {
+            recorded.push(frame);
+        }
+        if self.stream.is_some() {
+            let gain = 0.3 * self.output_volume;
+            self.buffer
+                .lock()
+                .unwrap()
+                .extend_from_slice(&frame.map(|sample| sample * gain));
+        }
+    }
+
+    /// Return the output of the current T-cycle, with the volumes of NR50 applied
+    fn output(&self) -> [f32; OUTPUT_CHANNELS] {
+        if !self.enabled {
+            return [0.0; OUTPUT_CHANNELS];
+        }
+        let [left, right] = self.mix();
+        let (left_volume, right_volume) = self.master_volumes();
+        [left * left_volume, right * right_volume]
     }
 
-    fn mix(&self) -> f32 {
-        let mut sample = 0.0;
+    /// Mix the channels sent to each output according to NR51, the left output first
+    fn mix(&self) -> [f32; OUTPUT_CHANNELS] {
+        let mut frame = [0.0; OUTPUT_CHANNELS];
 
-        for i in 0..self.sound_channels.len() {
-            sample += self.sound_channels[i].get_dac_output();
+        for (i, channel) in self.sound_channels.iter().enumerate() {
+            if !self.is_audible(i) {
+                continue;
+            }
+            let output = channel.get_dac_output();
+            if self.panning_bits & (0x10 << i) != 0 {
+                frame[0] += output;
+            }
+            if self.panning_bits & (0x01 << i) != 0 {
+                frame[1] += output;
+            }
         }
-        sample /= self.sound_channels.len() as f32;
-        sample
+        frame.map(|sample| sample / self.sound_channels.len() as f32)
+    }
+
+    /// Return the volumes of the left and right outputs set by NR50, between 1/8 and 1
+    fn master_volumes(&self) -> (f32, f32) {
+        let volume = |bits: u8| f32::from((bits & 0b111) + 1) / 8.0;
+        (volume(self.master_bits >> 4), volume(self.master_bits))
     }
 
     fn get_power_channels_statuses_byte(&self) -> u8 {
@@ -266,17 +516,25 @@ impl Ticker for Apu {
     }
 
     fn tick(&mut self, _addr_bus: &mut dyn Bus<u8>) {
+        if !self.replaying {
+            self.cycles += 1;
+        }
+        // Frame sequencer is clocked at 512 Hz
+        // 0x400_000 (TCycle freq.) / 0x2000 = 512 Hz
         self.cycle_counter += 1;
-        if self.enabled && self.stream.is_some() {
+        let frame_sequencer_clocked = self.cycle_counter >= NB_CYCLES_512_HZ;
+        if frame_sequencer_clocked {
+            self.cycle_counter %= NB_CYCLES_512_HZ;
+        }
+
+        // the channels run whether or not their output is heard,
+        // their state is visible through the registers
+        if self.enabled {
             for i in 0..self.sound_channels.len() {
                 self.sound_channels[i].step();
             }
 
-            // Frame sequencer is clocked at 512 Hz
-            // 0x400_000 (TCycle freq.) / 0x2000 = 512 Hz
-            if self.cycle_counter >= NB_CYCLES_512_HZ {
-                self.cycle_counter %= NB_CYCLES_512_HZ;
-
+            if frame_sequencer_clocked {
                 let step = self.frame_sequencer.step();
                 for i in 0..self.sound_channels.len() {
                     self.sound_channels[i].frame_sequencer(step);
@@ -284,8 +542,21 @@ impl Ticker for Apu {
             }
         }
 
-        if self.cycle_counter % self.nb_cycles_per_sample == 0 {
-            self.add_sample();
+        // without an output stream, a recording or a scope nobody consumes the samples
+        if !self.replaying
+            && (self.stream.is_some() || self.recorded.is_some() || self.scope.is_some())
+        {
+            if let Some(frame) = self.resampler.push(self.output()) {
+                if let Some(scope) = self.scope.as_mut() {
+                    let mut point: ScopePoint = [0.0; SOUND_CHANNELS + 1];
+                    for (output, channel) in point.iter_mut().zip(&self.sound_channels) {
+                        *output = channel.get_dac_output();
+                    }
+                    point[SOUND_CHANNELS] = (frame[0] + frame[1]) / 2.0;
+                    scope.push(point);
+                }
+                self.add_sample(frame);
+            }
         }
     }
 }
@@ -317,13 +588,28 @@ where
             _ => Err(Error::SegmentationFault(addr.into())),
         }
     }
-    fn write(&mut self, v: u8, addr: A, _source: Option<Source>) -> Result<(), Error> {
+    fn write(&mut self, v: u8, addr: A, source: Option<Source>) -> Result<(), Error> {
         use IORegArea::{
             Nr10, Nr11, Nr12, Nr13, Nr14, Nr21, Nr22, Nr23, Nr24, Nr30, Nr31, Nr32, Nr33, Nr34,
             Nr41, Nr42, Nr43, Nr44, Nr50, Nr51, Nr52, WaveRam0, WaveRam1, WaveRam2, WaveRam3,
             WaveRam4, WaveRam5, WaveRam6, WaveRam7, WaveRam8, WaveRam9, WaveRamA, WaveRamB,
             WaveRamC, WaveRamD, WaveRamE, WaveRamF,
         };
+        let address = addr.area_type() as u16;
+        // the registers ignore the writes while the apu is off, except NR52 and the wave RAM
+        if (NR10..=WAVE_RAM_F).contains(&address)
+            && (self.enabled || address == NR52 || address >= WAVE_RAM_0)
+        {
+            self.written[usize::from(address - NR10)] = v;
+        }
+        // the writes of the debugger aren't made by the game
+        if let Some(log) = self
+            .write_log
+            .as_mut()
+            .filter(|_| !self.replaying && source != Some(Source::Debugger))
+        {
+            log.push(self.cycles, address, v);
+        }
         match addr.area_type() {
             Nr10 | Nr11 | Nr12 | Nr13 | Nr14 => {
                 if self.enabled {
@@ -358,20 +644,11 @@ where
             }
             Nr50 => {
                 if self.enabled {
-                    let master_left = ((v >> 4) & 0b111) / 0b111;
-                    let master_right = (v & 0b111) / 0b111;
-                    self.master_volume = master_left.max(master_right);
-
                     self.master_bits = v;
                 }
             }
             Nr51 => {
                 if self.enabled {
-                    self.sound_channels[0].dac_enabled = v & 0b1_0000 != 0 || v & 0b1 != 0;
-                    self.sound_channels[1].dac_enabled = v & 0b10_0000 != 0 || v & 0b10 != 0;
-                    self.sound_channels[2].dac_enabled = v & 0b100_0000 != 0 || v & 0b100 != 0;
-                    self.sound_channels[3].dac_enabled = v & 0b1000_0000 != 0 || v & 0b1000 != 0;
-
                     self.panning_bits = v;
                 }
             }
@@ -387,6 +664,7 @@ where
                     ];
                     self.master_bits = 0;
                     self.panning_bits = 0;
+                    self.written[..usize::from(NR52 - NR10)].fill(0);
                 }
                 self.enabled = enabled;
             }
@@ -6,7 +6,7 @@ const DUTY_CYCLES: [[u8; DUTY_CYCLES_SIZE]; 4] = [
     [0, 1, 1, 1, 1, 1, 1, 0],
 ];
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone)]
 pub struct Duty {
     pub pattern_index: u8,
     step: u8,
@@ -1,6 +1,6 @@
 use crate::ChannelType;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct LengthCounter {
     channel_type: ChannelType,
     pub enabled: bool,
@@ -1,13 +1,13 @@
 const LFSR_ALL_BIT_SET: u16 = 0x7FFF;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum WidthMode {
     Width7Bits,
     Width14Bits,
 }
 
 // Linear Feedback Shift Register
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Lfsr {
     value: u16,
     pub width_mode: WidthMode,
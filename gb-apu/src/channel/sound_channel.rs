@@ -6,13 +6,13 @@ use crate::{
     channel::timer::Timer,
     channel::volume_envelope::{Direction, VolumeEnvelope},
     ChannelType, MASK_UNUSED_BITS_3F, MASK_UNUSED_BITS_7F, MASK_UNUSED_BITS_80,
-    MASK_UNUSED_BITS_9F, MASK_UNUSED_BITS_BF, MASK_UNUSED_BITS_FF,
+    MASK_UNUSED_BITS_9F, MASK_UNUSED_BITS_BF, MASK_UNUSED_BITS_FF, T_CYCLE_FREQUENCY,
 };
 use gb_bus::{io_reg_constant::WAVE_RAM_0, Address, Error, FileOperation, IORegArea, Source};
 
 use super::wave_ram::ProgrammableWave;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SoundChannel {
     pub enabled: bool,
     pub dac_enabled: bool,
@@ -103,6 +103,45 @@ impl SoundChannel {
         }
     }
 
+    pub fn sweep(&self) -> Option<&Sweep> {
+        self.sweep.as_ref()
+    }
+
+    pub fn duty(&self) -> Option<&Duty> {
+        self.duty.as_ref()
+    }
+
+    pub fn length_counter(&self) -> &LengthCounter {
+        &self.length_counter
+    }
+
+    pub fn volume_envelope(&self) -> Option<&VolumeEnvelope> {
+        self.volume_envelope.as_ref()
+    }
+
+    pub fn timer(&self) -> &Timer {
+        &self.timer
+    }
+
+    pub fn programmable_wave(&self) -> Option<&ProgrammableWave> {
+        self.programmable_wave.as_ref()
+    }
+
+    pub fn lfsr(&self) -> Option<&Lfsr> {
+        self.lfsr.as_ref()
+    }
+
+    /// Return the frequency of the tone in Hz,
+    /// for the noise channel the frequency at which the LFSR is clocked
+    pub fn tone_frequency(&self) -> f64 {
+        let steps = match self.channel_type {
+            ChannelType::SquareWave => 8,
+            ChannelType::WaveForm => 32,
+            ChannelType::Noise => 1,
+        };
+        f64::from(T_CYCLE_FREQUENCY) / f64::from(self.timer.period() * steps)
+    }
+
     pub fn step(&mut self) {
         let reached_zero = self.timer.step();
         if reached_zero {
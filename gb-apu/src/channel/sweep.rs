@@ -1,6 +1,6 @@
 use super::volume_envelope::Direction;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Sweep {
     pub enabled: bool,
     pub period: u8,
@@ -1,5 +1,5 @@
 use crate::ChannelType;
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Timer {
     channel_type: ChannelType,
     pub frequency: u16,
@@ -36,11 +36,16 @@ impl Timer {
         }
     }
 
-    pub fn reload(&mut self) {
-        self.counter = match self.channel_type {
+    /// Return the number of T-cycles between two steps of the channel
+    pub fn period(&self) -> u32 {
+        match self.channel_type {
             ChannelType::SquareWave => ((2048 - self.frequency) * 4) as u32,
             ChannelType::WaveForm => ((2048 - self.frequency) * 2) as u32,
             ChannelType::Noise => self.divisor() << self.shift_amout,
         }
     }
+
+    pub fn reload(&mut self) {
+        self.counter = self.period();
+    }
 }
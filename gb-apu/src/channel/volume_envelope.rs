@@ -1,10 +1,10 @@
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
 pub enum Direction {
     Inc,
     Dec,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct VolumeEnvelope {
     pub initial_volume: u8,
     pub envelope_direction: Direction,
@@ -1 +1,5 @@
 pub mod frame_sequencer;
+pub mod high_pass;
+pub mod resampler;
+pub mod scope;
+pub mod write_log;
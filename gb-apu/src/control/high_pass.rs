@@ -0,0 +1,63 @@
+use crate::{OUTPUT_CHANNELS, T_CYCLE_FREQUENCY};
+
+/// Charge kept by the capacitors of the outputs at each T-cycle
+const DMG_CHARGE_FACTOR: f64 = 0.999958;
+const CGB_CHARGE_FACTOR: f64 = 0.998943;
+
+/// High-pass filter made by the capacitors of the outputs,
+/// remove the offset of the DACs and the pops when they are switched on or off
+pub struct HighPass {
+    capacitors: [f32; OUTPUT_CHANNELS],
+    /// Charge kept by the capacitors between two samples
+    charge_factor: f32,
+}
+
+impl HighPass {
+    pub fn new(cgb_mode: bool, sample_rate: u32) -> Self {
+        let factor = if cgb_mode {
+            CGB_CHARGE_FACTOR
+        } else {
+            DMG_CHARGE_FACTOR
+        };
+        Self {
+            capacitors: [0.0; OUTPUT_CHANNELS],
+            charge_factor: factor.powf(f64::from(T_CYCLE_FREQUENCY) / f64::from(sample_rate))
+                as f32,
+        }
+    }
+
+    /// Filter a sample, the outputs are silent while every DAC is off
+    pub fn filter(
+        &mut self,
+        frame: [f32; OUTPUT_CHANNELS],
+        dacs_enabled: bool,
+    ) -> [f32; OUTPUT_CHANNELS] {
+        if !dacs_enabled {
+            return [0.0; OUTPUT_CHANNELS];
+        }
+        let mut output = [0.0; OUTPUT_CHANNELS];
+        for ((output, capacitor), input) in output.iter_mut().zip(&mut self.capacitors).zip(frame) {
+            *output = input - *capacitor;
+            *capacitor = input - *output * self.charge_factor;
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod test_high_pass {
+    use super::HighPass;
+
+    #[test]
+    fn remove_offset() {
+        let mut high_pass = HighPass::new(false, 48000);
+        let first = high_pass.filter([-1.0, 1.0], true);
+        assert_eq!(first, [-1.0, 1.0]);
+        let last = (0..48000)
+            .map(|_| high_pass.filter([-1.0, 1.0], true))
+            .last()
+            .unwrap();
+        assert!(last[0].abs() < 0.01 && last[1].abs() < 0.01);
+        assert_eq!(high_pass.filter([1.0, 1.0], false), [0.0, 0.0]);
+    }
+}
@@ -0,0 +1,157 @@
+use crate::{OUTPUT_CHANNELS, T_CYCLE_FREQUENCY};
+use std::f64::consts::PI;
+
+/// Length of a band-limited step, in samples, the output is delayed by half of it
+const STEP_WIDTH: usize = 32;
+/// Positions of a step between two samples with their own precomputed kernel
+const PHASES: usize = 64;
+/// Cutoff frequency of the steps relative to the sample rate, below the Nyquist frequency
+const CUTOFF: f64 = 0.45;
+
+/// Decimate the output of the APU, produced at each T-cycle, to the sample rate of the host.
+///
+/// The output of the APU is made of steps, each change of level is added to the samples
+/// as a band-limited step: a windowed sinc integrated over time. The frequencies above the
+/// Nyquist frequency of the host are filtered out instead of aliasing.
+/// The period is fractional, so the pitch stays right at any sample rate.
+pub struct Resampler {
+    /// Samples produced per T-cycle
+    samples_per_cycle: f64,
+    /// Position of the current T-cycle after the last sample, in samples
+    position: f64,
+    /// Level of the output at the previous T-cycle
+    level: [f32; OUTPUT_CHANNELS],
+    /// Changes of level added to the next samples, a ring starting at `head`
+    pending: [[f32; OUTPUT_CHANNELS]; STEP_WIDTH],
+    head: usize,
+    /// Sum of the changes of level up to the last sample
+    sum: [f32; OUTPUT_CHANNELS],
+    /// Derivative of a band-limited step for each phase, each one sums to 1
+    kernels: Vec<[f32; STEP_WIDTH]>,
+}
+
+impl Resampler {
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            samples_per_cycle: f64::from(sample_rate) / f64::from(T_CYCLE_FREQUENCY),
+            position: 0.0,
+            level: [0.0; OUTPUT_CHANNELS],
+            pending: [[0.0; OUTPUT_CHANNELS]; STEP_WIDTH],
+            head: 0,
+            sum: [0.0; OUTPUT_CHANNELS],
+            kernels: (0..=PHASES).map(kernel).collect(),
+        }
+    }
+
+    /// Add the output of a T-cycle, return a sample once its period is covered
+    pub fn push(&mut self, frame: [f32; OUTPUT_CHANNELS]) -> Option<[f32; OUTPUT_CHANNELS]> {
+        if frame != self.level {
+            let phase = (self.position * PHASES as f64).round() as usize;
+            let kernel = &self.kernels[phase.min(PHASES)];
+            for (offset, weight) in kernel.iter().enumerate() {
+                let pending = &mut self.pending[(self.head + offset) % STEP_WIDTH];
+                for ((pending, level), value) in pending.iter_mut().zip(self.level).zip(frame) {
+                    *pending += (value - level) * weight;
+                }
+            }
+            self.level = frame;
+        }
+
+        self.position += self.samples_per_cycle;
+        if self.position < 1.0 {
+            return None;
+        }
+        self.position -= 1.0;
+        let changes = std::mem::take(&mut self.pending[self.head]);
+        self.head = (self.head + 1) % STEP_WIDTH;
+        for (sum, change) in self.sum.iter_mut().zip(changes) {
+            *sum += change;
+        }
+        Some(self.sum)
+    }
+}
+
+/// Return the derivative of a step happening at `phase / PHASES` samples after a sample,
+/// sampled on the next [STEP_WIDTH] samples: a sinc low-pass filter with a Blackman window
+fn kernel(phase: usize) -> [f32; STEP_WIDTH] {
+    let start = phase as f64 / PHASES as f64;
+    let mut kernel = [0.0; STEP_WIDTH];
+    let mut total = 0.0;
+    for (offset, weight) in kernel.iter_mut().enumerate() {
+        // distance from the step to the sample, centered on the middle of the step
+        let x = (offset + 1) as f64 - start - (STEP_WIDTH / 2) as f64;
+        let sinc = if x == 0.0 {
+            1.0
+        } else {
+            (2.0 * PI * CUTOFF * x).sin() / (2.0 * PI * CUTOFF * x)
+        };
+        let angle = 2.0 * PI * x / STEP_WIDTH as f64;
+        let window = 0.42 + 0.5 * angle.cos() + 0.08 * (2.0 * angle).cos();
+        let value = sinc * window;
+        *weight = value;
+        total += value;
+    }
+    kernel.map(|weight| (weight / total) as f32)
+}
+
+#[cfg(test)]
+mod test_resampler {
+    use super::{Resampler, STEP_WIDTH};
+    use crate::T_CYCLE_FREQUENCY;
+
+    /// Return the RMS of a square wave of `frequency` Hz resampled at 48 kHz,
+    /// once the first steps are through the filter
+    fn square_rms(frequency: u32) -> f32 {
+        let mut resampler = Resampler::new(48000);
+        let half_period = T_CYCLE_FREQUENCY / frequency / 2;
+        let samples: Vec<f32> = (0..T_CYCLE_FREQUENCY / 10)
+            .filter_map(|cycle| {
+                let value = if (cycle / half_period) % 2 == 1 {
+                    -1.0
+                } else {
+                    1.0
+                };
+                resampler.push([value, value])
+            })
+            .map(|sample| sample[0])
+            .skip(2 * STEP_WIDTH)
+            .collect();
+        (samples.iter().map(|sample| sample * sample).sum::<f32>() / samples.len() as f32).sqrt()
+    }
+
+    #[test]
+    fn level() {
+        // 4 T-cycles per sample
+        let mut resampler = Resampler::new(T_CYCLE_FREQUENCY / 4);
+        let samples: Vec<_> = (0..4 * 2 * STEP_WIDTH)
+            .filter_map(|_| resampler.push([0.5, -0.5]))
+            .collect();
+        assert_eq!(samples.len(), 2 * STEP_WIDTH);
+        assert!(samples[0][0].abs() < 0.01);
+        for sample in &samples[STEP_WIDTH..] {
+            assert!((sample[0] - 0.5).abs() < 1e-4 && (sample[1] + 0.5).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn fractional_period() {
+        let mut resampler = Resampler::new(44100);
+        // one more T-cycle absorbs the rounding of the last period
+        let count = (0..=T_CYCLE_FREQUENCY)
+            .filter_map(|_| resampler.push([1.0, 1.0]))
+            .enumerate()
+            .inspect(|(index, sample)| {
+                assert!(*index < STEP_WIDTH || (sample[0] - 1.0).abs() < 1e-4)
+            })
+            .count();
+        assert_eq!(count, 44100);
+    }
+
+    #[test]
+    fn band_limited() {
+        // the square wave and its harmonics are kept below the Nyquist frequency
+        assert!(square_rms(1000) > 0.95);
+        // above the Nyquist frequency they are filtered out instead of aliasing
+        assert!(square_rms(30000) < 0.02);
+    }
+}
@@ -0,0 +1,43 @@
+use crate::SOUND_CHANNELS;
+use std::collections::VecDeque;
+
+/// Number of points kept by the scope, about 46ms of sound at 44.1kHz
+const SCOPE_SIZE: usize = 2048;
+
+/// Latest outputs of each channel followed by the mono mix, one point per output sample
+pub type ScopePoint = [f32; SOUND_CHANNELS + 1];
+
+/// History of the outputs drawn by the oscilloscope of the debugger
+#[derive(Debug, Default)]
+pub struct Scope {
+    points: VecDeque<ScopePoint>,
+}
+
+impl Scope {
+    pub fn push(&mut self, point: ScopePoint) {
+        if self.points.len() == SCOPE_SIZE {
+            self.points.pop_front();
+        }
+        self.points.push_back(point);
+    }
+
+    /// Return the points of the history, the oldest first
+    pub fn points(&self) -> impl Iterator<Item = &ScopePoint> {
+        self.points.iter()
+    }
+}
+
+#[cfg(test)]
+mod test_scope {
+    use super::{Scope, SCOPE_SIZE};
+
+    #[test]
+    fn keep_latest() {
+        let mut scope = Scope::default();
+        for i in 0..SCOPE_SIZE + 10 {
+            scope.push([i as f32; 5]);
+        }
+        assert_eq!(scope.points().count(), SCOPE_SIZE);
+        assert_eq!(scope.points().next(), Some(&[10.0; 5]));
+    }
+}
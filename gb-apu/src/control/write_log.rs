@@ -0,0 +1,170 @@
+use crate::T_CYCLE_FREQUENCY;
+use gb_bus::io_reg_constant::{NR10, WAVE_RAM_F};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// Rate of the samples counted by the wait commands of a VGM file
+const VGM_SAMPLE_RATE: u64 = 44100;
+const VGM_VERSION: u32 = 0x161;
+/// Size of the header, the commands start right after it
+const VGM_HEADER_SIZE: usize = 0x100;
+/// Offset of the clock of the Game Boy DMG in the header
+const VGM_DMG_CLOCK: usize = 0x80;
+
+/// Write to a sound register, at the cycle it happened
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegisterWrite {
+    /// T-cycles since the start of the log
+    pub cycle: u64,
+    /// Offset of the register from NR10, the wave RAM starts at `0x20`
+    pub register: u8,
+    pub value: u8,
+}
+
+/// Writes to the sound registers and the wave RAM since the start of the log.
+///
+/// The log starts with the writes restoring the state of the apu at that time,
+/// it can be exported as a VGM file played by the Game Boy DMG chip of VGM 1.61.
+#[derive(Debug, Clone, Default)]
+pub struct WriteLog {
+    /// Cycle of the apu at the start of the log
+    start: u64,
+    /// Duration of the log in T-cycles, set when it's stopped
+    end: u64,
+    writes: Vec<RegisterWrite>,
+}
+
+impl WriteLog {
+    /// Start a log at `cycle`, `initial` are the registers and values setting the current state
+    pub fn new(cycle: u64, initial: &[(u8, u8)]) -> Self {
+        Self {
+            start: cycle,
+            end: 0,
+            writes: initial
+                .iter()
+                .map(|&(register, value)| RegisterWrite {
+                    cycle: 0,
+                    register,
+                    value,
+                })
+                .collect(),
+        }
+    }
+
+    /// Log the write of `value` at `address`, the addresses outside of the apu are ignored
+    pub fn push(&mut self, cycle: u64, address: u16, value: u8) {
+        if (NR10..=WAVE_RAM_F).contains(&address) {
+            self.writes.push(RegisterWrite {
+                cycle: cycle - self.start,
+                register: (address - NR10) as u8,
+                value,
+            });
+        }
+    }
+
+    /// End the log at `cycle`
+    pub fn stop(&mut self, cycle: u64) {
+        self.end = cycle - self.start;
+    }
+
+    pub fn writes(&self) -> &[RegisterWrite] {
+        &self.writes
+    }
+
+    /// Write the log as a VGM file
+    pub fn write_vgm<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let mut commands = Vec::new();
+        let mut position = 0;
+        for write in &self.writes {
+            let sample = vgm_sample(write.cycle);
+            wait(&mut commands, sample - position);
+            position = sample;
+            commands.extend_from_slice(&[0xb3, write.register, write.value]);
+        }
+        let total_samples = vgm_sample(self.end).max(position);
+        wait(&mut commands, total_samples - position);
+        // end of the sound data
+        commands.push(0x66);
+
+        let mut header = [0_u8; VGM_HEADER_SIZE];
+        let mut set = |offset: usize, value: u32| {
+            header[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+        };
+        set(0x04, (VGM_HEADER_SIZE + commands.len() - 4) as u32);
+        set(0x08, VGM_VERSION);
+        set(0x18, total_samples as u32);
+        // the offset of the data is relative to its own field
+        set(0x34, (VGM_HEADER_SIZE - 0x34) as u32);
+        set(VGM_DMG_CLOCK, T_CYCLE_FREQUENCY);
+        header[..4].copy_from_slice(b"Vgm ");
+
+        writer.write_all(&header)?;
+        writer.write_all(&commands)?;
+        writer.flush()
+    }
+
+    /// Write the log as a VGM file at `path`
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        self.write_vgm(BufWriter::new(File::create(path)?))
+    }
+}
+
+/// Return the VGM sample at `cycle`
+fn vgm_sample(cycle: u64) -> u64 {
+    cycle * VGM_SAMPLE_RATE / u64::from(T_CYCLE_FREQUENCY)
+}
+
+/// Push the commands waiting `samples`
+fn wait(commands: &mut Vec<u8>, mut samples: u64) {
+    while samples > 0 {
+        let count = samples.min(0xffff);
+        match count {
+            1..=16 => commands.push(0x70 + count as u8 - 1),
+            // a frame at 60 and 50 Hz
+            735 => commands.push(0x62),
+            882 => commands.push(0x63),
+            _ => {
+                commands.push(0x61);
+                commands.extend_from_slice(&(count as u16).to_le_bytes());
+            }
+        }
+        samples -= count;
+    }
+}
+
+#[cfg(test)]
+mod test_write_log {
+    use super::WriteLog;
+
+    #[test]
+    fn vgm() {
+        let mut log = WriteLog::new(1000, &[(0x16, 0x80)]);
+        log.push(1000 + 0x40_0000, 0xff30, 0x12);
+        log.push(1000 + 0x40_0000 + 400, 0xff40, 0x91);
+        log.push(1000 + 0x40_0000 + 400, 0xff24, 0x77);
+        log.stop(1000 + 0x40_0000 + 400 + 0x40_0000 / 60);
+        assert_eq!(log.writes().len(), 3);
+
+        let mut vgm = Vec::new();
+        log.write_vgm(&mut vgm).unwrap();
+        assert_eq!(&vgm[..4], b"Vgm ");
+        assert_eq!(&vgm[0x04..0x08], &(vgm.len() as u32 - 4).to_le_bytes());
+        assert_eq!(&vgm[0x08..0x0c], &0x161_u32.to_le_bytes());
+        assert_eq!(&vgm[0x18..0x1c], &(44100_u32 + 4 + 735).to_le_bytes());
+        assert_eq!(&vgm[0x34..0x38], &0xcc_u32.to_le_bytes());
+        assert_eq!(&vgm[0x80..0x84], &0x40_0000_u32.to_le_bytes());
+        assert_eq!(
+            &vgm[0x100..],
+            &[
+                0xb3, 0x16, 0x80, // NR52 at the start
+                0x61, 0x44, 0xac, // wait a second
+                0xb3, 0x20, 0x12, // wave RAM
+                0x73, // wait 4 samples
+                0xb3, 0x14, 0x77, // NR50
+                0x62, // wait a frame
+                0x66
+            ]
+        );
+    }
+}
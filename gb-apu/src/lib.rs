@@ -12,6 +12,10 @@ pub enum ChannelType {
 }
 
 pub const SAMPLE_RATES: [SampleRate; 2] = [SampleRate(44100), SampleRate(48000)];
+/// Samples of a frame of the audio buffer, the left sample first
+pub const OUTPUT_CHANNELS: usize = 2;
+/// Number of sound channels, in the order of their registers
+pub const SOUND_CHANNELS: usize = 4;
 
 pub const MASK_UNUSED_BITS_FF: u8 = 0xFF;
 pub const MASK_UNUSED_BITS_3F: u8 = 0x3F;
@@ -0,0 +1,37 @@
+use std::fmt::{self, Display};
+
+use crate::native::identifier;
+use nom::{combinator::map_opt, IResult};
+
+/// Bank currently mapped by the cartridge controller
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Bank {
+    /// ROM bank mapped at `4000-7FFF`, `BANK`
+    Rom,
+    /// External RAM bank mapped at `A000-BFFF`, `RAMBANK`
+    Ram,
+}
+
+impl Display for Bank {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Bank::Rom => write!(f, "BANK"),
+            Bank::Ram => write!(f, "RAMBANK"),
+        }
+    }
+}
+
+/// Parse a [Bank] keyword
+///
+/// ```
+/// # use gb_breakpoint::bank::{bank, Bank};
+/// assert_eq!(bank("BANK"), Ok(("", Bank::Rom)));
+/// assert_eq!(bank("RAMBANK"), Ok(("", Bank::Ram)));
+/// ```
+pub fn bank(input: &str) -> IResult<&str, Bank> {
+    map_opt(identifier, |name| match name {
+        "BANK" | "ROMBANK" => Some(Bank::Rom),
+        "RAMBANK" => Some(Bank::Ram),
+        _ => None,
+    })(input)
+}
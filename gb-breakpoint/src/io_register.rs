@@ -0,0 +1,113 @@
+use std::fmt::{self, Display};
+
+use crate::native::identifier;
+use nom::{combinator::map_opt, IResult};
+
+/// Name and address of the IO registers that can be used in an expression
+const IO_REGISTERS: [(&str, u16); 55] = [
+    ("P1", 0xff00),
+    ("SB", 0xff01),
+    ("SC", 0xff02),
+    ("DIV", 0xff04),
+    ("TIMA", 0xff05),
+    ("TMA", 0xff06),
+    ("TAC", 0xff07),
+    ("IF", 0xff0f),
+    ("NR10", 0xff10),
+    ("NR11", 0xff11),
+    ("NR12", 0xff12),
+    ("NR13", 0xff13),
+    ("NR14", 0xff14),
+    ("NR21", 0xff16),
+    ("NR22", 0xff17),
+    ("NR23", 0xff18),
+    ("NR24", 0xff19),
+    ("NR30", 0xff1a),
+    ("NR31", 0xff1b),
+    ("NR32", 0xff1c),
+    ("NR33", 0xff1d),
+    ("NR34", 0xff1e),
+    ("NR41", 0xff20),
+    ("NR42", 0xff21),
+    ("NR43", 0xff22),
+    ("NR44", 0xff23),
+    ("NR50", 0xff24),
+    ("NR51", 0xff25),
+    ("NR52", 0xff26),
+    ("LCDC", 0xff40),
+    ("STAT", 0xff41),
+    ("SCY", 0xff42),
+    ("SCX", 0xff43),
+    ("LY", 0xff44),
+    ("LYC", 0xff45),
+    ("DMA", 0xff46),
+    ("BGP", 0xff47),
+    ("OBP0", 0xff48),
+    ("OBP1", 0xff49),
+    ("WY", 0xff4a),
+    ("WX", 0xff4b),
+    ("KEY1", 0xff4d),
+    ("VBK", 0xff4f),
+    ("HDMA1", 0xff51),
+    ("HDMA2", 0xff52),
+    ("HDMA3", 0xff53),
+    ("HDMA4", 0xff54),
+    ("HDMA5", 0xff55),
+    ("RP", 0xff56),
+    ("BCPS", 0xff68),
+    ("BCPD", 0xff69),
+    ("OCPS", 0xff6a),
+    ("OCPD", 0xff6b),
+    ("SVBK", 0xff70),
+    ("IE", 0xffff),
+];
+
+/// A named IO register, evaluated by reading its address on the bus
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct IoRegister {
+    name: &'static str,
+    address: u16,
+}
+
+impl IoRegister {
+    /// Find the IO register with the given name
+    pub fn from_name(name: &str) -> Option<Self> {
+        IO_REGISTERS
+            .iter()
+            .find(|(reg_name, _)| *reg_name == name)
+            .map(|&(name, address)| Self { name, address })
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn address(&self) -> u16 {
+        self.address
+    }
+}
+
+impl Display for IoRegister {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+/// Parse an [IoRegister] from its name
+///
+/// ```
+/// # use gb_breakpoint::io_register::io_register;
+/// assert_eq!(io_register("LY").map(|(_, reg)| reg.address()), Ok(0xff44));
+/// assert!(io_register("LYX").is_err());
+/// ```
+pub fn io_register(input: &str) -> IResult<&str, IoRegister> {
+    map_opt(identifier, IoRegister::from_name)(input)
+}
+
+#[test]
+fn test_io_register() {
+    assert_eq!(io_register("LCDC").unwrap().1.address(), 0xff40);
+    assert_eq!(io_register("IE").unwrap().1.address(), 0xffff);
+    assert_eq!(io_register("NR52").unwrap().1.address(), 0xff26);
+    assert!(io_register("lcdc").is_err());
+}
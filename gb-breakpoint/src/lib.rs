@@ -1,3 +1,5 @@
+pub mod bank;
+pub mod io_register;
 pub mod native;
 pub mod operation;
 pub mod parser;
@@ -12,6 +14,8 @@ use std::{
     str::FromStr,
 };
 
+use bank::Bank;
+use io_register::IoRegister;
 use operation::BinaryExpr;
 pub use operation::Operator;
 use register::Register;
@@ -28,7 +32,15 @@ macro_rules! boxed {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Node {
     Register(Register),
+    IoRegister(IoRegister),
+    Bank(Bank),
+    /// Number of times the breakpoint was reached
+    Hits,
+    /// Address of a label loaded from a symbol file
+    Label(String),
     Address(Box<Node>),
+    /// 16-bit little endian read, `*w addr`
+    WordAddress(Box<Node>),
     Raw(u16),
     UnaryExpr(UnaryExpr),
     BinaryExpr(BinaryExpr),
@@ -38,7 +50,19 @@ impl Display for Node {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Node::Register(r) => write!(f, "{}", r),
-            Node::Address(addr) => write!(f, "*{}", addr),
+            Node::IoRegister(r) => write!(f, "{}", r),
+            Node::Bank(b) => write!(f, "{}", b),
+            Node::Hits => write!(f, "hits"),
+            Node::Label(name) => {
+                // the names read as another value keep the explicit prefix
+                if parser::value(name) == Ok(("", self.clone())) {
+                    write!(f, "{}", name)
+                } else {
+                    write!(f, "@{}", name)
+                }
+            }
+            Node::Address(addr) => write!(f, "*{}", Deref(addr)),
+            Node::WordAddress(addr) => write!(f, "*w {}", Deref(addr)),
             Node::Raw(v) => write!(f, "{:#X}", v),
             Node::UnaryExpr(expr) => write!(f, "{}", expr),
             Node::BinaryExpr(expr) => write!(f, "{}", expr),
@@ -46,6 +70,18 @@ impl Display for Node {
     }
 }
 
+/// Display the operand of an address, adding parenthesis around a binary expression
+struct Deref<'a>(&'a Node);
+
+impl Display for Deref<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            Node::BinaryExpr(expr) => write!(f, "({})", expr),
+            node => write!(f, "{}", node),
+        }
+    }
+}
+
 impl Node {
     pub fn simple(address: u16) -> Self {
         Self::BinaryExpr(BinaryExpr {
@@ -54,6 +90,45 @@ impl Node {
             rhs: boxed!(Self::Raw(address)),
         })
     }
+
+    /// Create the expression `PC == address && BANK == bank`
+    pub fn banked(bank: u16, address: u16) -> Self {
+        Self::BinaryExpr(BinaryExpr {
+            op: Operator::LogicAnd,
+            lhs: boxed!(Self::simple(address)),
+            rhs: boxed!(Self::BinaryExpr(BinaryExpr {
+                op: Operator::Eq,
+                lhs: boxed!(Self::Bank(Bank::Rom)),
+                rhs: boxed!(Self::Raw(bank)),
+            })),
+        })
+    }
+
+    /// Return the labels used by the expression
+    pub fn labels(&self) -> Vec<&str> {
+        match self {
+            Node::Label(name) => vec![name.as_str()],
+            Node::Address(child) | Node::WordAddress(child) => child.labels(),
+            Node::UnaryExpr(expr) => expr.child.labels(),
+            Node::BinaryExpr(expr) => {
+                let mut labels = expr.lhs.labels();
+                labels.extend(expr.rhs.labels());
+                labels
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Indicate if the expression use the `hits` counter
+    pub fn uses_hits(&self) -> bool {
+        match self {
+            Node::Hits => true,
+            Node::Address(child) | Node::WordAddress(child) => child.uses_hits(),
+            Node::UnaryExpr(expr) => expr.child.uses_hits(),
+            Node::BinaryExpr(expr) => expr.lhs.uses_hits() || expr.rhs.uses_hits(),
+            _ => false,
+        }
+    }
 }
 
 impl FromStr for Node {
@@ -62,7 +137,7 @@ impl FromStr for Node {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         use nom::combinator::all_consuming;
 
-        let (_rest, ast) = match all_consuming(parser::expr)(s) {
+        let (_rest, ast) = match all_consuming(parser::expr)(s.trim()) {
             Ok(ret) => ret,
             Err(e) => {
                 return Err(format!("Invalid input: {}", e));
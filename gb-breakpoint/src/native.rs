@@ -5,7 +5,7 @@ use nom::{combinator::map, IResult};
 /// # Definition
 ///
 /// ```txt
-/// raw_value = [A-Fa-f0-9]{1,4}
+/// raw_value = ('0x')? [A-Fa-f0-9]{1,4}
 /// ```
 ///
 /// # Examples
@@ -13,13 +13,22 @@ use nom::{combinator::map, IResult};
 /// ```
 /// # use gb_breakpoint::native::value;
 /// assert_eq!(value("42"), Ok(("", 0x42)));
+/// assert_eq!(value("0x42"), Ok(("", 0x42)));
 /// ```
 pub fn value(input: &str) -> IResult<&str, u16> {
-    use nom::bytes::complete::take_while_m_n;
+    use nom::{
+        bytes::complete::{tag_no_case, take_while_m_n},
+        combinator::opt,
+        sequence::preceded,
+    };
 
-    map(take_while_m_n(1, 4, |c: char| c.is_ascii_hexdigit()), |s| {
-        u16::from_str_radix(s, 16).unwrap()
-    })(input)
+    map(
+        preceded(
+            opt(tag_no_case("0x")),
+            take_while_m_n(1, 4, |c: char| c.is_ascii_hexdigit()),
+        ),
+        |s| u16::from_str_radix(s, 16).unwrap(),
+    )(input)
 }
 
 #[test]
@@ -28,4 +37,44 @@ fn test_raw_value() {
     assert_eq!(value("1f"), Ok(("", 0x1f_u16)));
     assert_eq!(value("b1f"), Ok(("", 0xb1f_u16)));
     assert_eq!(value("ab1f"), Ok(("", 0xab1f_u16)));
+    assert_eq!(value("0xFF44"), Ok(("", 0xff44_u16)));
+    assert_eq!(value("0X0"), Ok(("", 0_u16)));
+}
+
+/// Return the value of a whole word written in hexadecimal, like `dead`
+///
+/// # Examples
+///
+/// ```
+/// # use gb_breakpoint::native::hex_word;
+/// assert_eq!(hex_word("Face"), Some(0xface));
+/// assert_eq!(hex_word("wFace"), None);
+/// assert_eq!(hex_word("deadbeef"), None);
+/// ```
+pub fn hex_word(word: &str) -> Option<u16> {
+    if (1..=4).contains(&word.len()) && word.chars().all(|c| c.is_ascii_hexdigit()) {
+        u16::from_str_radix(word, 16).ok()
+    } else {
+        None
+    }
+}
+
+/// Parse a whole word used to name a register or a keyword
+///
+/// # Definition
+///
+/// ```txt
+/// identifier = [A-Za-z0-9_.]+
+/// ```
+///
+/// # Examples
+///
+/// ```
+/// # use gb_breakpoint::native::identifier;
+/// assert_eq!(identifier("LCDC == 91"), Ok((" == 91", "LCDC")));
+/// ```
+pub fn identifier(input: &str) -> IResult<&str, &str> {
+    use nom::bytes::complete::take_while1;
+
+    take_while1(|c: char| c.is_ascii_alphanumeric() || c == '_' || c == '.')(input)
 }
@@ -5,7 +5,13 @@ use crate::{
     parser::{any_value, ws},
     Node,
 };
-use nom::{branch::alt, bytes::complete::tag, combinator::map, sequence::tuple, IResult};
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    combinator::{map, not},
+    sequence::{pair, terminated},
+    IResult,
+};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct BinaryExpr {
@@ -16,7 +22,17 @@ pub struct BinaryExpr {
 
 impl Display for BinaryExpr {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} {} {}", self.lhs, self.op, self.rhs)
+        let precedence = self.op.precedence();
+
+        match self.lhs.as_ref() {
+            Node::BinaryExpr(lhs) if lhs.op.precedence() < precedence => write!(f, "({})", lhs)?,
+            lhs => write!(f, "{}", lhs)?,
+        }
+        write!(f, " {} ", self.op)?;
+        match self.rhs.as_ref() {
+            Node::BinaryExpr(rhs) if rhs.op.precedence() <= precedence => write!(f, "({})", rhs),
+            rhs => write!(f, "{}", rhs),
+        }
     }
 }
 
@@ -46,6 +62,37 @@ pub enum Operator {
     SupEq,
     /// Check if a value is lower or equal to another, `<=`
     InfEq,
+    /// Wrapping addition, `+`
+    Add,
+    /// Wrapping subtraction, `-`
+    Sub,
+    /// Shift a value to the left, `<<`
+    ShiftLeft,
+    /// Shift a value to the right, `>>`
+    ShiftRight,
+}
+
+impl Operator {
+    /// Binding power of the operator, the higher it is the tighter the operator bind.
+    /// The order follow the one of Rust, so `A & F == 3` is `(A & F) == 3`.
+    pub fn precedence(&self) -> u8 {
+        match self {
+            Operator::Add | Operator::Sub => 8,
+            Operator::ShiftLeft | Operator::ShiftRight => 7,
+            Operator::BinaryAnd => 6,
+            Operator::BinaryXor => 5,
+            Operator::BinaryOr => 4,
+            Operator::Eq
+            | Operator::NotEq
+            | Operator::Sup
+            | Operator::Inf
+            | Operator::SupEq
+            | Operator::InfEq => 3,
+            Operator::LogicAnd => 2,
+            Operator::LogicXor => 1,
+            Operator::LogicOr => 0,
+        }
+    }
 }
 
 impl Display for Operator {
@@ -65,11 +112,16 @@ impl Display for Operator {
             Operator::LogicAnd => write!(f, "&&"),
             Operator::LogicXor => write!(f, "^^"),
             Operator::LogicOr => write!(f, "||"),
+
+            Operator::Add => write!(f, "+"),
+            Operator::Sub => write!(f, "-"),
+            Operator::ShiftLeft => write!(f, "<<"),
+            Operator::ShiftRight => write!(f, ">>"),
         }
     }
 }
 
-/// Parse a [Operator] token
+/// Parse a comparison [Operator] token
 ///
 /// # Definition
 ///
@@ -80,9 +132,6 @@ impl Display for Operator {
 ///        |= '<'
 ///        |= '>='
 ///        |= '<='
-///        |= '&'
-///        |= '|'
-///        |= '^'
 /// ```
 ///
 /// # Examples
@@ -97,12 +146,9 @@ pub fn bin_op(input: &str) -> IResult<&str, Operator> {
         map(tag("!="), |_| Operator::NotEq),
         map(tag("<="), |_| Operator::InfEq),
         map(tag(">="), |_| Operator::SupEq),
-        map(tag(">"), |_| Operator::Sup),
-        map(tag("<"), |_| Operator::Inf),
-        map(tag("|"), |_| Operator::BinaryOr),
-        map(tag("&"), |_| Operator::BinaryAnd),
-        map(tag("^"), |_| Operator::BinaryXor),
-    ))(input.trim())
+        map(terminated(tag(">"), not(tag(">"))), |_| Operator::Sup),
+        map(terminated(tag("<"), not(tag("<"))), |_| Operator::Inf),
+    ))(input)
 }
 
 #[test]
@@ -113,9 +159,74 @@ fn test_bin_op() {
     assert_eq!(bin_op("<="), Ok(("", Operator::InfEq)));
     assert_eq!(bin_op("<"), Ok(("", Operator::Inf)));
     assert_eq!(bin_op(">"), Ok(("", Operator::Sup)));
-    assert_eq!(bin_op("|"), Ok(("", Operator::BinaryOr)));
-    assert_eq!(bin_op("&"), Ok(("", Operator::BinaryAnd)));
-    assert_eq!(bin_op("^"), Ok(("", Operator::BinaryXor)));
+    assert!(bin_op("<<").is_err());
+}
+
+/// Parse a bitwise [Operator] token
+///
+/// # Definition
+///
+/// ```txt
+/// bitwise_op |= '&'
+///            |= '|'
+///            |= '^'
+/// ```
+///
+/// # Examples
+///
+/// ```
+/// # use gb_breakpoint::operation::bitwise_op;
+/// assert!(bitwise_op("&").is_ok());
+/// assert!(bitwise_op("&&").is_err());
+/// ```
+pub fn bitwise_op(input: &str) -> IResult<&str, Operator> {
+    alt((
+        map(terminated(tag("&"), not(tag("&"))), |_| Operator::BinaryAnd),
+        map(terminated(tag("^"), not(tag("^"))), |_| Operator::BinaryXor),
+        map(terminated(tag("|"), not(tag("|"))), |_| Operator::BinaryOr),
+    ))(input)
+}
+
+#[test]
+fn test_bitwise_op() {
+    assert_eq!(bitwise_op("|"), Ok(("", Operator::BinaryOr)));
+    assert_eq!(bitwise_op("&"), Ok(("", Operator::BinaryAnd)));
+    assert_eq!(bitwise_op("^"), Ok(("", Operator::BinaryXor)));
+    assert!(bitwise_op("||").is_err());
+}
+
+/// Parse an arithmetic [Operator] token
+///
+/// # Definition
+///
+/// ```txt
+/// arith_op |= '+'
+///          |= '-'
+///          |= '<<'
+///          |= '>>'
+/// ```
+///
+/// # Examples
+///
+/// ```
+/// # use gb_breakpoint::operation::arith_op;
+/// assert!(arith_op("<<").is_ok());
+/// ```
+pub fn arith_op(input: &str) -> IResult<&str, Operator> {
+    alt((
+        map(tag("+"), |_| Operator::Add),
+        map(tag("-"), |_| Operator::Sub),
+        map(tag("<<"), |_| Operator::ShiftLeft),
+        map(tag(">>"), |_| Operator::ShiftRight),
+    ))(input)
+}
+
+#[test]
+fn test_arith_op() {
+    assert_eq!(arith_op("+"), Ok(("", Operator::Add)));
+    assert_eq!(arith_op("-"), Ok(("", Operator::Sub)));
+    assert_eq!(arith_op("<<"), Ok(("", Operator::ShiftLeft)));
+    assert_eq!(arith_op(">>"), Ok(("", Operator::ShiftRight)));
 }
 
 /// Parse a [Operator] token
@@ -149,12 +260,97 @@ fn test_comb_op() {
     assert_eq!(comb_op("^^"), Ok(("", Operator::LogicXor)));
 }
 
-/// Parse an expression to generate an [Ast]
+/// Parse a chain of operand separated by operators of the same precedence.
+/// The chain is left associative, so `A - 1 - 2` is `(A - 1) - 2`.
+fn chain<'a>(
+    input: &'a str,
+    operand: fn(&'a str) -> IResult<&'a str, Node>,
+    operator: fn(&'a str) -> IResult<&'a str, Operator>,
+    precedence: u8,
+) -> IResult<&'a str, Node> {
+    let (mut input, mut lhs) = operand(input)?;
+
+    while let Ok((rest, (op, rhs))) = pair(ws(operator), operand)(input) {
+        if op.precedence() != precedence {
+            break;
+        }
+        lhs = Node::BinaryExpr(BinaryExpr {
+            op,
+            lhs: boxed!(lhs),
+            rhs: boxed!(rhs),
+        });
+        input = rest;
+    }
+    Ok((input, lhs))
+}
+
+/// Parse an arithmetic expression
 ///
 /// # Definition
 ///
 /// ```txt
-/// operation = any_value bin_op any_value
+/// arith_expr = shift_expr
+/// shift_expr = sum_expr (('<<'|'>>') sum_expr)*
+/// sum_expr   = any_value (('+'|'-') any_value)*
+/// ```
+///
+/// # Examples
+///
+/// ```
+/// # use gb_breakpoint::operation::arith_expr;
+/// assert!(arith_expr("HL + 2").is_ok());
+/// ```
+pub fn arith_expr(input: &str) -> IResult<&str, Node> {
+    fn sum_expr(input: &str) -> IResult<&str, Node> {
+        chain(input, any_value, arith_op, Operator::Add.precedence())
+    }
+
+    chain(input, sum_expr, arith_op, Operator::ShiftLeft.precedence())
+}
+
+/// Parse a bitwise expression
+///
+/// # Definition
+///
+/// ```txt
+/// bitwise_expr = xor_expr ('|' xor_expr)*
+/// xor_expr     = and_expr ('^' and_expr)*
+/// and_expr     = arith_expr ('&' arith_expr)*
+/// ```
+///
+/// # Examples
+///
+/// ```
+/// # use gb_breakpoint::operation::bitwise_expr;
+/// assert!(bitwise_expr("STAT & 3").is_ok());
+/// ```
+pub fn bitwise_expr(input: &str) -> IResult<&str, Node> {
+    fn and_expr(input: &str) -> IResult<&str, Node> {
+        chain(
+            input,
+            arith_expr,
+            bitwise_op,
+            Operator::BinaryAnd.precedence(),
+        )
+    }
+    fn xor_expr(input: &str) -> IResult<&str, Node> {
+        chain(
+            input,
+            and_expr,
+            bitwise_op,
+            Operator::BinaryXor.precedence(),
+        )
+    }
+
+    chain(input, xor_expr, bitwise_op, Operator::BinaryOr.precedence())
+}
+
+/// Parse a comparison expression
+///
+/// # Definition
+///
+/// ```txt
+/// operation = bitwise_expr (bin_op bitwise_expr)?
 /// ```
 ///
 /// # Examples
@@ -163,15 +359,8 @@ fn test_comb_op() {
 /// # use gb_breakpoint::operation::bin_expr;
 /// assert!(bin_expr("BC == DE").is_ok());
 /// ```
-pub fn bin_expr(input: &str) -> IResult<&str, BinaryExpr> {
-    map(
-        tuple((any_value, ws(bin_op), any_value)),
-        |(lhs, op, rhs)| BinaryExpr {
-            op,
-            lhs: boxed!(lhs),
-            rhs: boxed!(rhs),
-        },
-    )(input)
+pub fn bin_expr(input: &str) -> IResult<&str, Node> {
+    chain(input, bitwise_expr, bin_op, Operator::Eq.precedence())
 }
 
 #[cfg(test)]
@@ -194,18 +383,34 @@ mod unit_operation {
         utils_test_expr!(bin_expr, "HL != *ff0f", "HL != *0xFF0F");
         utils_test_expr!(bin_expr, "HL < DE", "HL < DE");
     }
+
+    #[test]
+    fn precedence() {
+        utils_test_expr!(bin_expr, "A & 0F == 3", "A & 0xF == 0x3");
+        utils_test_expr!(bin_expr, "HL - 1 - 2", "HL - 0x1 - 0x2");
+        utils_test_expr!(bin_expr, "HL - (1 - 2)", "HL - (0x1 - 0x2)");
+        utils_test_expr!(bin_expr, "(A | 1) & 3", "(A | 0x1) & 0x3");
+        utils_test_expr!(bin_expr, "A << 1 + 1", "A << 0x1 + 0x1");
+        utils_test_expr!(bin_expr, "(A << 1) + 1", "(A << 0x1) + 0x1");
+    }
 }
 
-pub fn comb_expr(input: &str) -> IResult<&str, BinaryExpr> {
-    use crate::parser::expr;
-    use crate::wrapper::wrap_bin_expr;
+/// Parse a logical expression
+///
+/// # Definition
+///
+/// ```txt
+/// comb_expr = xor_expr ('||' xor_expr)*
+/// xor_expr  = and_expr ('^^' and_expr)*
+/// and_expr  = operation ('&&' operation)*
+/// ```
+pub fn comb_expr(input: &str) -> IResult<&str, Node> {
+    fn and_expr(input: &str) -> IResult<&str, Node> {
+        chain(input, bin_expr, comb_op, Operator::LogicAnd.precedence())
+    }
+    fn xor_expr(input: &str) -> IResult<&str, Node> {
+        chain(input, and_expr, comb_op, Operator::LogicXor.precedence())
+    }
 
-    map(
-        tuple((wrap_bin_expr, ws(comb_op), expr)),
-        |(lhs, op, rhs)| BinaryExpr {
-            op,
-            lhs: boxed!(lhs),
-            rhs: boxed!(rhs),
-        },
-    )(input)
+    chain(input, xor_expr, comb_op, Operator::LogicOr.precedence())
 }
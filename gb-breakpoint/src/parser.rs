@@ -1,13 +1,22 @@
-use crate::{boxed, Node};
-use nom::{branch::alt, bytes::complete::tag, combinator::map, sequence::delimited, IResult};
+use crate::{boxed, native::identifier, Node};
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::space1,
+    combinator::{map, map_opt, opt, peek},
+    sequence::{delimited, preceded, terminated},
+    IResult,
+};
 
 /// Parse a breakpoint expression to generate an [Ast]
 ///
+/// Operators follow the precedence of Rust:
+/// `+ -`, `<< >>`, `&`, `^`, `|`, comparisons, `&&`, `^^` then `||`.
+///
 /// # Definition
 ///
 /// ```txt
-/// expr |= operation comb_op expr
-///      |= operation
+/// expr = comb_expr
 /// ```
 ///
 /// # Examples
@@ -16,11 +25,10 @@ use nom::{branch::alt, bytes::complete::tag, combinator::map, sequence::delimite
 /// # use gb_breakpoint::parser::expr;
 /// assert!(expr("AF == DEAD").is_ok());
 /// assert!(expr("PC == 42 && *FF0F == 5").is_ok());
+/// assert!(expr("PC == 4000 && BANK == 3 && LY > 90").is_ok());
 /// ```
 pub fn expr(input: &str) -> IResult<&str, Node> {
-    use crate::wrapper::{wrap_bin_expr, wrap_comb_expr};
-
-    alt((wrap_comb_expr, wrap_bin_expr))(input)
+    crate::operation::comb_expr(input)
 }
 
 /// Skip surrounding whitespaces
@@ -31,9 +39,9 @@ where
     E: nom::error::ParseError<I>,
     P: nom::Parser<I, O, E>,
 {
-    use nom::character::complete::space0;
+    use nom::character::complete::multispace0;
 
-    delimited(space0, parser, space0)
+    delimited(multispace0, parser, multispace0)
 }
 
 /// Parser a value that can be wrapped in a [Ast::UnaryExpr]
@@ -41,7 +49,7 @@ where
 /// # Definition
 ///
 /// ```txt
-/// any_value = unary | value
+/// any_value = unary | '(' expr ')' | value
 /// ```
 ///
 /// # Examples
@@ -50,44 +58,123 @@ where
 /// # use gb_breakpoint::parser::any_value;
 /// assert!(any_value("42").is_ok());
 /// assert!(any_value("U(AF)").is_ok());
+/// assert!(any_value("(A + 1)").is_ok());
 /// ```
 pub fn any_value(input: &str) -> IResult<&str, Node> {
     use crate::wrapper::wrap_unary;
 
-    alt((wrap_unary, value))(input)
+    alt((wrap_unary, parenthesis, value))(input)
+}
+
+/// Parse an expression surrounded by parenthesis
+fn parenthesis(input: &str) -> IResult<&str, Node> {
+    delimited(ws(tag("(")), expr, ws(tag(")")))(input)
 }
 
 /// Parse a value
 ///
+/// Register names take precedence over hexadecimal values,
+/// so the value `C` need to be written as `0C` or `0xC`.
+/// A word that is a valid hexadecimal value is a value, like `dead`,
+/// the other words starting with a letter are labels, see [label].
+///
 /// # Definition
 ///
 /// ```txt
 /// value |= register
+///       |= io_register
+///       |= bank
+///       |= 'hits'
+///       |= label
 ///       |= u16
 ///       |= address
 /// ```
 pub fn value(input: &str) -> IResult<&str, Node> {
-    use crate::wrapper::{wrap_register, wrap_value};
+    use crate::wrapper::{wrap_bank, wrap_io_register, wrap_register, wrap_value};
+
+    alt((
+        wrap_register,
+        wrap_io_register,
+        wrap_bank,
+        hits,
+        label,
+        wrap_value,
+        address,
+    ))(input)
+}
 
-    alt((wrap_register, wrap_value, address))(input)
+/// Parse the [Ast::Hits] keyword
+fn hits(input: &str) -> IResult<&str, Node> {
+    map_opt(identifier, |name| {
+        if name == "hits" {
+            Some(Node::Hits)
+        } else {
+            None
+        }
+    })(input)
 }
 
-/// Parse an [Ast::Address]
+/// Parse an [Ast::Label], a label start with a letter, a `_` or a `.`
+///
+/// A word that is a valid hexadecimal value is not a label,
+/// such a label is written with the `@` prefix, like `@Face`.
 ///
 /// # Definition
 ///
 /// ```txt
-/// address = '*' any_value
+/// label = '@' identifier | identifier
+/// ```
+///
+/// # Examples
+///
 /// ```
+/// # use gb_breakpoint::{parser::label, Node};
+/// assert_eq!(label("wPlayer.hp"), Ok(("", Node::Label("wPlayer.hp".to_owned()))));
+/// assert_eq!(label("@Face"), Ok(("", Node::Label("Face".to_owned()))));
+/// assert!(label("Face").is_err());
+/// assert!(label("0x42").is_err());
+/// ```
+pub fn label(input: &str) -> IResult<&str, Node> {
+    use crate::native::hex_word;
+
+    alt((
+        map(preceded(tag("@"), identifier), |name| {
+            Node::Label(name.to_owned())
+        }),
+        map_opt(identifier, |name| {
+            let is_label_start =
+                name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_' || c == '.');
+
+            (is_label_start && hex_word(name).is_none()).then(|| Node::Label(name.to_owned()))
+        }),
+    ))(input)
+}
+
+/// Parse an [Ast::Address] or an [Ast::WordAddress]
+///
+/// # Definition
+///
+/// ```txt
+/// address = '*' ('w' (' ' | '('))? any_value
+/// ```
+///
+/// The `w` marker need to be separated from the value, so `*wPlayerHP` read the label `wPlayerHP`.
 ///
 /// # Examples
 ///
 /// ```
-/// # use gb_breakpoint::parser::address;
+/// # use gb_breakpoint::{parser::address, Node};
 /// assert!(address("*42").is_ok());
+/// assert!(address("*w C0A2").is_ok());
+/// assert!(matches!(address("*wHP"), Ok(("", Node::Address(_)))));
 /// ```
 pub fn address(input: &str) -> IResult<&str, Node> {
     let (input, _) = tag("*")(input)?;
+    let (input, word) = opt(terminated(tag("w"), alt((space1, peek(tag("("))))))(input)?;
 
-    map(map(any_value, |v| boxed!(v)), Node::Address)(input)
+    if word.is_some() {
+        map(map(any_value, |v| boxed!(v)), Node::WordAddress)(input)
+    } else {
+        map(map(any_value, |v| boxed!(v)), Node::Address)(input)
+    }
 }
@@ -1,6 +1,7 @@
 use std::fmt::Display;
 
-use nom::{branch::alt, bytes::complete::tag, combinator::map, IResult};
+use crate::native::identifier;
+use nom::{combinator::map_opt, IResult};
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum Register {
@@ -10,6 +11,24 @@ pub enum Register {
     HL,
     SP,
     PC,
+
+    A,
+    F,
+    B,
+    C,
+    D,
+    E,
+    H,
+    L,
+
+    /// Zero flag
+    ZF,
+    /// Subtraction flag
+    NF,
+    /// Half carry flag
+    HF,
+    /// Carry flag
+    CF,
 }
 
 impl Display for Register {
@@ -21,26 +40,58 @@ impl Display for Register {
             Register::HL => write!(f, "HL"),
             Register::SP => write!(f, "SP"),
             Register::PC => write!(f, "PC"),
+            Register::A => write!(f, "A"),
+            Register::F => write!(f, "F"),
+            Register::B => write!(f, "B"),
+            Register::C => write!(f, "C"),
+            Register::D => write!(f, "D"),
+            Register::E => write!(f, "E"),
+            Register::H => write!(f, "H"),
+            Register::L => write!(f, "L"),
+            Register::ZF => write!(f, "ZF"),
+            Register::NF => write!(f, "NF"),
+            Register::HF => write!(f, "HF"),
+            Register::CF => write!(f, "CF"),
         }
     }
 }
 
 /// function that parser an input into a [Register]
 ///
+/// The register name need to be a whole word, so `DEAD` is not parsed as `DE`.
+/// The flags are named `Z`, `N`, `HF` and `CF`, or `ZF` and `NF`,
+/// `H` and `C` being the 8-bit registers.
+///
 /// ```
 /// # use gb_breakpoint::register::{Register, register};
 /// assert_eq!(register("AF"), Ok(("", Register::AF)));
+/// assert_eq!(register("Z"), Ok(("", Register::ZF)));
+/// assert_eq!(register("C"), Ok(("", Register::C)));
+/// assert!(register("DEAD").is_err());
 /// assert!(register("foo").is_err());
 /// ```
 pub fn register(input: &str) -> IResult<&str, Register> {
-    alt((
-        map(tag("AF"), |_| Register::AF),
-        map(tag("BC"), |_| Register::BC),
-        map(tag("DE"), |_| Register::DE),
-        map(tag("HL"), |_| Register::HL),
-        map(tag("SP"), |_| Register::SP),
-        map(tag("PC"), |_| Register::PC),
-    ))(input)
+    map_opt(identifier, |name| match name {
+        "AF" => Some(Register::AF),
+        "BC" => Some(Register::BC),
+        "DE" => Some(Register::DE),
+        "HL" => Some(Register::HL),
+        "SP" => Some(Register::SP),
+        "PC" => Some(Register::PC),
+        "A" => Some(Register::A),
+        "F" => Some(Register::F),
+        "B" => Some(Register::B),
+        "C" => Some(Register::C),
+        "D" => Some(Register::D),
+        "E" => Some(Register::E),
+        "H" => Some(Register::H),
+        "L" => Some(Register::L),
+        "Z" | "ZF" => Some(Register::ZF),
+        "N" | "NF" => Some(Register::NF),
+        "HF" => Some(Register::HF),
+        "CF" => Some(Register::CF),
+        _ => None,
+    })(input)
 }
 
 #[test]
@@ -52,3 +103,14 @@ fn test_raw_register() {
     assert_eq!(register("PC"), Ok(("", Register::PC)));
     assert_eq!(register("SP"), Ok(("", Register::SP)));
 }
+
+#[test]
+fn test_8bits_register() {
+    assert_eq!(register("A"), Ok(("", Register::A)));
+    assert_eq!(register("F"), Ok(("", Register::F)));
+    assert_eq!(register("L==5"), Ok(("==5", Register::L)));
+    assert_eq!(register("CF"), Ok(("", Register::CF)));
+    assert_eq!(register("Z"), Ok(("", Register::ZF)));
+    assert_eq!(register("N"), Ok(("", Register::NF)));
+    assert!(register("AFF").is_err());
+}
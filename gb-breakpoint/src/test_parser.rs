@@ -8,6 +8,10 @@ fn test_address() {
         address("*dead"),
         Ok(("", Node::Address(boxed!(Node::Raw(0xdead)))))
     );
+    assert_eq!(
+        address("*@dead"),
+        Ok(("", Node::Address(boxed!(Node::Label("dead".to_owned())))))
+    );
 }
 
 #[cfg(test)]
@@ -48,3 +52,60 @@ mod unit_expr {
         utils_test_expr!(expr, "*4088 == e3", "*0x4088 == 0xE3");
     }
 }
+
+#[cfg(test)]
+mod unit_extended_expr {
+    use crate::parser::expr;
+
+    #[test]
+    fn registers() {
+        utils_test_expr!(expr, "A == 5 && ZF", "A == 0x5 && ZF");
+        utils_test_expr!(expr, "LY>90", "LY > 0x90");
+        utils_test_expr!(expr, "STAT & 3 == 1", "STAT & 0x3 == 0x1");
+        utils_test_expr!(expr, "0xDEAD == AF", "0xDEAD == AF");
+    }
+
+    #[test]
+    fn bank_and_hits() {
+        utils_test_expr!(
+            expr,
+            "PC == 4000 && BANK == 3 && LY > 90",
+            "PC == 0x4000 && BANK == 0x3 && LY > 0x90"
+        );
+        utils_test_expr!(expr, "PC==150&&hits>=3", "PC == 0x150 && hits >= 0x3");
+    }
+
+    #[test]
+    fn address() {
+        utils_test_expr!(expr, "*w 0xC0A2 == 1234", "*w 0xC0A2 == 0x1234");
+        utils_test_expr!(expr, "*(HL + 1) != 0", "*(HL + 0x1) != 0x0");
+        utils_test_expr!(expr, "U(*w 0xff80) == 1", "U(*w 0xFF80) == 0x1");
+    }
+
+    #[test]
+    fn labels() {
+        utils_test_expr!(expr, "PC == Main.loop", "PC == Main.loop");
+        utils_test_expr!(expr, "*wPlayerHP < 10", "*wPlayerHP < 0x10");
+        utils_test_expr!(expr, "*w(wScore) == 0", "*w wScore == 0x0");
+        utils_test_expr!(expr, "PC == _start + 3", "PC == _start + 0x3");
+        utils_test_expr!(expr, "PC == add_item", "PC == add_item");
+        utils_test_expr!(expr, "PC == Face", "PC == 0xFACE");
+        utils_test_expr!(expr, "PC == @Face", "PC == @Face");
+        utils_test_expr!(expr, "*w @LY == 0", "*w @LY == 0x0");
+        utils_test_expr!(expr, "*w C0A2 == 1234", "*w 0xC0A2 == 0x1234");
+    }
+
+    #[test]
+    fn logic_precedence() {
+        utils_test_expr!(
+            expr,
+            "(A == 1 || A == 2) && B == 3",
+            "(A == 0x1 || A == 0x2) && B == 0x3"
+        );
+        utils_test_expr!(
+            expr,
+            "A == 1 || A == 2 && B == 3",
+            "A == 0x1 || A == 0x2 && B == 0x3"
+        );
+    }
+}
@@ -1,4 +1,8 @@
-use crate::{boxed, wrapper::wrap_register, Node};
+use crate::{
+    boxed,
+    parser::{expr, ws},
+    Node,
+};
 use nom::{branch::alt, bytes::complete::tag, combinator::map, sequence::delimited, IResult};
 use std::fmt::{self, Display};
 
@@ -39,17 +43,17 @@ impl Display for UnaryOperator {
 /// # Definition
 ///
 /// ```txt
-/// unary_expr = (L|U) '(' register ')'
+/// unary_expr = (L|U) '(' expr ')'
 /// ```
 pub fn unary_expr(input: &str) -> IResult<&str, UnaryExpr> {
     let (input, unary_op) = unary_expr_id(input)?;
 
-    let (input, reg) = delimited(tag("("), wrap_register, tag(")"))(input)?;
+    let (input, child) = delimited(tag("("), ws(expr), tag(")"))(input)?;
     Ok((
         input,
         UnaryExpr {
             op: unary_op,
-            child: boxed!(reg),
+            child: boxed!(child),
         },
     ))
 }
@@ -4,9 +4,17 @@ use crate::Node;
 
 /// Wrap [crate::register::Register] in [Ast::Register]
 pub fn wrap_register(input: &str) -> IResult<&str, Node> {
-    let res = map(crate::register::register, Node::Register)(input);
-    eprintln!("[wrap_register] res: {:?}", res);
-    res
+    map(crate::register::register, Node::Register)(input)
+}
+
+/// Wrap [crate::io_register::IoRegister] in [Ast::IoRegister]
+pub fn wrap_io_register(input: &str) -> IResult<&str, Node> {
+    map(crate::io_register::io_register, Node::IoRegister)(input)
+}
+
+/// Wrap [crate::bank::Bank] in [Ast::Bank]
+pub fn wrap_bank(input: &str) -> IResult<&str, Node> {
+    map(crate::bank::bank, Node::Bank)(input)
 }
 
 /// Wrap [crate::native::value] in [Ast::Raw]
@@ -18,13 +26,3 @@ pub fn wrap_value(input: &str) -> IResult<&str, Node> {
 pub fn wrap_unary(input: &str) -> IResult<&str, Node> {
     map(crate::unary::unary_expr, Node::UnaryExpr)(input)
 }
-
-/// Wrap [crate::operation::bin_expr] to [Ast::BinaryExpr]
-pub fn wrap_bin_expr(input: &str) -> IResult<&str, Node> {
-    map(crate::operation::bin_expr, Node::BinaryExpr)(input)
-}
-
-/// Wrap [crate::operation::comb_expr] to [Ast::BinaryExpr]
-pub fn wrap_comb_expr(input: &str) -> IResult<&str, Node> {
-    map(crate::operation::comb_expr, Node::BinaryExpr)(input)
-}
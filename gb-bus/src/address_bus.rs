@@ -8,6 +8,7 @@ use crate::{
         IO_REG_START, IO_REG_STOP, OAM_START, OAM_STOP, RAM_START, RAM_STOP, ROM_START, ROM_STOP,
         VRAM_START, VRAM_STOP,
     },
+    watchpoint::{Access, Watchpoints},
     Addr, Area, Error, FileOperation, Source,
 };
 
@@ -82,6 +83,8 @@ pub struct AddressBus {
     pub hram: Rc<RefCell<dyn FileOperation<Addr<Area>, Area>>>,
     /// register to enable/disable all interrupts
     pub ie_reg: Rc<RefCell<dyn FileOperation<Addr<Area>, Area>>>,
+    /// addresses watched by the debugger
+    pub watchpoints: Watchpoints,
 }
 
 impl Default for AddressBus {
@@ -96,6 +99,7 @@ impl Default for AddressBus {
             io_reg: Rc::new(RefCell::new(PanicDevice::named("io_reg"))),
             hram: Rc::new(RefCell::new(PanicDevice::named("hram"))),
             ie_reg: Rc::new(RefCell::new(PanicDevice::named("ie_reg"))),
+            watchpoints: Watchpoints::default(),
         }
     }
 }
@@ -160,11 +164,25 @@ impl AddressBus {
 
 impl AddressBus {
     pub fn write_byte(&mut self, addr: u16, v: u8, source: Option<Source>) -> Result<(), Error> {
-        match_area!(write_area, self, addr, v, source)
+        if !self.watchpoints.is_watching(addr, Access::Write) {
+            return match_area!(write_area, self, addr, v, source);
+        }
+        let previous = match_area!(read_area, self, addr, Some(Source::Debugger)).ok();
+        let res = match_area!(write_area, self, addr, v, source);
+        if res.is_ok() {
+            self.watchpoints.on_write(addr, v, previous, source);
+        }
+        res
     }
 
     pub fn read_byte(&self, addr: u16, source: Option<Source>) -> Result<u8, Error> {
-        match_area!(read_area, self, addr, source)
+        let res = match_area!(read_area, self, addr, source);
+        if !self.watchpoints.is_empty() {
+            if let Ok(v) = res {
+                self.watchpoints.on_read(addr, v, source);
+            }
+        }
+        res
     }
 
     pub fn iter(&self) -> Iter {
@@ -185,7 +203,9 @@ impl crate::Bus<u8> for AddressBus {
 #[cfg(test)]
 mod test_address_bus {
     use super::AddressBus;
-    use crate::generic::CharDevice;
+    use crate::generic::{CharDevice, SimpleRW};
+    use crate::watchpoint::{Access, AccessMask, Owner, Trigger, Watchpoint};
+    use crate::Source;
     use std::{cell::RefCell, rc::Rc};
 
     #[test]
@@ -200,6 +220,7 @@ mod test_address_bus {
             io_reg: Rc::new(RefCell::new(CharDevice(7))),
             hram: Rc::new(RefCell::new(CharDevice(8))),
             ie_reg: Rc::new(RefCell::new(CharDevice(9))),
+            ..Default::default()
         };
 
         assert_eq!(addr_bus.read_byte(0x10, None), Ok(1));
@@ -225,6 +246,7 @@ mod test_address_bus {
             io_reg: Rc::new(RefCell::new(CharDevice(7))),
             hram: Rc::new(RefCell::new(CharDevice(8))),
             ie_reg: Rc::new(RefCell::new(CharDevice(9))),
+            ..Default::default()
         };
 
         assert_eq!(addr_bus.write_byte(0x11, 0x30, None), Ok(()));
@@ -247,4 +269,28 @@ mod test_address_bus {
         assert_eq!(addr_bus.read_byte(0xff80, None), Ok(0x37));
         assert_eq!(addr_bus.read_byte(0xffff, None), Ok(0x38));
     }
+
+    #[test]
+    fn watchpoints() {
+        let mut addr_bus = AddressBus::default();
+        addr_bus.with_hram(Rc::new(RefCell::new(SimpleRW::<0x80>::default())));
+        addr_bus.watchpoints.set(
+            Owner::Debugger,
+            vec![Watchpoint::new(
+                0xff90..=0xff9f,
+                AccessMask::WRITE,
+                Trigger::Changed,
+            )],
+        );
+
+        assert_eq!(addr_bus.write_byte(0xff90, 0x42, Some(Source::Dma)), Ok(()));
+        assert_eq!(addr_bus.write_byte(0xff90, 0x42, None), Ok(()));
+        assert_eq!(addr_bus.read_byte(0xff90, None), Ok(0x42));
+
+        let hits = addr_bus.watchpoints.take_hits(Owner::Debugger);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].access, Access::Write);
+        assert_eq!(hits[0].previous, Some(0));
+        assert_eq!(hits[0].source, Some(Source::Dma));
+    }
 }
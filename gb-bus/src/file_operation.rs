@@ -38,6 +38,8 @@ pub enum Source {
     Cpu,
     Ppu,
     Dma,
+    /// A component requesting an interrupt, the value written to `IF` holds only the requested bits
+    Interrupt,
     Debugger,
 }
 
@@ -1,6 +1,7 @@
 use crate::Source;
 use crate::{area::Area, error::Error, io_reg_area::IORegArea, Address, FileOperation};
 
+#[derive(Clone)]
 pub struct SimpleRW<const SIZE: usize> {
     store: [u8; SIZE],
 }
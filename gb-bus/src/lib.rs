@@ -9,6 +9,7 @@ pub mod io_reg_area;
 mod io_reg_bus;
 pub mod io_reg_constant;
 mod serial;
+pub mod watchpoint;
 mod working_ram;
 
 pub use address::Addr;
@@ -19,6 +20,7 @@ pub use file_operation::{Address, FileOperation, Source};
 pub use io_reg_area::IORegArea;
 pub use io_reg_bus::IORegBus;
 pub use serial::Serial;
+pub use watchpoint::{Watchpoint, WatchpointHit};
 pub use working_ram::WorkingRam;
 
 pub trait Bus<N> {
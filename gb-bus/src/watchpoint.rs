@@ -0,0 +1,407 @@
+use crate::Source;
+use std::{
+    cell::RefCell,
+    fmt::{self, Display},
+    ops::{Range, RangeInclusive},
+};
+
+/// Kind of access made on the address bus
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+    Execute,
+}
+
+impl Display for Access {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Access::Read => write!(f, "R"),
+            Access::Write => write!(f, "W"),
+            Access::Execute => write!(f, "X"),
+        }
+    }
+}
+
+/// Set of accesses a [Watchpoint] is listening to
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct AccessMask {
+    pub read: bool,
+    pub write: bool,
+    pub execute: bool,
+}
+
+impl AccessMask {
+    pub const READ: Self = Self {
+        read: true,
+        write: false,
+        execute: false,
+    };
+    pub const WRITE: Self = Self {
+        read: false,
+        write: true,
+        execute: false,
+    };
+    pub const EXECUTE: Self = Self {
+        read: false,
+        write: false,
+        execute: true,
+    };
+
+    pub fn contains(&self, access: Access) -> bool {
+        match access {
+            Access::Read => self.read,
+            Access::Write => self.write,
+            Access::Execute => self.execute,
+        }
+    }
+}
+
+impl Display for AccessMask {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}{}{}",
+            if self.read { 'R' } else { '-' },
+            if self.write { 'W' } else { '-' },
+            if self.execute { 'X' } else { '-' }
+        )
+    }
+}
+
+/// Condition that need to be met by the accessed value for a [Watchpoint] to trigger
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trigger {
+    /// Trigger on every access
+    Any,
+    /// Trigger only when a write modify the stored value
+    Changed,
+    /// Trigger when the value is equal to
+    Equal(u8),
+    /// Trigger when the value is not equal to
+    NotEqual(u8),
+    /// Trigger when the value is greater than
+    Greater(u8),
+    /// Trigger when the value is lower than
+    Lower(u8),
+}
+
+impl Default for Trigger {
+    fn default() -> Self {
+        Trigger::Any
+    }
+}
+
+impl Trigger {
+    fn is_met(&self, value: u8, previous: Option<u8>) -> bool {
+        match self {
+            Trigger::Any => true,
+            Trigger::Changed => matches!(previous, Some(previous) if previous != value),
+            Trigger::Equal(v) => value == *v,
+            Trigger::NotEqual(v) => value != *v,
+            Trigger::Greater(v) => value > *v,
+            Trigger::Lower(v) => value < *v,
+        }
+    }
+}
+
+impl Display for Trigger {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Trigger::Any => write!(f, "any"),
+            Trigger::Changed => write!(f, "changed"),
+            Trigger::Equal(v) => write!(f, "== {:02X}", v),
+            Trigger::NotEqual(v) => write!(f, "!= {:02X}", v),
+            Trigger::Greater(v) => write!(f, "> {:02X}", v),
+            Trigger::Lower(v) => write!(f, "< {:02X}", v),
+        }
+    }
+}
+
+/// Tool that installed a [Watchpoint], each one only manage and receive the hits of its own
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Owner {
+    Debugger,
+    Gdb,
+}
+
+/// A watchpoint fire when an address of its range is accessed on the bus
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Watchpoint {
+    pub range: RangeInclusive<u16>,
+    pub access: AccessMask,
+    pub trigger: Trigger,
+    pub enabled: bool,
+    pub owner: Owner,
+}
+
+impl Watchpoint {
+    /// Create a watchpoint owned by the debugger
+    pub fn new(range: RangeInclusive<u16>, access: AccessMask, trigger: Trigger) -> Self {
+        Self {
+            range,
+            access,
+            trigger,
+            enabled: true,
+            owner: Owner::Debugger,
+        }
+    }
+
+    pub fn owned_by(self, owner: Owner) -> Self {
+        Self { owner, ..self }
+    }
+
+    fn is_watching(&self, addr: u16, access: Access) -> bool {
+        self.enabled && self.access.contains(access) && self.range.contains(&addr)
+    }
+}
+
+impl Display for Watchpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.range.start() == self.range.end() {
+            write!(f, "{:04X}", self.range.start())?;
+        } else {
+            write!(f, "{:04X}-{:04X}", self.range.start(), self.range.end())?;
+        }
+        write!(f, " {} {}", self.access, self.trigger)
+    }
+}
+
+/// Record of a triggered [Watchpoint]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchpointHit {
+    pub owner: Owner,
+    /// Index of the watchpoint in the watch list of its owner
+    pub index: usize,
+    pub address: u16,
+    pub access: Access,
+    pub value: u8,
+    /// Value stored before a write access
+    pub previous: Option<u8>,
+    /// Who initiated the access, `None` is used by the cpu
+    pub source: Option<Source>,
+}
+
+impl Display for WatchpointHit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {:04X} = {:02X}",
+            self.access, self.address, self.value
+        )?;
+        if let Some(previous) = self.previous {
+            write!(f, " (was {:02X})", previous)?;
+        }
+        match self.source {
+            Some(source) => write!(f, " by {:?}", source),
+            None => write!(f, " by Cpu"),
+        }
+    }
+}
+
+/// Watch list used by the [crate::AddressBus] to record the accesses made on watched addresses.
+///
+/// The watchpoints of every [Owner] share the list, sorted by owner,
+/// so they are all checked at once but each owner only sees its own.
+#[derive(Default, Debug)]
+pub struct Watchpoints {
+    list: Vec<Watchpoint>,
+    hits: RefCell<Vec<WatchpointHit>>,
+}
+
+impl Watchpoints {
+    /// Replace the watchpoints of `owner`, the ones of the other owners are kept
+    pub fn set(&mut self, owner: Owner, list: Vec<Watchpoint>) {
+        let range = self.bounds(owner);
+        self.list
+            .splice(range, list.into_iter().map(|w| w.owned_by(owner)));
+        self.hits.get_mut().retain(|hit| hit.owner != owner);
+    }
+
+    /// Return the watchpoints of `owner`
+    pub fn list(&self, owner: Owner) -> &[Watchpoint] {
+        &self.list[self.bounds(owner)]
+    }
+
+    fn bounds(&self, owner: Owner) -> Range<usize> {
+        let start = self.list.partition_point(|w| w.owner < owner);
+        let end = self.list.partition_point(|w| w.owner <= owner);
+        start..end
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    /// Indicate if any watchpoint need to check an `access` on `addr`
+    pub fn is_watching(&self, addr: u16, access: Access) -> bool {
+        self.list.iter().any(|w| w.is_watching(addr, access))
+    }
+
+    /// Take the hits of the watchpoints of `owner` recorded since the last call
+    pub fn take_hits(&mut self, owner: Owner) -> Vec<WatchpointHit> {
+        let (taken, kept) = std::mem::take(self.hits.get_mut())
+            .into_iter()
+            .partition(|hit| hit.owner == owner);
+        *self.hits.get_mut() = kept;
+        taken
+    }
+
+    /// Drop the hits recorded for every owner
+    pub fn clear_hits(&mut self) {
+        self.hits.get_mut().clear();
+    }
+
+    pub fn on_read(&self, addr: u16, value: u8, source: Option<Source>) {
+        self.record(addr, Access::Read, value, None, source)
+    }
+
+    pub fn on_write(&self, addr: u16, value: u8, previous: Option<u8>, source: Option<Source>) {
+        self.record(addr, Access::Write, value, previous, source)
+    }
+
+    /// Notify that the cpu is about to execute the instruction at `pc`,
+    /// it must be called once per executed instruction
+    pub fn on_execute(&self, pc: u16, opcode: u8) {
+        self.record(pc, Access::Execute, opcode, None, None)
+    }
+
+    fn record(
+        &self,
+        addr: u16,
+        access: Access,
+        value: u8,
+        previous: Option<u8>,
+        source: Option<Source>,
+    ) {
+        if source == Some(Source::Debugger) {
+            return;
+        }
+        let mut hits = self.hits.borrow_mut();
+        for (index, watchpoint) in self.list.iter().enumerate() {
+            if watchpoint.is_watching(addr, access) && watchpoint.trigger.is_met(value, previous) {
+                hits.push(WatchpointHit {
+                    owner: watchpoint.owner,
+                    index: index - self.bounds(watchpoint.owner).start,
+                    address: addr,
+                    access,
+                    value,
+                    previous,
+                    source,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_watchpoints {
+    use super::{Access, AccessMask, Owner, Trigger, Watchpoint, Watchpoints};
+    use crate::Source;
+
+    #[test]
+    fn read_write() {
+        let mut watchpoints = Watchpoints::default();
+        watchpoints.set(
+            Owner::Debugger,
+            vec![
+                Watchpoint::new(0xc000..=0xc0ff, AccessMask::WRITE, Trigger::Any),
+                Watchpoint::new(0xc042..=0xc042, AccessMask::READ, Trigger::Equal(0x2a)),
+            ],
+        );
+
+        watchpoints.on_read(0xc042, 0x10, None);
+        watchpoints.on_write(0xd000, 0x10, None, None);
+        assert!(watchpoints.take_hits(Owner::Debugger).is_empty());
+
+        watchpoints.on_read(0xc042, 0x2a, Some(Source::Dma));
+        watchpoints.on_write(0xc010, 0x10, None, None);
+        let hits = watchpoints.take_hits(Owner::Debugger);
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].index, 1);
+        assert_eq!(hits[0].source, Some(Source::Dma));
+        assert_eq!(hits[1].index, 0);
+        assert_eq!(hits[1].access, Access::Write);
+        assert!(watchpoints.take_hits(Owner::Debugger).is_empty());
+    }
+
+    #[test]
+    fn changed() {
+        let mut watchpoints = Watchpoints::default();
+        watchpoints.set(
+            Owner::Debugger,
+            vec![Watchpoint::new(
+                0xff80..=0xff80,
+                AccessMask::WRITE,
+                Trigger::Changed,
+            )],
+        );
+
+        watchpoints.on_write(0xff80, 5, Some(5), None);
+        assert!(watchpoints.take_hits(Owner::Debugger).is_empty());
+        watchpoints.on_write(0xff80, 6, Some(5), None);
+        assert_eq!(watchpoints.take_hits(Owner::Debugger)[0].previous, Some(5));
+    }
+
+    #[test]
+    fn execute() {
+        let mut watchpoints = Watchpoints::default();
+        watchpoints.set(
+            Owner::Debugger,
+            vec![Watchpoint::new(
+                0x150..=0x150,
+                AccessMask::EXECUTE,
+                Trigger::Any,
+            )],
+        );
+
+        // a loop jumping on itself executes the same address again
+        watchpoints.on_execute(0x150, 0x18);
+        watchpoints.on_execute(0x150, 0x18);
+        assert_eq!(watchpoints.take_hits(Owner::Debugger).len(), 2);
+        watchpoints.on_execute(0x151, 0);
+        assert!(watchpoints.take_hits(Owner::Debugger).is_empty());
+    }
+
+    #[test]
+    fn debugger_is_ignored() {
+        let mut watchpoints = Watchpoints::default();
+        watchpoints.set(
+            Owner::Debugger,
+            vec![Watchpoint::new(
+                0x0000..=0xffff,
+                AccessMask::READ,
+                Trigger::Any,
+            )],
+        );
+
+        watchpoints.on_read(0x1234, 0, Some(Source::Debugger));
+        assert!(watchpoints.take_hits(Owner::Debugger).is_empty());
+    }
+
+    #[test]
+    fn owners() {
+        let mut watchpoints = Watchpoints::default();
+        let watch = |address| Watchpoint::new(address..=address, AccessMask::WRITE, Trigger::Any);
+        watchpoints.set(Owner::Gdb, vec![watch(0xc000)]);
+        watchpoints.set(Owner::Debugger, vec![watch(0xc001), watch(0xc000)]);
+        assert_eq!(
+            watchpoints.list(Owner::Gdb),
+            &[watch(0xc000).owned_by(Owner::Gdb)]
+        );
+        assert_eq!(watchpoints.list(Owner::Debugger).len(), 2);
+
+        watchpoints.on_write(0xc000, 1, None, None);
+        let hits = watchpoints.take_hits(Owner::Debugger);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].index, 1);
+        let hits = watchpoints.take_hits(Owner::Gdb);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].index, 0);
+
+        watchpoints.set(Owner::Debugger, Vec::new());
+        assert_eq!(watchpoints.list(Owner::Gdb).len(), 1);
+        watchpoints.on_write(0xc001, 1, None, None);
+        assert!(watchpoints.take_hits(Owner::Gdb).is_empty());
+    }
+}
@@ -29,6 +29,35 @@ impl WorkingRam {
         }
     }
 
+    /// Return the number of banks available in the current mode
+    pub fn bank_count(&self) -> usize {
+        self.storage.len() / RAM_BANK_SIZE
+    }
+
+    /// Read a byte of the bank `bank` regardless of the bank currently mapped,
+    /// `offset` is relative to the start of the bank
+    pub fn read_bank(&self, bank: usize, offset: u16) -> Option<u8> {
+        self.storage.get(Self::bank_index(bank, offset)).copied()
+    }
+
+    /// Write a byte of the bank `bank` regardless of the bank currently mapped,
+    /// `offset` is relative to the start of the bank.
+    ///
+    /// Return `false` when the bank doesn't exist in the current mode
+    pub fn write_bank(&mut self, bank: usize, offset: u16, value: u8) -> bool {
+        match self.storage.get_mut(Self::bank_index(bank, offset)) {
+            Some(byte) => {
+                *byte = value;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn bank_index(bank: usize, offset: u16) -> usize {
+        bank * RAM_BANK_SIZE + (offset as usize & (RAM_BANK_SIZE - 1))
+    }
+
     fn offset_addr(&self, addr: usize) -> usize {
         let bank = self.get_bank_for_addr(addr);
         (bank * RAM_BANK_SIZE) | (addr & 0xfff) as usize
@@ -87,3 +116,26 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod test_working_ram {
+    use super::WorkingRam;
+    use crate::{address::Addr, Area, FileOperation, IORegArea};
+
+    #[test]
+    fn banks() {
+        let mut wram = WorkingRam::new(true);
+        assert_eq!(wram.bank_count(), 8);
+        assert!(wram.write_bank(3, 0x0010, 42));
+        assert!(!wram.write_bank(8, 0x0010, 42));
+
+        let svbk = Addr::byte_reg(IORegArea::Svbk, 0xff70);
+        let address = Addr::new(Area::Ram, 0x1010, 0xd010);
+        FileOperation::<_, IORegArea>::write(&mut wram, 3, svbk, None).unwrap();
+        assert_eq!(FileOperation::<_, Area>::read(&wram, address, None), Ok(42));
+        FileOperation::<_, Area>::write(&mut wram, 7, address, None).unwrap();
+        assert_eq!(wram.read_bank(3, 0x0010), Some(7));
+        assert_eq!(wram.read_bank(1, 0x0010), Some(0));
+        assert_eq!(WorkingRam::new(false).read_bank(2, 0), None);
+    }
+}
@@ -28,6 +28,8 @@ macro_rules! counted_cycles {
 impl Clock {
     /// The amount of cycles to execute per frame.
     pub const CYCLES_PER_FRAME: usize = 17556;
+    /// The amount of cycles executed in a second, in single speed.
+    pub const CYCLES_PER_SECOND: usize = 0x10_0000;
 
     pub fn inc_frame(&mut self) -> bool {
         self.curr_frame_cycle += 1;
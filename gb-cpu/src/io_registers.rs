@@ -31,6 +31,9 @@ pub struct IORegisters {
 
     pub current_speed: Speed,
     pub prepare_to_switch: bool,
+    /// Interrupts requested by the components since the last call to [IORegisters::take_requests]
+    #[cfg_attr(feature = "serialization", serde(skip))]
+    requests: u8,
 }
 
 impl Default for IORegisters {
@@ -41,6 +44,7 @@ impl Default for IORegisters {
             enable_mask: 9,
             current_speed: Speed::Normal,
             prepare_to_switch: false,
+            requests: 0,
         }
     }
 }
@@ -79,6 +83,12 @@ impl IORegisters {
     pub fn fast_mode(&self) -> bool {
         self.current_speed == Speed::Double
     }
+
+    /// Return the interrupts requested by the components since the last call,
+    /// including the ones requested while already pending
+    pub fn take_requests(&mut self) -> u8 {
+        std::mem::take(&mut self.requests)
+    }
 }
 
 impl<A> FileOperation<A, Area> for IORegisters
@@ -112,8 +122,13 @@ where
         }
     }
 
-    fn write(&mut self, v: u8, addr: A, _source: Option<Source>) -> Result<(), gb_bus::Error> {
+    fn write(&mut self, v: u8, addr: A, source: Option<Source>) -> Result<(), gb_bus::Error> {
         match addr.area_type() {
+            IORegArea::IF if source == Some(Source::Interrupt) => {
+                let requested = v & !(IORegisters::FLAG_MASK);
+                self.flag |= requested;
+                self.requests |= requested;
+            }
             IORegArea::IF => self.flag = v & !(IORegisters::FLAG_MASK),
             IORegArea::Key1 => {
                 self.prepare_to_switch = v & 1 == 1;
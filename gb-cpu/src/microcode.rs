@@ -1,5 +1,6 @@
 mod arithmetic;
 mod bitwise;
+pub mod call_stack;
 mod condition;
 pub mod controller;
 mod dec;
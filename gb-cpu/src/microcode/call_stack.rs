@@ -0,0 +1,204 @@
+use std::fmt::{self, Display};
+
+/// Maximum number of frames kept, the oldest are dropped first
+const MAX_DEPTH: usize = 256;
+
+/// What created a [Frame]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameKind {
+    Call,
+    Restart,
+    Interrupt,
+}
+
+impl Display for FrameKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FrameKind::Call => write!(f, "CALL"),
+            FrameKind::Restart => write!(f, "RST"),
+            FrameKind::Interrupt => write!(f, "INT"),
+        }
+    }
+}
+
+/// A function call tracked by the [CallStack]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frame {
+    pub kind: FrameKind,
+    /// Address of the instruction that created the frame
+    pub call_site: u16,
+    /// Address of the called function
+    pub target: u16,
+    /// Address where the execution resume once the function returns
+    pub return_address: u16,
+    /// Value of SP once the return address was pushed
+    pub sp: u16,
+    /// ROM bank mapped when the frame was created, filled by the owner of the mbc
+    pub bank: Option<usize>,
+}
+
+/// Shadow call stack maintained next to the real stack of the cpu
+///
+/// The frames are pushed on `CALL`, `RST` and interrupt dispatch and popped on `RET` and `RETI`.
+/// Games that manipulate SP by hand desynchronize it from the real stack,
+/// the frames that cannot be returned to anymore are dropped when it happens.
+///
+/// The calls are only tracked once enabled, the returns from the calls made
+/// before are counted as desynchronizations.
+#[derive(Debug, Default, Clone)]
+pub struct CallStack {
+    frames: Vec<Frame>,
+    /// Number of desynchronizations detected
+    pub desyncs: usize,
+    tracking: bool,
+}
+
+impl CallStack {
+    pub fn is_tracking(&self) -> bool {
+        self.tracking
+    }
+
+    /// Start or stop the tracking of the calls, the frames are dropped when it change
+    pub fn set_tracking(&mut self, tracking: bool) {
+        if tracking != self.tracking {
+            self.tracking = tracking;
+            self.clear();
+        }
+    }
+
+    /// Return the frames, the innermost last
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+        self.desyncs = 0;
+    }
+
+    /// Fill the bank of the frames created since the last call
+    pub fn fill_banks(&mut self, rom_bank: impl FnOnce() -> usize) {
+        if matches!(self.frames.last(), Some(frame) if frame.bank.is_none()) {
+            let bank = rom_bank();
+            for frame in self.frames.iter_mut().rev() {
+                if frame.bank.is_some() {
+                    break;
+                }
+                frame.bank = Some(bank);
+            }
+        }
+    }
+
+    pub fn push(&mut self, frame: Frame) {
+        if self.frames.len() == MAX_DEPTH {
+            self.frames.remove(0);
+        }
+        self.frames.push(frame);
+    }
+
+    /// Handle a return to `return_address` that popped the return address stored at `sp`
+    pub fn on_return(&mut self, sp: u16, return_address: u16) {
+        let matching = self
+            .frames
+            .iter()
+            .rposition(|frame| frame.sp == sp && frame.return_address == return_address);
+
+        match matching {
+            Some(index) => {
+                if index + 1 != self.frames.len() {
+                    self.desync(format!(
+                        "return to {:04X} skipped {} frames",
+                        return_address,
+                        self.frames.len() - index - 1
+                    ));
+                }
+                self.frames.truncate(index);
+            }
+            None => self.desync(format!("return to unknown address {:04X}", return_address)),
+        }
+    }
+
+    /// Drop the frames whose return address was popped without returning
+    pub fn sync(&mut self, sp: u16) {
+        let alive = self
+            .frames
+            .iter()
+            .rposition(|frame| frame.sp >= sp)
+            .map_or(0, |index| index + 1);
+
+        if alive != self.frames.len() {
+            self.desync(format!(
+                "SP={:04X} discarded {} frames",
+                sp,
+                self.frames.len() - alive
+            ));
+            self.frames.truncate(alive);
+        }
+    }
+
+    fn desync(&mut self, reason: String) {
+        log::debug!("call stack desynchronized: {}", reason);
+        self.desyncs += 1;
+    }
+}
+
+#[cfg(test)]
+mod test_call_stack {
+    use super::{CallStack, Frame, FrameKind};
+
+    fn frame(return_address: u16, sp: u16) -> Frame {
+        Frame {
+            kind: FrameKind::Call,
+            call_site: return_address - 3,
+            target: 0x4000,
+            return_address,
+            sp,
+            bank: None,
+        }
+    }
+
+    #[test]
+    fn call_return() {
+        let mut stack = CallStack::default();
+        stack.push(frame(0x153, 0xdffc));
+        stack.push(frame(0x4003, 0xdffa));
+        stack.on_return(0xdffa, 0x4003);
+        assert_eq!(stack.frames(), &[frame(0x153, 0xdffc)]);
+        stack.on_return(0xdffc, 0x153);
+        assert!(stack.frames().is_empty());
+        assert_eq!(stack.desyncs, 0);
+    }
+
+    #[test]
+    fn fill_banks() {
+        let mut stack = CallStack::default();
+        stack.push(frame(0x153, 0xdffc));
+        stack.fill_banks(|| 1);
+        stack.push(frame(0x4003, 0xdffa));
+        stack.fill_banks(|| 2);
+        stack.fill_banks(|| unreachable!());
+        let banks: Vec<_> = stack.frames().iter().map(|frame| frame.bank).collect();
+        assert_eq!(banks, vec![Some(1), Some(2)]);
+    }
+
+    #[test]
+    fn desync() {
+        let mut stack = CallStack::default();
+        stack.push(frame(0x153, 0xdffc));
+        stack.push(frame(0x4003, 0xdffa));
+
+        // jump table using `PUSH HL; RET`
+        stack.on_return(0xdff8, 0x2000);
+        assert_eq!(stack.frames().len(), 2);
+        assert_eq!(stack.desyncs, 1);
+
+        // `POP HL` of the return address
+        stack.sync(0xdffc);
+        assert_eq!(stack.frames(), &[frame(0x153, 0xdffc)]);
+        assert_eq!(stack.desyncs, 2);
+
+        // reset of the stack pointer
+        stack.sync(0xfffe);
+        assert!(stack.frames().is_empty());
+    }
+}
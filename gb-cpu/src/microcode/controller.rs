@@ -1,5 +1,9 @@
 use super::{
-    fetch::fetch, interrupts::handle_interrupts, opcode::Opcode, opcode_cb::OpcodeCB,
+    call_stack::{CallStack, Frame, FrameKind},
+    fetch::fetch,
+    interrupts::handle_interrupts,
+    opcode::Opcode,
+    opcode_cb::OpcodeCB,
     MicrocodeFlow, State,
 };
 use crate::{
@@ -50,6 +54,7 @@ impl From<OpcodeCB> for OpcodeType {
     }
 }
 
+#[derive(Clone)]
 pub struct MicrocodeController {
     /// current opcode
     pub opcode: Option<OpcodeType>,
@@ -69,6 +74,13 @@ pub struct MicrocodeController {
     cache: Vec<u8>,
     /// Debug helper to catch the event of end of instruction
     pub is_instruction_finished: bool,
+    /// Debug helper to catch the dispatch of an interrupt, hold the address of its vector
+    /// until the start of the next task
+    pub interrupt_dispatched: Option<u16>,
+    /// Debug helper tracking the function calls
+    pub call_stack: CallStack,
+    /// Value of PC and SP at the start of the current task
+    task_start: (u16, u16),
     pub cgb_mode: bool,
 }
 
@@ -97,6 +109,9 @@ impl MicrocodeController {
             halted_from_stop: false,
             cycles_in_halt_mode: 0,
             is_instruction_finished: true,
+            interrupt_dispatched: None,
+            call_stack: CallStack::default(),
+            task_start: (0, 0),
             cgb_mode,
         }
     }
@@ -115,6 +130,8 @@ impl MicrocodeController {
             self.push_to_current_cycle(&cycle);
         } else {
             self.clear();
+            self.task_start = (state.regs.pc, state.regs.sp);
+            self.interrupt_dispatched = None;
             self.pull_next_task(&mut state, int_flags);
         }
 
@@ -122,6 +139,57 @@ impl MicrocodeController {
 
         if self.cycles.is_empty() {
             self.is_instruction_finished = true;
+            if self.call_stack.is_tracking() {
+                self.update_call_stack(state.regs);
+            }
+        }
+    }
+
+    /// Track the calls and returns of the task that just finished
+    fn update_call_stack(&mut self, regs: &Registers) {
+        let (pc, sp) = self.task_start;
+        let pushed = regs.sp == sp.wrapping_sub(2);
+        let popped = regs.sp == sp.wrapping_add(2);
+        let frame = |kind, len: u16| Frame {
+            kind,
+            call_site: pc,
+            target: regs.pc,
+            return_address: pc.wrapping_add(len),
+            sp: regs.sp,
+            bank: None,
+        };
+
+        if self.interrupt_dispatched.is_some() {
+            self.call_stack.push(frame(FrameKind::Interrupt, 0));
+            return;
+        }
+        match self.opcode {
+            Some(OpcodeType::Unprefixed(
+                Opcode::Call16
+                | Opcode::CallNz
+                | Opcode::CallZ16
+                | Opcode::CallNc16
+                | Opcode::CallC16,
+            )) if pushed => self.call_stack.push(frame(FrameKind::Call, 3)),
+            Some(OpcodeType::Unprefixed(
+                Opcode::Rst00
+                | Opcode::Rst08
+                | Opcode::Rst10
+                | Opcode::Rst18
+                | Opcode::Rst20
+                | Opcode::Rst28
+                | Opcode::Rst30
+                | Opcode::Rst38,
+            )) if pushed => self.call_stack.push(frame(FrameKind::Restart, 1)),
+            Some(OpcodeType::Unprefixed(
+                Opcode::Ret
+                | Opcode::RetNz
+                | Opcode::RetZ
+                | Opcode::RetNc
+                | Opcode::RetC
+                | Opcode::Reti,
+            )) if popped => self.call_stack.on_return(sp, regs.pc),
+            _ => self.call_stack.sync(regs.sp),
         }
     }
 
@@ -26,7 +26,9 @@ pub fn handle_interrupts(ctl: &mut MicrocodeController, state: &mut State) -> Mi
     int_flags.flag ^= bit_to_res;
 
     // Push interrupt source address to cache
-    ctl.push_u16(0x0040 | ((source_bit as u16) << 3));
+    let vector = 0x0040 | ((source_bit as u16) << 3);
+    ctl.push_u16(vector);
+    ctl.interrupt_dispatched = Some(vector);
 
     ctl.push_cycles(&[
         // Store pc into stack
@@ -1,7 +1,17 @@
+use gb_bus::watchpoint::Owner;
+use gb_bus::{Watchpoint, WatchpointHit};
+use gb_cpu::microcode::call_stack::CallStack;
 use gb_dbg::dbg_interfaces::{
-    AudioRegs, CpuRegs, DebugOperations, IORegs, MemoryDebugOperations, PpuRegs,
-    RegisterDebugOperations, RegisterMap, RegisterValue,
+    AudioRegs, BankedArea, CpuRegs, DebugOperations, IORegs, MemoryDebugOperations, PpuRegs,
+    RegisterDebugOperations, RegisterMap, RegisterValue, Screen, WatchpointDebugOperations,
 };
+use gb_dbg::audio::{ChannelState, ScopePoint};
+use gb_dbg::profiler::Profiler;
+use gb_dbg::symbols::SymbolTable;
+use gb_dbg::timeline::Timeline;
+use gb_dbg::trace::Tracer;
+use gb_roms::code_data_log::CodeDataLog;
+use std::cell::Ref;
 
 pub struct Iter<'a> {
     count: u32,
@@ -54,18 +64,127 @@ pub struct Game {
     pub f: u8,
     pub pc: u16,
     pub memory: Vec<u8>,
+    /// Watchpoints of each [Owner], indexed by owner
+    pub watchpoints: [Vec<Watchpoint>; 2],
+    pub symbols: SymbolTable,
+    pub call_stack: CallStack,
+    pub tracer: Tracer,
+    pub timeline: Timeline,
+    pub profiler: Profiler,
 }
 
 impl DebugOperations for Game {
     fn cycle(&self) -> usize {
         (self.a + self.b) as usize
     }
+
+    fn is_stopped(&self) -> bool {
+        false
+    }
+
+    fn rom_bank(&self) -> usize {
+        1
+    }
+
+    fn ram_bank(&self) -> usize {
+        0
+    }
+
+    fn rom_bank_count(&self) -> usize {
+        2
+    }
+
+    fn read_rom(&self, bank: usize, offset: u16) -> u8 {
+        self.memory[bank * 0x4000 + offset as usize]
+    }
+
+    fn bank_count(&self, _area: BankedArea) -> usize {
+        0
+    }
+
+    fn read_bank(&self, _area: BankedArea, _bank: usize, _offset: u16) -> u8 {
+        0xff
+    }
+
+    fn screen(&self) -> Screen {
+        Screen {
+            width: 160,
+            height: 144,
+            pixels: vec![[0xff; 3]; 160 * 144],
+        }
+    }
+
+    fn symbols(&self) -> &SymbolTable {
+        &self.symbols
+    }
+
+    fn call_stack(&self) -> &CallStack {
+        &self.call_stack
+    }
+
+    fn tracer(&mut self) -> &mut Tracer {
+        &mut self.tracer
+    }
+
+    fn timeline(&mut self) -> &mut Timeline {
+        &mut self.timeline
+    }
+
+    fn profiler(&mut self) -> &mut Profiler {
+        &mut self.profiler
+    }
+
+    fn instruction_count(&self) -> u64 {
+        0
+    }
+
+    fn snapshot_before(&self, _instruction: u64) -> Option<u64> {
+        None
+    }
+
+    fn rewind(&mut self, _instruction: u64) -> bool {
+        false
+    }
+
+    fn step_instruction(&mut self) {}
+
+    fn frozen(&self) -> &[(u16, u8)] {
+        &[]
+    }
+
+    fn set_frozen(&mut self, _frozen: Vec<(u16, u8)>) {}
+
+    fn code_data_log(&self) -> Option<Ref<'_, CodeDataLog>> {
+        None
+    }
+
+    fn set_code_data_logging(&mut self, _recording: bool) {}
+
+    fn clear_code_data_log(&mut self) {}
+
+    fn audio_channels(&self) -> Vec<ChannelState> {
+        Vec::new()
+    }
+
+    fn set_channel_muted(&mut self, _channel: usize, _muted: bool) {}
+
+    fn set_channel_soloed(&mut self, _channel: usize, _soloed: bool) {}
+
+    fn set_audio_scope(&mut self, _enabled: bool) {}
+
+    fn audio_scope(&self) -> Vec<ScopePoint> {
+        Vec::new()
+    }
 }
 
 impl MemoryDebugOperations for Game {
     fn read(&self, index: u16) -> u8 {
         *self.memory.get(index as usize).unwrap()
     }
+
+    fn write(&mut self, index: u16, value: u8) {
+        self.memory[index as usize] = value;
+    }
 }
 
 impl Default for Game {
@@ -79,10 +198,30 @@ impl Default for Game {
             f: 8,
             pc: 500,
             memory: vec![0xFFu8; u16::MAX as usize],
+            watchpoints: [Vec::new(), Vec::new()],
+            symbols: SymbolTable::default(),
+            call_stack: CallStack::default(),
+            tracer: Tracer::default(),
+            timeline: Timeline::default(),
+            profiler: Profiler::default(),
         }
     }
 }
 
+impl WatchpointDebugOperations for Game {
+    fn watchpoints(&self, owner: Owner) -> &[Watchpoint] {
+        &self.watchpoints[owner as usize]
+    }
+
+    fn set_watchpoints(&mut self, owner: Owner, watchpoints: Vec<Watchpoint>) {
+        self.watchpoints[owner as usize] = watchpoints;
+    }
+
+    fn take_watchpoint_hits(&mut self, _owner: Owner) -> Vec<WatchpointHit> {
+        Vec::new()
+    }
+}
+
 impl Game {
     pub fn iter(&self) -> Iter<'_> {
         Iter {
@@ -116,6 +255,18 @@ impl RegisterDebugOperations for Game {
         }
     }
 
+    fn cpu_set(&mut self, key: CpuRegs, value: u16) {
+        let byte = value as u8;
+        match key {
+            CpuRegs::AF => self.a = byte,
+            CpuRegs::BC => self.b = byte,
+            CpuRegs::DE => self.d = byte,
+            CpuRegs::HL => self.f = byte,
+            CpuRegs::SP => self.c = byte,
+            CpuRegs::PC => self.pc = value,
+        }
+    }
+
     fn ppu_get(&self, _key: PpuRegs) -> RegisterValue {
         0xff_u8.into()
     }
@@ -25,7 +25,9 @@ impl App for DebuggerApp {
                     Until::Step(_n) => self.memory.pc += 1_u16,
                     Until::Frame(n) => self.memory.pc += (n << 2) as u16,
                     Until::Second(n) => self.memory.pc += (n << 3) as u16,
+                    Until::RunTo(address) => self.memory.pc = address,
                     Until::Null => self.memory.pc = 0,
+                    _ => self.memory.pc += 1_u16,
                 },
                 ControlFlow::Continue(_) => self.memory.pc = 1,
             };
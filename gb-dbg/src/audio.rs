@@ -0,0 +1,134 @@
+/// Number of sound channels
+pub const CHANNELS: usize = 4;
+/// Latest outputs of each channel followed by the mono mix, between -1 and 1
+pub type ScopePoint = [f32; CHANNELS + 1];
+
+const NOTES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+
+/// Decoded state of a sound channel
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ChannelState {
+    pub enabled: bool,
+    pub dac_enabled: bool,
+    pub muted: bool,
+    pub soloed: bool,
+    /// Frequency of the tone in Hz, of the LFSR clock for the noise channel
+    pub frequency: f64,
+    /// Period written in NRx3 and NRx4 by the square and wave channels
+    pub period: Option<u16>,
+    /// Duty pattern of the square channels, from 0 (12.5%) to 3 (75%)
+    pub duty: Option<u8>,
+    pub envelope: Option<Envelope>,
+    /// Steps left before the length counter disables the channel, when it's enabled
+    pub length: Option<u16>,
+    pub sweep: Option<Sweep>,
+    /// Output level of the wave channel set by NR32, from 0 (mute) to 3 (25%)
+    pub wave_level: Option<u8>,
+    /// Samples of the wave channel, as read from `FF30-FF3F`
+    pub wave_ram: Option<[u8; 16]>,
+    /// Set when the LFSR of the noise channel is 7 bits wide
+    pub short_lfsr: Option<bool>,
+}
+
+/// Volume envelope of the square and noise channels
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Envelope {
+    /// Current volume, between 0 and 15
+    pub volume: u8,
+    pub initial_volume: u8,
+    pub increase: bool,
+    /// Number of 64 Hz ticks between two changes of the volume, 0 when disabled
+    pub pace: u8,
+}
+
+/// Frequency sweep of the first channel
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Sweep {
+    pub enabled: bool,
+    /// Number of 128 Hz ticks between two changes of the period, 0 when disabled
+    pub pace: u8,
+    pub decrease: bool,
+    pub shift: u8,
+    /// Period the next change starts from
+    pub shadow_period: u16,
+}
+
+/// Return the name of the nearest note of `frequency` with the deviation in cents, as `A4 +2`
+pub fn note_name(frequency: f64) -> Option<String> {
+    if !frequency.is_finite() || frequency <= 0.0 {
+        return None;
+    }
+    // 69 is the MIDI number of A4, tuned to 440 Hz
+    let semitones = 12.0 * (frequency / 440.0).log2() + 69.0;
+    let note = semitones.round();
+    if !(0.0..=127.0).contains(&note) {
+        return None;
+    }
+    let cents = ((semitones - note) * 100.0).round() as i32;
+    let note = note as usize;
+    let octave = note as i32 / 12 - 1;
+    Some(format!("{}{} {:+}", NOTES[note % 12], octave, cents))
+}
+
+/// Return the duty cycle of a duty pattern
+pub fn duty_cycle(duty: u8) -> &'static str {
+    match duty & 0b11 {
+        0 => "12.5%",
+        1 => "25%",
+        2 => "50%",
+        _ => "75%",
+    }
+}
+
+/// Return the offset of the window of `width` samples to draw to keep a periodic wave still:
+/// the last window starting where the wave rises through its middle,
+/// or the last samples when the wave never does
+pub fn trigger(samples: &[f32], width: usize) -> usize {
+    let last = samples.len().saturating_sub(width);
+    let (min, max) = samples
+        .iter()
+        .fold((f32::MAX, f32::MIN), |(min, max), &sample| {
+            (min.min(sample), max.max(sample))
+        });
+    if max <= min {
+        return last;
+    }
+    let middle = (min + max) / 2.0;
+    (1..=last)
+        .rev()
+        .find(|&i| samples[i - 1] < middle && samples[i] >= middle)
+        .unwrap_or(last)
+}
+
+#[cfg(test)]
+mod test_audio {
+    use super::{note_name, trigger};
+
+    #[test]
+    fn notes() {
+        assert_eq!(note_name(440.0).as_deref(), Some("A4 +0"));
+        assert_eq!(note_name(261.63).as_deref(), Some("C4 +0"));
+        assert_eq!(note_name(450.0).as_deref(), Some("A4 +39"));
+        // the period 1750 of a square channel
+        assert_eq!(
+            note_name(131072.0 / (2048.0 - 1750.0)).as_deref(),
+            Some("A4 -1")
+        );
+        assert_eq!(note_name(10.0).as_deref(), Some("D#-1 +49"));
+        assert_eq!(note_name(0.0), None);
+        assert_eq!(note_name(f64::INFINITY), None);
+    }
+
+    #[test]
+    fn rising_edge() {
+        let square: Vec<f32> = (0..64)
+            .map(|i| if (i + 3) % 16 < 8 { -1.0 } else { 1.0 })
+            .collect();
+        assert_eq!(trigger(&square, 16), 37);
+
+        assert_eq!(trigger(&[0.5; 64], 16), 48);
+        assert_eq!(trigger(&[0.5; 8], 16), 0);
+    }
+}
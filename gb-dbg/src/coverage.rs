@@ -0,0 +1,344 @@
+use crate::png;
+use crate::symbols::SymbolTable;
+use gb_roms::code_data_log::CodeDataLog;
+use std::fmt::{self, Display};
+use std::io::{self, Write};
+
+const BANK_SIZE: usize = 0x4000;
+/// Width of the heatmaps, in bytes of the ROM
+const HEATMAP_WIDTH: usize = 256;
+/// Bytes of the ROM per cell of the HTML heatmap
+const HTML_BLOCK: usize = 256;
+
+/// Format of an exported code/data log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Flags of each byte, in the CDL format of FCEUX
+    Cdl,
+    /// Coverage of each bank and routine, with a heatmap of the ROM
+    Html,
+    /// Image of the ROM, one pixel per byte
+    Png,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 3] = [ExportFormat::Cdl, ExportFormat::Html, ExportFormat::Png];
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Cdl => "cdl",
+            ExportFormat::Html => "html",
+            ExportFormat::Png => "png",
+        }
+    }
+}
+
+impl Display for ExportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExportFormat::Cdl => write!(f, "CDL"),
+            ExportFormat::Html => write!(f, "HTML"),
+            ExportFormat::Png => write!(f, "PNG"),
+        }
+    }
+}
+
+/// Number of bytes of a part of the ROM by usage
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Coverage {
+    pub size: usize,
+    /// Bytes executed
+    pub code: usize,
+    /// Bytes read as data, by the cpu or by the DMA, and never executed
+    pub data: usize,
+}
+
+impl Coverage {
+    pub fn of(log: &CodeDataLog, offsets: std::ops::Range<usize>) -> Self {
+        offsets.fold(Self::default(), |mut coverage, offset| {
+            let flags = log.flags(offset);
+            coverage.size += 1;
+            if flags & CodeDataLog::CODE != 0 {
+                coverage.code += 1;
+            } else if flags & (CodeDataLog::DATA | CodeDataLog::DMA) != 0 {
+                coverage.data += 1;
+            }
+            coverage
+        })
+    }
+
+    /// Return the bytes never used
+    pub fn unused(&self) -> usize {
+        self.size - self.code - self.data
+    }
+
+    pub fn code_percent(&self) -> f64 {
+        percent(self.code, self.size)
+    }
+
+    pub fn data_percent(&self) -> f64 {
+        percent(self.data, self.size)
+    }
+}
+
+/// Return the coverage of each ROM bank
+pub fn banks(log: &CodeDataLog) -> Vec<Coverage> {
+    (0..log.rom_size())
+        .step_by(BANK_SIZE)
+        .map(|start| Coverage::of(log, start..(start + BANK_SIZE).min(log.rom_size())))
+        .collect()
+}
+
+/// Coverage of the code following a label, up to the next label of the bank
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoutineCoverage {
+    pub name: String,
+    pub bank: usize,
+    pub address: u16,
+    pub coverage: Coverage,
+}
+
+/// Return the coverage of the labels of the ROM, sorted by bank then by address.
+/// The local labels are part of their parent label.
+pub fn routines(log: &CodeDataLog, symbols: &SymbolTable) -> Vec<RoutineCoverage> {
+    let mut labels: Vec<_> = symbols
+        .symbols()
+        .filter(|symbol| symbol.address < 0x8000 && !symbol.name.contains('.'))
+        .map(|symbol| {
+            let bank = if symbol.address < 0x4000 {
+                0
+            } else {
+                symbol.bank as usize
+            };
+            (bank, symbol.address, &symbol.name)
+        })
+        .collect();
+    labels.sort();
+
+    labels
+        .iter()
+        .enumerate()
+        .map(|(index, &(bank, address, name))| {
+            let area_end: usize = if address < 0x4000 { 0x4000 } else { 0x8000 };
+            let end = labels
+                .get(index + 1)
+                .filter(|(next_bank, next, _)| *next_bank == bank && (*next as usize) < area_end)
+                .map_or(area_end, |(_, next, _)| *next as usize);
+            let start = rom_offset(bank, address);
+            RoutineCoverage {
+                name: name.clone(),
+                bank,
+                address,
+                coverage: Coverage::of(log, start..start + (end - address as usize)),
+            }
+        })
+        .collect()
+}
+
+/// Return the offset in the ROM of `address` of `bank`
+fn rom_offset(bank: usize, address: u16) -> usize {
+    bank * BANK_SIZE + (address as usize & (BANK_SIZE - 1))
+}
+
+/// Write the log in `format`, the HTML report include the coverage of the labels of `symbols`
+pub fn export(
+    writer: &mut impl Write,
+    log: &CodeDataLog,
+    format: ExportFormat,
+    symbols: &SymbolTable,
+) -> io::Result<()> {
+    match format {
+        ExportFormat::Cdl => writer.write_all(&log.to_cdl()),
+        ExportFormat::Html => write_html(writer, log, symbols),
+        ExportFormat::Png => {
+            let rows = (log.rom_size() + HEATMAP_WIDTH - 1) / HEATMAP_WIDTH;
+            let pixels: Vec<[u8; 3]> = (0..rows * HEATMAP_WIDTH)
+                .map(|offset| color(log.flags(offset)))
+                .collect();
+            png::write_rgb(writer, HEATMAP_WIDTH as u32, rows as u32, &pixels)
+        }
+    }
+}
+
+/// Return the color of a byte with `flags` in the heatmaps
+pub fn color(flags: u8) -> [u8; 3] {
+    let code = flags & CodeDataLog::CODE != 0;
+    let data = flags & CodeDataLog::DATA != 0;
+    match (code, data) {
+        (true, true) => [220, 200, 40],
+        (true, false) if flags & CodeDataLog::OPCODE != 0 => [40, 220, 40],
+        (true, false) => [20, 140, 20],
+        (false, true) => [60, 120, 255],
+        (false, false) if flags & CodeDataLog::DMA != 0 => [220, 100, 220],
+        (false, false) => [24, 24, 24],
+    }
+}
+
+fn write_html(writer: &mut impl Write, log: &CodeDataLog, symbols: &SymbolTable) -> io::Result<()> {
+    let total = Coverage::of(log, 0..log.rom_size());
+    writeln!(writer, "<!DOCTYPE html>")?;
+    writeln!(
+        writer,
+        "<html><head><meta charset=\"utf-8\"><title>ROM coverage</title>"
+    )?;
+    writeln!(
+        writer,
+        "<style>body{{font-family:monospace;background:#111;color:#ddd}}\
+         td,th{{padding:0 8px;text-align:right}}\
+         .map{{border-collapse:collapse}}.map td{{width:6px;height:6px;padding:0}}</style>"
+    )?;
+    writeln!(writer, "</head><body>")?;
+    writeln!(
+        writer,
+        "<h1>ROM coverage</h1><p>{} bytes, {:.1}% executed, {:.1}% read as data</p>",
+        total.size,
+        total.code_percent(),
+        total.data_percent()
+    )?;
+
+    writeln!(writer, "<h2>Banks</h2><table>")?;
+    writeln!(
+        writer,
+        "<tr><th>Bank</th><th>Code</th><th>Data</th><th>Unused</th><th>Map</th></tr>"
+    )?;
+    for (bank, coverage) in banks(log).iter().enumerate() {
+        write!(
+            writer,
+            "<tr><td>{:02X}</td><td>{:.1}%</td><td>{:.1}%</td><td>{}</td><td><table class=\"map\"><tr>",
+            bank,
+            coverage.code_percent(),
+            coverage.data_percent(),
+            coverage.unused()
+        )?;
+        let start = bank * BANK_SIZE;
+        let end = (start + BANK_SIZE).min(log.rom_size());
+        for block in (start..end).step_by(HTML_BLOCK) {
+            let coverage = Coverage::of(log, block..(block + HTML_BLOCK).min(end));
+            let shade = |count: usize| 24 + count * 231 / coverage.size;
+            write!(
+                writer,
+                "<td style=\"background:rgb({},{},{})\" title=\"{:02X}:{:04X} code {} data {}\"></td>",
+                24,
+                shade(coverage.code),
+                shade(coverage.data),
+                bank,
+                block - start + if bank == 0 { 0 } else { BANK_SIZE },
+                coverage.code,
+                coverage.data
+            )?;
+        }
+        writeln!(writer, "</tr></table></td></tr>")?;
+    }
+    writeln!(writer, "</table>")?;
+
+    let routines = routines(log, symbols);
+    if !routines.is_empty() {
+        writeln!(writer, "<h2>Labels</h2><table>")?;
+        writeln!(
+            writer,
+            "<tr><th>Label</th><th>Address</th><th>Size</th><th>Executed</th></tr>"
+        )?;
+        for routine in routines {
+            writeln!(
+                writer,
+                "<tr><td style=\"text-align:left\">{}</td><td>{:02X}:{:04X}</td><td>{}</td><td>{:.1}%</td></tr>",
+                escape(&routine.name),
+                routine.bank,
+                routine.address,
+                routine.coverage.size,
+                routine.coverage.code_percent()
+            )?;
+        }
+        writeln!(writer, "</table>")?;
+    }
+    writeln!(writer, "</body></html>")
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn percent(part: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 * 100.0 / total as f64
+    }
+}
+
+#[cfg(test)]
+mod test_coverage {
+    use super::{banks, export, routines, Coverage, ExportFormat};
+    use crate::symbols::SymbolTable;
+    use gb_roms::code_data_log::CodeDataLog;
+
+    fn log() -> CodeDataLog {
+        let log = CodeDataLog::new(0x8000);
+        log.mark(0x150, CodeDataLog::CODE | CodeDataLog::OPCODE);
+        log.mark(0x151, CodeDataLog::CODE | CodeDataLog::OPERAND);
+        log.mark(0x152, CodeDataLog::DATA);
+        log.mark(0x4000, CodeDataLog::DMA);
+        log
+    }
+
+    #[test]
+    fn coverage() {
+        let log = log();
+        assert_eq!(
+            banks(&log),
+            vec![
+                Coverage {
+                    size: 0x4000,
+                    code: 2,
+                    data: 1
+                },
+                Coverage {
+                    size: 0x4000,
+                    code: 0,
+                    data: 1
+                }
+            ]
+        );
+
+        let symbols =
+            SymbolTable::parse("00:0150 Main\n00:0151 Main.loop\n00:0154 Other\n".as_bytes())
+                .unwrap();
+        let routines = routines(&log, &symbols);
+        assert_eq!(routines.len(), 2);
+        assert_eq!(routines[0].name, "Main");
+        assert_eq!(
+            routines[0].coverage,
+            Coverage {
+                size: 4,
+                code: 2,
+                data: 1
+            }
+        );
+        assert_eq!(routines[1].coverage.size, 0x4000 - 0x154);
+    }
+
+    #[test]
+    fn exports() {
+        let log = log();
+        let symbols = SymbolTable::default();
+
+        let mut cdl = Vec::new();
+        export(&mut cdl, &log, ExportFormat::Cdl, &symbols).unwrap();
+        assert_eq!(cdl.len(), 0x8000);
+        assert_eq!(&cdl[0x150..0x153], &[0x01, 0x01, 0x02]);
+        assert_eq!(cdl[0x4000], 0x02);
+
+        let mut html = Vec::new();
+        export(&mut html, &log, ExportFormat::Html, &symbols).unwrap();
+        let html = String::from_utf8(html).unwrap();
+        assert!(html.contains("<td>01</td>"));
+        assert!(html.ends_with("</body></html>\n"));
+
+        let mut png = Vec::new();
+        export(&mut png, &log, ExportFormat::Png, &symbols).unwrap();
+        // 256 pixels by 128 rows
+        assert_eq!(&png[16..24], &[0, 0, 1, 0, 0, 0, 0, 128]);
+    }
+}
@@ -1,6 +1,16 @@
+use std::cell::Ref;
 use std::fmt::{self, Debug, Display, Formatter};
 
+use crate::audio::{ChannelState, ScopePoint};
+use crate::profiler::Profiler;
+use crate::symbols::SymbolTable;
+use crate::timeline::Timeline;
+use crate::trace::Tracer;
 use gb_breakpoint::register::Register;
+use gb_bus::watchpoint::Owner;
+use gb_bus::{Watchpoint, WatchpointHit};
+use gb_cpu::microcode::call_stack::CallStack;
+use gb_roms::code_data_log::CodeDataLog;
 
 pub struct RegisterMap<T: Display + Debug>(pub T, pub RegisterValue);
 
@@ -31,17 +41,196 @@ impl From<RegisterValue> for u16 {
     }
 }
 
-pub trait DebugOperations: MemoryDebugOperations + RegisterDebugOperations {
+pub trait DebugOperations:
+    MemoryDebugOperations + RegisterDebugOperations + WatchpointDebugOperations
+{
     fn cycle(&self) -> usize;
+
+    /// Indicate if the emulation is paused
+    fn is_stopped(&self) -> bool;
+
+    /// Return the ROM bank mapped at `4000-7FFF`
+    fn rom_bank(&self) -> usize;
+
+    /// Return the external RAM bank mapped at `A000-BFFF`
+    fn ram_bank(&self) -> usize;
+
+    /// Return the number of ROM banks of the cartridge
+    fn rom_bank_count(&self) -> usize;
+
+    /// Read a byte of the ROM bank `bank` without going through the MBC,
+    /// `offset` is relative to the start of the bank
+    fn read_rom(&self, bank: usize, offset: u16) -> u8;
+
+    /// Return the number of banks of `area`, 0 when the area doesn't exist
+    fn bank_count(&self, area: BankedArea) -> usize;
+
+    /// Read a byte of the bank `bank` of `area` regardless of the bank currently mapped,
+    /// `offset` is relative to the start of the area
+    fn read_bank(&self, area: BankedArea, bank: usize, offset: u16) -> u8;
+
+    /// Return the last frame drawn on the screen
+    fn screen(&self) -> Screen;
+
+    /// Return the labels loaded for the current ROM
+    fn symbols(&self) -> &SymbolTable;
+
+    /// Return the calls tracked by the cpu
+    fn call_stack(&self) -> &CallStack;
+
+    /// Return the execution tracer
+    fn tracer(&mut self) -> &mut Tracer;
+
+    /// Return the recorder of the hardware events of each frame
+    fn timeline(&mut self) -> &mut Timeline;
+
+    /// Return the profiler attributing the cycles to the routines
+    fn profiler(&mut self) -> &mut Profiler;
+
+    /// Return the number of instructions executed since the power on
+    fn instruction_count(&self) -> u64;
+
+    /// Return the instruction of the last snapshot taken before `instruction`
+    fn snapshot_before(&self, instruction: u64) -> Option<u64>;
+
+    /// Bring the emulation back to the end of `instruction` and pause it,
+    /// return `false` when the instruction is older than the history
+    fn rewind(&mut self, instruction: u64) -> bool;
+
+    /// Execute one instruction again after a rewind
+    fn step_instruction(&mut self);
+
+    /// Return the bytes written at the end of each frame to freeze their value
+    fn frozen(&self) -> &[(u16, u8)];
+
+    /// Replace the bytes written at the end of each frame
+    fn set_frozen(&mut self, frozen: Vec<(u16, u8)>);
+
+    /// Return the usage of the ROM bytes, `None` until the logging is started
+    fn code_data_log(&self) -> Option<Ref<'_, CodeDataLog>>;
+
+    /// Start or stop recording the usage of the ROM bytes
+    fn set_code_data_logging(&mut self, recording: bool);
+
+    /// Forget the usage of the ROM bytes logged so far
+    fn clear_code_data_log(&mut self);
+
+    /// Return the state of the sound channels, in the order of their registers
+    fn audio_channels(&self) -> Vec<ChannelState>;
+
+    /// Leave a sound channel out of the mix
+    fn set_channel_muted(&mut self, channel: usize, muted: bool);
+
+    /// Only mix the soloed channels, all channels are mixed when none is soloed
+    fn set_channel_soloed(&mut self, channel: usize, soloed: bool);
+
+    /// Start or stop keeping the latest outputs of the sound channels
+    fn set_audio_scope(&mut self, enabled: bool);
+
+    /// Return the latest outputs of the sound channels and of the mix, the oldest first
+    fn audio_scope(&self) -> Vec<ScopePoint>;
+}
+
+/// Memory which can be read bank by bank, including the banks not mapped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BankedArea {
+    Rom,
+    CartRam,
+    Vram,
+    Wram,
+    Oam,
+    WaveRam,
+    BgPalettes,
+    ObjPalettes,
+}
+
+impl BankedArea {
+    pub const ALL: [BankedArea; 8] = [
+        BankedArea::Rom,
+        BankedArea::CartRam,
+        BankedArea::Vram,
+        BankedArea::Wram,
+        BankedArea::Oam,
+        BankedArea::WaveRam,
+        BankedArea::BgPalettes,
+        BankedArea::ObjPalettes,
+    ];
+
+    /// Return the address where `bank` is mapped, the palettes aren't mapped
+    /// so their bytes are numbered from 0
+    pub fn start(self, bank: usize) -> u16 {
+        match self {
+            BankedArea::Rom if bank == 0 => 0x0000,
+            BankedArea::Rom => 0x4000,
+            BankedArea::CartRam => 0xa000,
+            BankedArea::Vram => 0x8000,
+            BankedArea::Wram if bank == 0 => 0xc000,
+            BankedArea::Wram => 0xd000,
+            BankedArea::Oam => 0xfe00,
+            BankedArea::WaveRam => 0xff30,
+            BankedArea::BgPalettes | BankedArea::ObjPalettes => 0x0000,
+        }
+    }
+
+    /// Return the size of a bank
+    pub fn size(self) -> u16 {
+        match self {
+            BankedArea::Rom => 0x4000,
+            BankedArea::CartRam | BankedArea::Vram => 0x2000,
+            BankedArea::Wram => 0x1000,
+            BankedArea::Oam => 0xa0,
+            BankedArea::WaveRam => 0x10,
+            BankedArea::BgPalettes | BankedArea::ObjPalettes => 0x40,
+        }
+    }
+}
+
+impl Display for BankedArea {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            BankedArea::Rom => "ROM",
+            BankedArea::CartRam => "Cartridge RAM",
+            BankedArea::Vram => "VRAM",
+            BankedArea::Wram => "WRAM",
+            BankedArea::Oam => "OAM",
+            BankedArea::WaveRam => "Wave RAM",
+            BankedArea::BgPalettes => "BG palettes",
+            BankedArea::ObjPalettes => "OBJ palettes",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Image of the screen, the pixels are stored row after row
+pub struct Screen {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<[u8; 3]>,
 }
 
 pub trait MemoryDebugOperations {
     fn read(&self, index: u16) -> u8;
+
+    /// Write `value` at `index` as the cpu would do
+    fn write(&mut self, index: u16, value: u8);
+}
+
+pub trait WatchpointDebugOperations {
+    /// Return the watchpoints of `owner` currently installed on the address bus
+    fn watchpoints(&self, owner: Owner) -> &[Watchpoint];
+
+    /// Replace the watchpoints of `owner` installed on the address bus
+    fn set_watchpoints(&mut self, owner: Owner, watchpoints: Vec<Watchpoint>);
+
+    /// Return the watchpoints of `owner` triggered since the last call
+    fn take_watchpoint_hits(&mut self, owner: Owner) -> Vec<WatchpointHit>;
 }
 
 pub trait RegisterDebugOperations {
     fn cpu_get(&self, key: CpuRegs) -> RegisterValue;
 
+    fn cpu_set(&mut self, key: CpuRegs, value: u16);
+
     fn ppu_get(&self, key: PpuRegs) -> RegisterValue;
 
     fn io_get(&self, key: IORegs) -> RegisterValue;
@@ -92,6 +281,7 @@ impl TryFrom<Register> for CpuRegs {
             Register::HL => Ok(CpuRegs::HL),
             Register::SP => Ok(CpuRegs::SP),
             Register::PC => Ok(CpuRegs::PC),
+            _ => Err(format!("{} is not a 16-bit register", s)),
         }
     }
 }
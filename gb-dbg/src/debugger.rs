@@ -1,23 +1,55 @@
-mod breakpoints;
+mod audio;
+pub(crate) mod breakpoints;
+mod call_stack;
+mod console;
+mod coverage;
 pub mod disassembler;
 pub mod flow_control;
 pub mod memory;
 pub mod options;
+mod profiler;
+mod ram_search;
 pub mod registers;
 mod status_bar;
+mod timeline;
+mod trace;
+mod watchpoints;
 
 use crate::dbg_interfaces::{CpuRegs, DebugOperations};
+use crate::debugger::audio::AudioViewer;
+use crate::debugger::breakpoints::breakpoint::Breakpoint;
+use crate::debugger::breakpoints::evaluation::{compute_expression, set_register};
 use crate::debugger::breakpoints::BreakpointEditor;
-use crate::debugger::disassembler::DisassemblyViewer;
-use crate::debugger::flow_control::FlowController;
+use crate::debugger::call_stack::CallStackViewer;
+use crate::debugger::console::command::{Command, HELP};
+use crate::debugger::console::Console;
+use crate::debugger::coverage::CoverageViewer;
+use crate::debugger::disassembler::{DisassemblyViewer, ListingAction};
+use crate::debugger::flow_control::{FlowController, Reverse};
 use crate::debugger::memory::MemoryViewer;
 use crate::debugger::options::DebuggerOptions;
+use crate::debugger::profiler::ProfilerViewer;
+use crate::debugger::ram_search::RamSearch;
 use crate::debugger::registers::RegisterEditor;
 use crate::debugger::status_bar::StatusBar;
+use crate::debugger::timeline::TimelineViewer;
+use crate::debugger::trace::TraceEditor;
+use crate::debugger::watchpoints::WatchpointEditor;
 use crate::until::Until;
 use egui::style::Margin;
 use egui::{vec2, Color32, Context, Style, Vec2};
+use gb_breakpoint::Node;
+use gb_bus::watchpoint::{Owner, Trigger};
+use gb_bus::Watchpoint;
 use std::ops::ControlFlow;
+use std::path::PathBuf;
+
+/// Save state operation requested from the console, without a path the default file is used
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StateRequest {
+    Save(Option<PathBuf>),
+    Load(Option<PathBuf>),
+}
 
 pub struct Debugger {
     memory_editor: MemoryViewer,
@@ -25,9 +57,19 @@ pub struct Debugger {
     flow_controller: FlowController,
     pub disassembler: DisassemblyViewer,
     breakpoint_editor: BreakpointEditor,
+    watchpoint_editor: WatchpointEditor,
+    call_stack_viewer: CallStackViewer,
+    trace_editor: TraceEditor,
+    timeline_viewer: TimelineViewer,
+    ram_search: RamSearch,
+    profiler_viewer: ProfilerViewer,
+    coverage_viewer: CoverageViewer,
+    audio_viewer: AudioViewer,
+    console: Console,
     status_bar: StatusBar,
     flow_status: Option<ControlFlow<Until>>,
     pub reset_triggered: bool,
+    pub state_request: Option<StateRequest>,
 }
 
 impl Debugger {
@@ -45,9 +87,8 @@ impl Debugger {
         style.visuals.override_text_color = Some(Color32::WHITE);
         ui_ctx.set_style(style);
 
-        // Update cache
-        self.disassembler
-            .may_update_cache(game_ctx.cpu_get(CpuRegs::PC).into(), game_ctx);
+        // Update the disassembly
+        self.disassembler.update(game_ctx);
 
         egui::SidePanel::left("left_panel")
             .frame(egui::Frame {
@@ -68,8 +109,19 @@ impl Debugger {
             })
             .default_width(350.0)
             .resizable(false)
-            .show(ui_ctx, |ui| self.breakpoint_editor.draw(ui, game_ctx));
+            .show(ui_ctx, |ui| {
+                self.breakpoint_editor.draw(ui, game_ctx);
+                ui.separator();
+                self.watchpoint_editor.draw(ui, game_ctx);
+                ui.separator();
+                if let Some((bank, address)) = self.call_stack_viewer.draw(ui, game_ctx) {
+                    self.disassembler.goto(Some(bank), address);
+                }
+                ui.separator();
+                self.trace_editor.draw(ui, game_ctx);
+            });
 
+        let mut reverse = None;
         egui::TopBottomPanel::top("top_panel")
             .frame(egui::Frame {
                 margin: Margin::from(vec2(8., 8.)),
@@ -86,9 +138,46 @@ impl Debugger {
                     }
                     ui.separator();
                     self.flow_status = self.flow_controller.draw(ui);
+                    ui.separator();
+                    reverse = self.flow_controller.draw_reverse(ui);
+                    ui.separator();
+                    if ui
+                        .selectable_label(self.timeline_viewer.open, "Timeline")
+                        .clicked()
+                    {
+                        self.timeline_viewer.open = !self.timeline_viewer.open;
+                    }
+                    if ui
+                        .selectable_label(self.ram_search.open, "RAM Search")
+                        .clicked()
+                    {
+                        self.ram_search.open = !self.ram_search.open;
+                    }
+                    if ui
+                        .selectable_label(self.profiler_viewer.open, "Profiler")
+                        .clicked()
+                    {
+                        self.profiler_viewer.open = !self.profiler_viewer.open;
+                    }
+                    if ui
+                        .selectable_label(self.coverage_viewer.open, "Code/Data Logger")
+                        .clicked()
+                    {
+                        self.coverage_viewer.open = !self.coverage_viewer.open;
+                    }
+                    if ui
+                        .selectable_label(self.audio_viewer.open, "Audio")
+                        .clicked()
+                    {
+                        self.audio_viewer.open = !self.audio_viewer.open;
+                    }
+                    if ui.selectable_label(self.console.open, "Console").clicked() {
+                        self.console.open = !self.console.open;
+                    }
                 });
             });
 
+        let mut listing_action = None;
         egui::CentralPanel::default()
             .frame(egui::Frame {
                 margin: Margin::from(vec2(16., 16.)),
@@ -99,7 +188,10 @@ impl Debugger {
                 egui::Grid::new("main_panel")
                     .spacing(Vec2::new(16., 16.))
                     .show(ui, |ui| {
-                        self.disassembler.draw(ui);
+                        let breakpoint_editor = &self.breakpoint_editor;
+                        listing_action = self
+                            .disassembler
+                            .draw(ui, game_ctx, |expr| breakpoint_editor.has_breakpoint(expr));
                         ui.end_row();
 
                         self.status_bar.draw(ui, game_ctx, info);
@@ -108,6 +200,247 @@ impl Debugger {
                         self.register_editor.draw(ui, game_ctx);
                     });
             });
+        match listing_action {
+            Some(ListingAction::ToggleBreakpoint(expr)) => {
+                self.breakpoint_editor.toggle_breakpoint(expr)
+            }
+            Some(ListingAction::RunTo(address)) => {
+                self.flow_status = Some(ControlFlow::Break(Until::RunTo(address)))
+            }
+            None => {}
+        }
+
+        let reversed = match reverse {
+            Some(Reverse::Step) => Some(self.step_back(game_ctx)),
+            Some(Reverse::Continue) => Some(self.reverse_continue(game_ctx)),
+            None => None,
+        };
+        match reversed {
+            Some(Ok(message)) => log::info!("{}", message),
+            Some(Err(e)) => log::warn!("{}", e),
+            None => {}
+        }
+
+        self.timeline_viewer.draw(ui_ctx, game_ctx);
+        self.profiler_viewer.draw(ui_ctx, game_ctx);
+        self.coverage_viewer.draw(ui_ctx, game_ctx);
+        self.audio_viewer.draw(ui_ctx, game_ctx);
+        if let Some(watchpoint) = self.ram_search.draw(ui_ctx, game_ctx) {
+            self.watchpoint_editor.add_watchpoint(watchpoint);
+        }
+
+        if let Some(line) = self.console.draw(ui_ctx) {
+            self.run_command(&line, game_ctx);
+        }
+        // the script wait for the emulation to stop before running the next commands
+        while game_ctx.is_stopped() && self.flow_status.is_none() {
+            match self.console.next_script_line() {
+                Some(line) => self.run_command(&line, game_ctx),
+                None => break,
+            }
+        }
+    }
+
+    fn run_command<DBGOPS: DebugOperations>(&mut self, line: &str, dbg: &mut DBGOPS) {
+        self.console.echo(line);
+        match line
+            .parse::<Command>()
+            .and_then(|command| self.execute(command, dbg))
+        {
+            Ok(Some(output)) => self.console.print(&output),
+            Ok(None) => {}
+            Err(e) => self.console.error(&e),
+        }
+    }
+
+    fn execute<DBGOPS: DebugOperations>(
+        &mut self,
+        command: Command,
+        dbg: &mut DBGOPS,
+    ) -> Result<Option<String>, String> {
+        let output = match command {
+            Command::Break(expr) => {
+                let breakpoint = Breakpoint::from_node(expr);
+                let unknown = breakpoint.unknown_labels(dbg);
+                let output = if unknown.is_empty() {
+                    format!("breakpoint {}", breakpoint)
+                } else {
+                    format!(
+                        "breakpoint {}, unknown labels: {}",
+                        breakpoint,
+                        unknown.join(", ")
+                    )
+                };
+                self.breakpoint_editor.add_breakpoint(breakpoint);
+                output
+            }
+            Command::Watch(range, access) => {
+                let watchpoint = Watchpoint::new(range, access, Trigger::Any);
+                let output = format!("watchpoint {}", watchpoint);
+                self.watchpoint_editor.add_watchpoint(watchpoint);
+                output
+            }
+            Command::Continue => {
+                self.flow_status = Some(ControlFlow::Continue(()));
+                return Ok(None);
+            }
+            Command::Pause => {
+                self.flow_status = Some(ControlFlow::Break(Until::Null));
+                return Ok(None);
+            }
+            Command::Flow(until) => {
+                self.flow_status = Some(ControlFlow::Break(until));
+                return Ok(None);
+            }
+            Command::RunTo(expr) => {
+                let address = compute_expression(&expr, dbg, None);
+                self.flow_status = Some(ControlFlow::Break(Until::RunTo(address)));
+                format!("run to {:04X}", address)
+            }
+            Command::Examine(expr, count) => {
+                let address = compute_expression(&expr, dbg, None);
+                (0..count)
+                    .step_by(8)
+                    .map(|offset| {
+                        let start = address.wrapping_add(offset as u16);
+                        let bytes: Vec<String> = (0..(count - offset).min(8))
+                            .map(|i| format!("{:02X}", dbg.read(start.wrapping_add(i as u16))))
+                            .collect();
+                        format!("{:04X}: {}", start, bytes.join(" "))
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            }
+            Command::Print(expr) => {
+                let value = compute_expression(&expr, dbg, None);
+                format!("{} = {:#06X} ({})", expr, value, value)
+            }
+            Command::Set(target, expr) => {
+                let value = compute_expression(&expr, dbg, None);
+                match &target {
+                    Node::Register(register) => set_register(*register, value, dbg),
+                    Node::IoRegister(register) => dbg.write(register.address(), value as u8),
+                    Node::Address(address) => {
+                        let address = compute_expression(address, dbg, None);
+                        dbg.write(address, value as u8)
+                    }
+                    _ => return Err(format!("cannot assign to `{}`", target)),
+                }
+                format!(
+                    "{} = {:#06X}",
+                    target,
+                    compute_expression(&target, dbg, None)
+                )
+            }
+            Command::Trace(true) => {
+                let tracer = dbg.tracer();
+                tracer.start();
+                format!("tracing to {}", tracer.path().display())
+            }
+            Command::Trace(false) => {
+                dbg.tracer().stop();
+                "trace stopped".to_owned()
+            }
+            Command::SaveState(path) => {
+                self.state_request = Some(StateRequest::Save(path));
+                return Ok(None);
+            }
+            Command::LoadState(path) => {
+                self.state_request = Some(StateRequest::Load(path));
+                return Ok(None);
+            }
+            Command::Reset => {
+                self.reset_triggered = true;
+                return Ok(None);
+            }
+            Command::ReverseStep => self.step_back(dbg)?,
+            Command::ReverseContinue => self.reverse_continue(dbg)?,
+            Command::Help => HELP.to_owned(),
+        };
+        Ok(Some(output))
+    }
+
+    /// Bring the emulation back to the end of the previous instruction
+    fn step_back<DBGOPS: DebugOperations>(&mut self, dbg: &mut DBGOPS) -> Result<String, String> {
+        match dbg.instruction_count().checked_sub(1) {
+            Some(instruction) => self.rewind(dbg, instruction),
+            None => Err("no instruction executed yet".to_owned()),
+        }
+    }
+
+    /// Bring the emulation back to the last instruction that reached a breakpoint
+    /// or a watchpoint. The history is searched backward, from one snapshot to the previous one.
+    fn reverse_continue<DBGOPS: DebugOperations>(
+        &mut self,
+        dbg: &mut DBGOPS,
+    ) -> Result<String, String> {
+        let now = dbg.instruction_count();
+        let mut end = now;
+        let mut oldest = None;
+        while let Some(start) = dbg.snapshot_before(end) {
+            if let Some(hit) = self.last_hit(dbg, start, end, now) {
+                return self.rewind(dbg, hit);
+            }
+            oldest = Some(start);
+            end = start;
+        }
+        match oldest {
+            Some(start) => self
+                .rewind(dbg, start)
+                .map(|message| format!("no breakpoint reached in the history, {}", message)),
+            None => Err("no history to go back to".to_owned()),
+        }
+    }
+
+    /// Replay the instructions from `start` to `end` and return the last one
+    /// before `now` that reached a breakpoint or a watchpoint
+    fn last_hit<DBGOPS: DebugOperations>(
+        &self,
+        dbg: &mut DBGOPS,
+        start: u64,
+        end: u64,
+        now: u64,
+    ) -> Option<u64> {
+        if !dbg.rewind(start) {
+            return None;
+        }
+        let mut reached = self.breakpoint_editor.reached(dbg);
+        let mut hit = None;
+        while dbg.instruction_count() < end {
+            dbg.step_instruction();
+            let instruction = dbg.instruction_count();
+            let watched = !dbg.take_watchpoint_hits(Owner::Debugger).is_empty();
+            let current = self.breakpoint_editor.reached(dbg);
+            let triggered = current
+                .iter()
+                .zip(&reached)
+                .any(|(current, previous)| *current && !*previous);
+            if (watched || triggered) && instruction < now {
+                hit = Some(instruction);
+            }
+            reached = current;
+        }
+        hit
+    }
+
+    fn rewind<DBGOPS: DebugOperations>(
+        &mut self,
+        dbg: &mut DBGOPS,
+        instruction: u64,
+    ) -> Result<String, String> {
+        if !dbg.rewind(instruction) {
+            return Err(format!(
+                "instruction {} is older than the history",
+                instruction
+            ));
+        }
+        self.breakpoint_editor.sync(dbg);
+        self.flow_status = Some(ControlFlow::Break(Until::Null));
+        Ok(format!(
+            "back to instruction {}, PC={:04X}",
+            instruction,
+            u16::from(dbg.cpu_get(CpuRegs::PC))
+        ))
     }
 
     pub fn flow_status(&mut self) -> Option<ControlFlow<Until>> {
@@ -116,9 +449,10 @@ impl Debugger {
 
     pub fn updated_flow_status<DBGOPS: DebugOperations>(
         &mut self,
-        memory: &DBGOPS,
+        memory: &mut DBGOPS,
     ) -> Option<ControlFlow<Until>> {
-        if self.breakpoint_editor.are_breakpoints_triggered(memory) {
+        let watchpoint_triggered = self.watchpoint_editor.are_watchpoints_triggered(memory);
+        if self.breakpoint_editor.are_breakpoints_triggered(memory) || watchpoint_triggered {
             Some(ControlFlow::Break(Until::Null))
         } else {
             self.flow_status()
@@ -147,17 +481,26 @@ impl DebuggerBuilder {
     }
 
     pub fn build(self) -> Debugger {
+        let options = self.options.unwrap_or_default();
         Debugger {
-            memory_editor: MemoryViewer::new(
-                self.options.clone().unwrap_or_default().address_ranges,
-            ),
-            register_editor: RegisterEditor,
-            flow_controller: FlowController,
+            memory_editor: MemoryViewer::new(options.address_ranges),
+            register_editor: RegisterEditor::default(),
+            flow_controller: FlowController::default(),
             disassembler: DisassemblyViewer::default(),
-            breakpoint_editor: BreakpointEditor::new(self.options.unwrap_or_default().breakpoints),
+            breakpoint_editor: BreakpointEditor::new(options.breakpoints),
+            watchpoint_editor: WatchpointEditor::default(),
+            call_stack_viewer: CallStackViewer,
+            trace_editor: TraceEditor::default(),
+            timeline_viewer: TimelineViewer::default(),
+            ram_search: RamSearch::default(),
+            profiler_viewer: ProfilerViewer::default(),
+            coverage_viewer: CoverageViewer::default(),
+            audio_viewer: AudioViewer::default(),
+            console: Console::new(options.script),
             status_bar: StatusBar,
             flow_status: None,
             reset_triggered: false,
+            state_request: None,
         }
     }
 }
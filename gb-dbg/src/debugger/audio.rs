@@ -0,0 +1,232 @@
+use crate::audio::{self, ChannelState, CHANNELS};
+use crate::dbg_interfaces::DebugOperations;
+use egui::{pos2, vec2, Color32, Context, Rect, RichText, Sense, Shape, Stroke, Ui};
+
+const SCOPE_WIDTH: f32 = 256.0;
+const SCOPE_HEIGHT: f32 = 48.0;
+/// Number of samples drawn by an oscilloscope, about 12ms at 44.1kHz
+const SCOPE_POINTS: usize = 512;
+const NAMES: [&str; CHANNELS] = ["CH1 Square", "CH2 Square", "CH3 Wave", "CH4 Noise"];
+const COLORS: [Color32; CHANNELS + 1] = [
+    Color32::from_rgb(240, 90, 90),
+    Color32::from_rgb(240, 190, 60),
+    Color32::from_rgb(80, 200, 120),
+    Color32::from_rgb(90, 150, 250),
+    Color32::from_gray(220),
+];
+
+/// Window showing the output and the state of each sound channel
+#[derive(Default, Debug)]
+pub struct AudioViewer {
+    pub open: bool,
+}
+
+impl AudioViewer {
+    pub fn draw<DBG: DebugOperations>(&mut self, ctx: &Context, dbg: &mut DBG) {
+        // the apu only keeps the outputs while they are shown
+        dbg.set_audio_scope(self.open);
+        let mut open = self.open;
+        egui::Window::new("Audio")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| self.draw_channels(ui, dbg));
+        self.open = open;
+    }
+
+    fn draw_channels<DBG: DebugOperations>(&mut self, ui: &mut Ui, dbg: &mut DBG) {
+        let channels = dbg.audio_channels();
+        let scope = dbg.audio_scope();
+        let line = |index: usize| -> Vec<f32> { scope.iter().map(|point| point[index]).collect() };
+
+        egui::Grid::new("audio_channels")
+            .spacing(vec2(12.0, 8.0))
+            .show(ui, |ui| {
+                for (i, channel) in channels.iter().enumerate() {
+                    ui.vertical(|ui| {
+                        ui.colored_label(COLORS[i], NAMES[i]);
+                        let mut muted = channel.muted;
+                        if ui.checkbox(&mut muted, "Mute").changed() {
+                            dbg.set_channel_muted(i, muted);
+                        }
+                        let mut soloed = channel.soloed;
+                        if ui.checkbox(&mut soloed, "Solo").changed() {
+                            dbg.set_channel_soloed(i, soloed);
+                        }
+                    });
+                    draw_scope(ui, &line(i), COLORS[i]);
+                    ui.vertical(|ui| {
+                        for text in describe(channel) {
+                            ui.label(RichText::new(text).monospace());
+                        }
+                    });
+                    ui.end_row();
+                }
+                ui.colored_label(COLORS[CHANNELS], "Mix");
+                draw_scope(ui, &line(CHANNELS), COLORS[CHANNELS]);
+                ui.end_row();
+            });
+
+        if let Some(wave_ram) = channels.get(2).and_then(|channel| channel.wave_ram) {
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label("Wave RAM");
+                ui.label(
+                    RichText::new(
+                        wave_ram
+                            .iter()
+                            .map(|byte| format!("{:02X}", byte))
+                            .collect::<Vec<_>>()
+                            .join(" "),
+                    )
+                    .monospace(),
+                );
+            });
+            draw_wave_ram(ui, &wave_ram);
+        }
+    }
+}
+
+/// Return the lines describing the state of `channel`
+fn describe(channel: &ChannelState) -> Vec<String> {
+    let status = match (channel.dac_enabled, channel.enabled) {
+        (false, _) => "DAC off",
+        (true, false) => "off",
+        (true, true) => "on",
+    };
+    let note = audio::note_name(channel.frequency).unwrap_or_default();
+    let mut lines = vec![match channel.period {
+        Some(period) => format!(
+            "{:<7} {:4.0} Hz {:<8} period {:03X}",
+            status, channel.frequency, note, period
+        ),
+        None => format!("{:<7} clock {:.0} Hz", status, channel.frequency),
+    }];
+
+    let mut details = Vec::new();
+    if let Some(duty) = channel.duty {
+        details.push(format!("duty {}", audio::duty_cycle(duty)));
+    }
+    if let Some(envelope) = channel.envelope {
+        details.push(format!(
+            "volume {:2} ({}{}, pace {})",
+            envelope.volume,
+            envelope.initial_volume,
+            if envelope.increase { '+' } else { '-' },
+            envelope.pace
+        ));
+    }
+    if let Some(level) = channel.wave_level {
+        details.push(format!(
+            "level {}",
+            ["mute", "100%", "50%", "25%"][usize::from(level & 0b11)]
+        ));
+    }
+    if let Some(short) = channel.short_lfsr {
+        details.push(format!("LFSR {} bits", if short { 7 } else { 15 }));
+    }
+    details.push(match channel.length {
+        Some(length) => format!("length {}", length),
+        None => "length off".to_owned(),
+    });
+    lines.push(details.join(", "));
+
+    if let Some(sweep) = channel.sweep {
+        lines.push(if sweep.enabled {
+            format!(
+                "sweep pace {}, {} shift {}, from {:03X}",
+                sweep.pace,
+                if sweep.decrease { "down" } else { "up" },
+                sweep.shift,
+                sweep.shadow_period
+            )
+        } else {
+            "sweep off".to_owned()
+        });
+    }
+    lines
+}
+
+/// Draw the last samples of an output, starting at a rising edge to keep the waves still
+fn draw_scope(ui: &mut Ui, samples: &[f32], color: Color32) {
+    let (response, painter) = ui.allocate_painter(vec2(SCOPE_WIDTH, SCOPE_HEIGHT), Sense::hover());
+    let rect = response.rect;
+    painter.rect_filled(rect, 0.0, Color32::from_gray(10));
+    painter.line_segment(
+        [
+            pos2(rect.min.x, rect.center().y),
+            pos2(rect.max.x, rect.center().y),
+        ],
+        Stroke::new(1.0, Color32::from_white_alpha(30)),
+    );
+
+    let start = audio::trigger(samples, SCOPE_POINTS);
+    let window = &samples[start..(start + SCOPE_POINTS).min(samples.len())];
+    if window.len() < 2 {
+        return;
+    }
+    let amplitude = SCOPE_HEIGHT / 2.0 - 1.0;
+    let points = window
+        .iter()
+        .enumerate()
+        .map(|(i, sample)| {
+            pos2(
+                rect.min.x + i as f32 * SCOPE_WIDTH / SCOPE_POINTS as f32,
+                rect.center().y - sample.clamp(-1.0, 1.0) * amplitude,
+            )
+        })
+        .collect();
+    painter.add(Shape::line(points, Stroke::new(1.0, color)));
+}
+
+/// Draw the 32 samples of the wave RAM as bars
+fn draw_wave_ram(ui: &mut Ui, wave_ram: &[u8; 16]) {
+    let (response, painter) = ui.allocate_painter(vec2(SCOPE_WIDTH, 32.0), Sense::hover());
+    let rect = response.rect;
+    painter.rect_filled(rect, 0.0, Color32::from_gray(10));
+    let width = SCOPE_WIDTH / 32.0;
+    let samples = wave_ram.iter().flat_map(|byte| [byte >> 4, byte & 0xf]);
+    for (i, sample) in samples.enumerate() {
+        let height = f32::from(sample) * 2.0;
+        let x = rect.min.x + i as f32 * width;
+        painter.rect_filled(
+            Rect::from_min_max(
+                pos2(x, rect.max.y - height),
+                pos2(x + width - 1.0, rect.max.y),
+            ),
+            0.0,
+            COLORS[2],
+        );
+    }
+}
+
+#[cfg(test)]
+mod test_audio_viewer {
+    use super::describe;
+    use crate::audio::{ChannelState, Envelope};
+
+    #[test]
+    fn square_channel() {
+        let channel = ChannelState {
+            enabled: true,
+            dac_enabled: true,
+            frequency: 440.0,
+            period: Some(0x6d6),
+            duty: Some(2),
+            envelope: Some(Envelope {
+                volume: 12,
+                initial_volume: 15,
+                increase: false,
+                pace: 3,
+            }),
+            length: None,
+            ..Default::default()
+        };
+        assert_eq!(
+            describe(&channel),
+            vec![
+                "on       440 Hz A4 +0    period 6D6".to_owned(),
+                "duty 50%, volume 12 (15-, pace 3), length off".to_owned()
+            ]
+        );
+    }
+}
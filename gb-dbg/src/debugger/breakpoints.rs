@@ -1,12 +1,18 @@
-mod breakpoint;
-mod evaluation;
+mod action;
+pub(crate) mod breakpoint;
+pub(crate) mod evaluation;
+mod settings;
 
 use crate::dbg_interfaces::DebugOperations;
 use crate::debugger::breakpoints::breakpoint::Breakpoint;
+use crate::debugger::breakpoints::settings::SettingsEditor;
 
 use egui::{Color32, RichText, Ui, Vec2};
+use gb_breakpoint::Node;
 
-const VALID_CHARS: &[char] = &['&', '|', '!', '=', '<', '>', '*', '%', '^', '(', ')'];
+const VALID_CHARS: &[char] = &[
+    '&', '|', '!', '=', '<', '>', '*', '%', '^', '(', ')', '+', '-', '_', '.',
+];
 
 #[derive(Default, Debug)]
 pub struct BreakpointOptions {
@@ -17,6 +23,7 @@ pub struct BreakpointOptions {
 pub struct BreakpointEditor {
     breakpoints: Vec<Breakpoint>,
     breakpoint_field: String,
+    settings: SettingsEditor,
     pub options: BreakpointOptions,
 }
 
@@ -25,6 +32,7 @@ impl Default for BreakpointEditor {
         Self {
             breakpoints: Vec::with_capacity(20),
             breakpoint_field: String::with_capacity(8),
+            settings: SettingsEditor::default(),
             options: Default::default(),
         }
     }
@@ -68,13 +76,15 @@ impl BreakpointEditor {
             }
 
             let mut deletion_list: Vec<usize> = Vec::with_capacity(20);
+            let mut selection = None;
             egui::Grid::new("breakpoints_".to_owned())
                 .striped(true)
-                .spacing(Vec2::new(47.0, 7.0))
+                .spacing(Vec2::new(32.0, 7.0))
                 .show(ui, |ui| {
                     ui.label("Delete");
                     ui.label("Enabled");
                     ui.label("Condition");
+                    ui.label("Hits");
                     ui.end_row();
 
                     for (i, breakpoint) in &mut self.breakpoints.iter_mut().enumerate() {
@@ -82,23 +92,67 @@ impl BreakpointEditor {
                             deletion_list.push(i)
                         }
                         ui.checkbox(&mut breakpoint.enabled, "");
-                        if breakpoint.is_triggered(regs) {
-                            ui.label(
-                                RichText::new(breakpoint.to_string().clone()).color(Color32::RED),
-                            );
-                        } else {
-                            ui.add(egui::Label::new(breakpoint.to_string().clone()));
+                        let unknown_labels = breakpoint.unknown_labels(regs);
+                        let mut condition = RichText::new(breakpoint.to_string());
+                        let mut hover_text = "edit the actions of the breakpoint".to_owned();
+                        if !unknown_labels.is_empty() {
+                            condition = condition.color(Color32::YELLOW);
+                            hover_text = format!("unknown labels: {}", unknown_labels.join(", "));
+                        } else if breakpoint.is_triggered(regs) {
+                            condition = condition.color(Color32::RED);
                         }
+                        let is_selected = self.settings.selected == Some(i);
+                        if ui
+                            .selectable_label(is_selected, condition)
+                            .on_hover_text(hover_text)
+                            .clicked()
+                        {
+                            selection = Some(i);
+                        }
+                        ui.label(breakpoint.hits.to_string());
                         ui.end_row();
                     }
                     ui.end_row();
                 });
-            deletion_list.into_iter().for_each(|i| {
+            if !deletion_list.is_empty() {
+                self.settings.selected = None;
+            }
+            deletion_list.into_iter().rev().for_each(|i| {
                 self.breakpoints.remove(i);
             });
+            if let Some(i) = selection {
+                self.settings.select(i, &self.breakpoints[i]);
+            }
+            if let Some(breakpoint) = self
+                .settings
+                .selected
+                .and_then(|i| self.breakpoints.get_mut(i))
+            {
+                ui.separator();
+                self.settings.draw(ui, breakpoint);
+            }
         });
     }
 
+    /// Indicate if a breakpoint use exactly the expression `expr`
+    pub fn has_breakpoint(&self, expr: &Node) -> bool {
+        self.breakpoints.iter().any(|b| b.expr() == expr)
+    }
+
+    /// Remove the breakpoints using the expression `expr`, or add one if there is none
+    pub fn toggle_breakpoint(&mut self, expr: Node) {
+        if self.has_breakpoint(&expr) {
+            self.breakpoints.retain(|b| b.expr() != &expr);
+            self.settings.selected = None;
+        } else {
+            self.breakpoints.push(Breakpoint::from_node(expr));
+        }
+    }
+
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) {
+        self.breakpoints.push(breakpoint);
+    }
+
     fn add_address_breakpoint(&mut self, address: u16) {
         self.breakpoints.push(Breakpoint::from_address(address));
     }
@@ -109,14 +163,30 @@ impl BreakpointEditor {
         Ok(())
     }
 
-    pub fn are_breakpoints_triggered<DBG: DebugOperations>(&mut self, context: &DBG) -> bool {
+    /// Run the actions of the triggered breakpoints,
+    /// return `true` when one of them need to stop the execution
+    pub fn are_breakpoints_triggered<DBG: DebugOperations>(&mut self, context: &mut DBG) -> bool {
+        let mut stop = false;
         for breakpoint in &mut self.breakpoints {
-            if breakpoint.active(context) {
-                log::debug!("breakpoint triggered break={:?}", breakpoint);
-                return true;
-            }
+            stop |= breakpoint.fire(context);
+        }
+        stop
+    }
+
+    /// Return for each breakpoint if its expression is true
+    pub fn reached<DBG: DebugOperations>(&self, context: &DBG) -> Vec<bool> {
+        self.breakpoints
+            .iter()
+            .map(|breakpoint| breakpoint.is_reached(context))
+            .collect()
+    }
+
+    /// Align the breakpoints on the machine after a rewind,
+    /// so they don't trigger on the instruction where the emulation was brought back
+    pub fn sync<DBG: DebugOperations>(&mut self, context: &DBG) {
+        for breakpoint in &mut self.breakpoints {
+            breakpoint.sync(context);
         }
-        false
     }
 
     fn draw_advanced_breakpoint_widget(&mut self, ui: &mut Ui) {
@@ -128,7 +198,7 @@ impl BreakpointEditor {
             let text_field_response = ui.add(
                 egui::TextEdit::multiline(&mut self.breakpoint_field)
                     .desired_width(150.0)
-                    .hint_text("AF == 0x80"),
+                    .hint_text("PC == 4000 && BANK == 3"),
             );
             if add_button_response.clicked()
                 || (text_field_response.clicked()
@@ -201,8 +271,7 @@ fn is_valid_address(address: &str) -> bool {
 fn is_valid_expression(expr: &str) -> bool {
     expr.chars()
         .all(|c| c.is_alphanumeric() || c.is_whitespace() || VALID_CHARS.contains(&c))
-        && !expr.is_empty()
-        && expr.split_whitespace().count() % 2 != 0
+        && !expr.trim().is_empty()
 }
 
 fn is_enter_not_modified(ui: &Ui) -> bool {
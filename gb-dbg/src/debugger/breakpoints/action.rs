@@ -0,0 +1,201 @@
+use gb_breakpoint::Node;
+use std::{
+    fmt::{self, Display},
+    fs::File,
+    io::{self, Write},
+    ops::RangeInclusive,
+    str::FromStr,
+};
+
+use crate::dbg_interfaces::{DebugOperations, Screen};
+
+use super::evaluation::compute_expression;
+
+/// Text with interpolated expressions, like `hp={*C0A2:d} pc={PC}`
+///
+/// The expressions are written in hexadecimal, unless suffixed by `:d`.
+/// Use `{{` and `}}` to write literal braces.
+#[derive(Debug, Clone)]
+pub struct Message {
+    source: String,
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone)]
+enum Part {
+    Text(String),
+    Expr { node: Node, decimal: bool },
+}
+
+impl FromStr for Message {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = s.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let mut expr = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => expr.push(c),
+                            None => return Err("unmatched `{`".to_owned()),
+                        }
+                    }
+                    let (expr, decimal) = match expr.rsplit_once(':') {
+                        Some((expr, "d")) => (expr, true),
+                        Some((expr, "x")) => (expr, false),
+                        Some((_, spec)) => return Err(format!("unknown format `{}`", spec)),
+                        None => (expr.as_str(), false),
+                    };
+                    let node = Node::from_str(expr).map_err(|e| format!("{{{}}}: {}", expr, e))?;
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(Part::Expr { node, decimal });
+                }
+                '}' => return Err("unmatched `}`".to_owned()),
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+        Ok(Self {
+            source: s.to_owned(),
+            parts,
+        })
+    }
+}
+
+impl Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+impl Message {
+    /// Replace the expressions of the message by their current value
+    pub fn format<DBG: DebugOperations>(&self, dbg: &DBG, hits: usize) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Text(text) => text.clone(),
+                Part::Expr { node, decimal } => {
+                    let value = compute_expression(node, dbg, Some(hits));
+                    if *decimal {
+                        value.to_string()
+                    } else {
+                        format!("{:02X}", value)
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+/// What a breakpoint does once it is triggered
+#[derive(Debug, Clone)]
+pub struct Actions {
+    /// Stop the execution
+    pub stop: bool,
+    /// Log a formatted message
+    pub log: Option<Message>,
+    /// Dump a memory range into a file
+    pub dump: Option<(RangeInclusive<u16>, Message)>,
+    /// Save the screen into a PPM image
+    pub screenshot: Option<Message>,
+    /// Arm the execution trace
+    pub arm_trace: bool,
+}
+
+impl Default for Actions {
+    fn default() -> Self {
+        Self {
+            stop: true,
+            log: None,
+            dump: None,
+            screenshot: None,
+            arm_trace: false,
+        }
+    }
+}
+
+impl Actions {
+    pub fn run<DBG: DebugOperations>(&self, dbg: &mut DBG, hits: usize) {
+        if let Some(message) = &self.log {
+            log::info!("[logpoint] {}", message.format(dbg, hits));
+        }
+        if let Some((range, path)) = &self.dump {
+            let path = path.format(dbg, hits);
+            if let Err(e) = dump_memory(&path, range.clone(), dbg) {
+                log::error!("cannot dump memory to {}: {}", path, e);
+            }
+        }
+        if let Some(path) = &self.screenshot {
+            let path = path.format(dbg, hits);
+            if let Err(e) = write_ppm(&path, &dbg.screen()) {
+                log::error!("cannot save screenshot to {}: {}", path, e);
+            }
+        }
+        if self.arm_trace {
+            dbg.tracer().arm();
+        }
+    }
+}
+
+fn dump_memory<DBG: DebugOperations>(
+    path: &str,
+    range: RangeInclusive<u16>,
+    dbg: &DBG,
+) -> io::Result<()> {
+    let data: Vec<u8> = range.map(|addr| dbg.read(addr)).collect();
+
+    File::create(path)?.write_all(&data)
+}
+
+/// Write the screen as a binary PPM image
+fn write_ppm(path: &str, screen: &Screen) -> io::Result<()> {
+    let mut file = io::BufWriter::new(File::create(path)?);
+
+    write!(file, "P6\n{} {}\n255\n", screen.width, screen.height)?;
+    for pixel in screen.pixels.iter() {
+        file.write_all(pixel)?;
+    }
+    file.flush()
+}
+
+#[cfg(test)]
+mod test_message {
+    use super::Message;
+    use std::str::FromStr;
+
+    #[test]
+    fn parse() {
+        assert_eq!(Message::from_str("pc={PC}").unwrap().parts.len(), 2);
+        assert_eq!(Message::from_str("{{raw}}").unwrap().parts.len(), 1);
+        assert_eq!(
+            Message::from_str("hp={*C0A2:d} pc={PC}")
+                .unwrap()
+                .parts
+                .len(),
+            4
+        );
+        assert!(Message::from_str("pc={PC").is_err());
+        assert!(Message::from_str("pc={PC +}").is_err());
+        assert!(Message::from_str("pc={PC:o}").is_err());
+        assert!(Message::from_str("pc}").is_err());
+    }
+}
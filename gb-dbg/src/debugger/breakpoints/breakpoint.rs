@@ -6,13 +6,25 @@ use std::{
 
 use crate::dbg_interfaces::DebugOperations;
 
-use super::evaluation::is_expression_true;
+use super::{
+    action::Actions,
+    evaluation::{is_expression_reached, is_expression_true},
+};
 
 #[derive(Debug)]
 pub struct Breakpoint {
     expr: Node,
     pub enabled: bool,
     last_state: bool,
+    /// Number of times the breakpoint was reached
+    pub hits: usize,
+    /// Number of times the whole condition was met
+    pub count: usize,
+    /// Number of times the condition is met before running the actions
+    pub ignore_count: usize,
+    /// Disable the breakpoint once its actions were run
+    pub disable_after_hit: bool,
+    pub actions: Actions,
 }
 
 impl Display for Breakpoint {
@@ -38,36 +50,90 @@ impl Display for Breakpoint {
 
 impl Breakpoint {
     pub fn from_address(address: u16) -> Self {
-        Self {
-            expr: Node::simple(address),
-            enabled: true,
-            last_state: false,
-        }
+        Self::from_node(Node::simple(address))
     }
 
     pub fn from_expression(expr: &str) -> anyhow::Result<Self> {
         let node = Node::from_str(expr).map_err(|e| anyhow::anyhow!(e))?;
 
         log::debug!("parsed expression: {:?}", node);
-        Ok(Self {
+        Ok(Self::from_node(node))
+    }
+
+    pub fn from_node(node: Node) -> Self {
+        Self {
             expr: node,
             enabled: true,
             last_state: false,
-        })
+            hits: 0,
+            count: 0,
+            ignore_count: 0,
+            disable_after_hit: false,
+            actions: Actions::default(),
+        }
+    }
+
+    pub fn expr(&self) -> &Node {
+        &self.expr
+    }
+
+    /// Return the labels of the expression missing from the symbol table
+    pub fn unknown_labels<DBG: DebugOperations>(&self, dbg: &DBG) -> Vec<&str> {
+        self.expr
+            .labels()
+            .into_iter()
+            .filter(|label| dbg.symbols().address_of(label).is_none())
+            .collect()
     }
 
     pub fn is_triggered<T: DebugOperations>(&self, regs: &T) -> bool {
-        self.enabled && is_expression_true(&self.expr, regs)
+        self.enabled && is_expression_true(&self.expr, regs, Some(self.hits))
+    }
+
+    /// Check if the expression is true, ignoring the conditions on `hits`
+    pub fn is_reached<DBG: DebugOperations>(&self, context: &DBG) -> bool {
+        self.enabled && is_expression_reached(&self.expr, context)
+    }
+
+    /// Align the state used to detect when the breakpoint is reached on the machine,
+    /// used after the emulation jumped in time
+    pub fn sync<DBG: DebugOperations>(&mut self, context: &DBG) {
+        self.last_state = self.is_reached(context);
     }
 
     /// check if breakpoint is active
     /// this method is used to prevent the breakpoint to trigger itself on the same session
+    ///
+    /// The breakpoint is reached when its expression become true, ignoring the conditions on `hits`.
+    /// Each time it is reached, the `hits` counter is incremented before checking the whole expression.
     pub fn active<DBG: DebugOperations>(&mut self, context: &DBG) -> bool {
-        let current_state = self.is_triggered(context);
+        let current_state = self.is_reached(context);
         let result = !self.last_state && current_state;
 
         self.last_state = current_state;
+        if !result {
+            return false;
+        }
+        self.hits += 1;
+        !self.expr.uses_hits() || self.is_triggered(context)
+    }
 
-        result
+    /// Run the actions of the breakpoint when it is active and the ignore count is exhausted.
+    /// Return `true` when the execution need to be stopped.
+    pub fn fire<DBG: DebugOperations>(&mut self, context: &mut DBG) -> bool {
+        if !self.active(context) {
+            return false;
+        }
+        self.count += 1;
+        if self.count <= self.ignore_count {
+            return false;
+        }
+        log::debug!("breakpoint triggered break={:?}", self);
+        self.actions.run(context, self.hits);
+        if self.disable_after_hit {
+            self.enabled = false;
+            self.last_state = false;
+        }
+        self.actions.stop
     }
 }
@@ -1,29 +1,144 @@
 use crate::dbg_interfaces::{CpuRegs, DebugOperations};
-use gb_breakpoint::{Node, Operator, UnaryOperator};
+use gb_breakpoint::{bank::Bank, register::Register, Node, Operator, UnaryOperator};
 
 const TRUE: u16 = 0xffff;
 const FALSE: u16 = 0x0000;
 
-pub fn is_expression_true<DBG: DebugOperations>(node: &Node, dbg: &DBG) -> bool {
-    compute_expression(node, dbg) != FALSE
+/// Evaluate an expression, `hits` is the value of the `hits` counter, `0` when `None`
+pub fn is_expression_true<DBG: DebugOperations>(
+    node: &Node,
+    dbg: &DBG,
+    hits: Option<usize>,
+) -> bool {
+    compute_expression(node, dbg, hits) != FALSE
 }
 
-pub fn compute_expression<DBG: DebugOperations>(node: &Node, dbg: &DBG) -> u16 {
+pub fn compute_expression<DBG: DebugOperations>(
+    node: &Node,
+    dbg: &DBG,
+    hits: Option<usize>,
+) -> u16 {
     let current = node;
 
     match current {
-        Node::Register(r) => u16::from(dbg.cpu_get(CpuRegs::try_from(*r).unwrap())),
-        Node::Address(a) => u16::from(dbg.read(compute_expression(a, dbg))),
+        Node::Register(r) => register_value(*r, dbg),
+        Node::IoRegister(r) => u16::from(dbg.read(r.address())),
+        Node::Bank(Bank::Rom) => dbg.rom_bank() as u16,
+        Node::Bank(Bank::Ram) => dbg.ram_bank() as u16,
+        Node::Hits => hits.map_or(0, |hits| hits.min(u16::MAX as usize) as u16),
+        Node::Label(name) => dbg.symbols().address_of(name).unwrap_or(0),
+        Node::Address(a) => u16::from(dbg.read(compute_expression(a, dbg, hits))),
+        Node::WordAddress(a) => {
+            let addr = compute_expression(a, dbg, hits);
+            u16::from_le_bytes([dbg.read(addr), dbg.read(addr.wrapping_add(1))])
+        }
         Node::Raw(v) => *v,
-        Node::UnaryExpr(expr) => eval_unary_op(&expr.op, compute_expression(&expr.child, dbg)),
+        Node::UnaryExpr(expr) => {
+            eval_unary_op(&expr.op, compute_expression(&expr.child, dbg, hits))
+        }
         Node::BinaryExpr(expr) => eval_binary_op(
             &expr.op,
-            compute_expression(&expr.lhs, dbg),
-            compute_expression(&expr.rhs, dbg),
+            compute_expression(&expr.lhs, dbg, hits),
+            compute_expression(&expr.rhs, dbg, hits),
         ),
     }
 }
 
+/// Evaluate an expression ignoring the conditions on the `hits` counter.
+///
+/// The operands of `&&`, `||` and `^^` using the counter are left out,
+/// so only the other operands decide, an expression only made of conditions on the counter is true.
+pub fn is_expression_reached<DBG: DebugOperations>(node: &Node, dbg: &DBG) -> bool {
+    !matches!(reached_value(node, dbg), Some(FALSE))
+}
+
+/// Return the value of `node` without the conditions on `hits`, `None` when it depends on them
+fn reached_value<DBG: DebugOperations>(node: &Node, dbg: &DBG) -> Option<u16> {
+    match node {
+        Node::BinaryExpr(expr) if is_logical(&expr.op) => {
+            match (reached_value(&expr.lhs, dbg), reached_value(&expr.rhs, dbg)) {
+                (Some(lhs), Some(rhs)) => Some(eval_binary_op(&expr.op, lhs, rhs)),
+                (Some(value), None) | (None, Some(value)) => Some(value),
+                (None, None) => None,
+            }
+        }
+        node if node.uses_hits() => None,
+        node => Some(compute_expression(node, dbg, None)),
+    }
+}
+
+fn is_logical(op: &Operator) -> bool {
+    matches!(
+        op,
+        Operator::LogicAnd | Operator::LogicOr | Operator::LogicXor
+    )
+}
+
+fn register_value<DBG: DebugOperations>(reg: Register, dbg: &DBG) -> u16 {
+    if let Ok(reg) = CpuRegs::try_from(reg) {
+        return u16::from(dbg.cpu_get(reg));
+    }
+
+    let flag = |mask: u16| u16::from(u16::from(dbg.cpu_get(CpuRegs::AF)) & mask != 0);
+    match reg {
+        Register::A => eval_unary_op(&UnaryOperator::Upper, dbg.cpu_get(CpuRegs::AF).into()),
+        Register::F => eval_unary_op(&UnaryOperator::Lower, dbg.cpu_get(CpuRegs::AF).into()),
+        Register::B => eval_unary_op(&UnaryOperator::Upper, dbg.cpu_get(CpuRegs::BC).into()),
+        Register::C => eval_unary_op(&UnaryOperator::Lower, dbg.cpu_get(CpuRegs::BC).into()),
+        Register::D => eval_unary_op(&UnaryOperator::Upper, dbg.cpu_get(CpuRegs::DE).into()),
+        Register::E => eval_unary_op(&UnaryOperator::Lower, dbg.cpu_get(CpuRegs::DE).into()),
+        Register::H => eval_unary_op(&UnaryOperator::Upper, dbg.cpu_get(CpuRegs::HL).into()),
+        Register::L => eval_unary_op(&UnaryOperator::Lower, dbg.cpu_get(CpuRegs::HL).into()),
+        Register::ZF => flag(0b1000_0000),
+        Register::NF => flag(0b0100_0000),
+        Register::HF => flag(0b0010_0000),
+        Register::CF => flag(0b0001_0000),
+        _ => unreachable!("16-bit registers are handled by CpuRegs"),
+    }
+}
+
+/// Write `value` in a register, only the lower byte is used by the 8-bit registers
+/// and the lower bit by the flags
+pub fn set_register<DBG: DebugOperations>(reg: Register, value: u16, dbg: &mut DBG) {
+    if let Ok(reg) = CpuRegs::try_from(reg) {
+        return dbg.cpu_set(reg, value);
+    }
+
+    let (pair, high) = match reg {
+        Register::A => (CpuRegs::AF, true),
+        Register::F => (CpuRegs::AF, false),
+        Register::B => (CpuRegs::BC, true),
+        Register::C => (CpuRegs::BC, false),
+        Register::D => (CpuRegs::DE, true),
+        Register::E => (CpuRegs::DE, false),
+        Register::H => (CpuRegs::HL, true),
+        Register::L => (CpuRegs::HL, false),
+        flag => {
+            let mask = match flag {
+                Register::ZF => 0b1000_0000,
+                Register::NF => 0b0100_0000,
+                Register::HF => 0b0010_0000,
+                _ => 0b0001_0000,
+            };
+            let af = u16::from(dbg.cpu_get(CpuRegs::AF));
+            let af = if value & 1 != 0 {
+                af | mask
+            } else {
+                af & !mask
+            };
+            return dbg.cpu_set(CpuRegs::AF, af);
+        }
+    };
+    let [low, upper] = u16::from(dbg.cpu_get(pair)).to_le_bytes();
+    let byte = value as u8;
+    let value = if high {
+        u16::from_le_bytes([low, byte])
+    } else {
+        u16::from_le_bytes([byte, upper])
+    };
+    dbg.cpu_set(pair, value);
+}
+
 pub fn eval_unary_op(op: &UnaryOperator, value: u16) -> u16 {
     match op {
         UnaryOperator::Upper => value.to_le_bytes()[1] as u16,
@@ -64,6 +179,10 @@ pub fn eval_binary_op(op: &Operator, lhs: u16, rhs: u16) -> u16 {
         Operator::BinaryAnd => lhs & rhs,
         Operator::BinaryXor => lhs ^ rhs,
         Operator::BinaryOr => lhs | rhs,
+        Operator::Add => lhs.wrapping_add(rhs),
+        Operator::Sub => lhs.wrapping_sub(rhs),
+        Operator::ShiftLeft => lhs.checked_shl(rhs.into()).unwrap_or(0),
+        Operator::ShiftRight => lhs.checked_shr(rhs.into()).unwrap_or(0),
         Operator::NotEq => {
             if lhs != rhs {
                 TRUE
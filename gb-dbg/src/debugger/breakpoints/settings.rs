@@ -0,0 +1,148 @@
+use std::str::FromStr;
+
+use egui::{Color32, RichText, Ui};
+
+use super::{
+    action::{Actions, Message},
+    breakpoint::Breakpoint,
+};
+use crate::debugger::watchpoints::parse_range;
+
+/// Edit the actions and options of the selected breakpoint
+#[derive(Default, Debug)]
+pub struct SettingsEditor {
+    pub selected: Option<usize>,
+    log_field: String,
+    dump_range_field: String,
+    dump_path_field: String,
+    screenshot_field: String,
+    error: Option<String>,
+}
+
+impl SettingsEditor {
+    pub fn select(&mut self, index: usize, breakpoint: &Breakpoint) {
+        let actions = &breakpoint.actions;
+
+        self.selected = Some(index);
+        self.log_field = actions
+            .log
+            .as_ref()
+            .map(Message::to_string)
+            .unwrap_or_default();
+        self.dump_range_field = actions
+            .dump
+            .as_ref()
+            .map(|(range, _)| format!("{:04X}-{:04X}", range.start(), range.end()))
+            .unwrap_or_default();
+        self.dump_path_field = actions
+            .dump
+            .as_ref()
+            .map(|(_, path)| path.to_string())
+            .unwrap_or_default();
+        self.screenshot_field = actions
+            .screenshot
+            .as_ref()
+            .map(Message::to_string)
+            .unwrap_or_default();
+        self.error = None;
+    }
+
+    pub fn draw(&mut self, ui: &mut Ui, breakpoint: &mut Breakpoint) {
+        ui.label(RichText::new(format!("⚙ {}", breakpoint)).color(Color32::LIGHT_BLUE));
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut breakpoint.actions.stop, "Break");
+            ui.checkbox(&mut breakpoint.disable_after_hit, "Disable after hit");
+            ui.checkbox(&mut breakpoint.actions.arm_trace, "Arm trace")
+                .on_hover_text("start the recording of a trace filtered on armed");
+        });
+        ui.horizontal(|ui| {
+            ui.label("Ignore count");
+            ui.add(egui::DragValue::new(&mut breakpoint.ignore_count));
+            ui.label(format!("count: {}", breakpoint.count));
+            if ui.button("Reset").clicked() {
+                breakpoint.hits = 0;
+                breakpoint.count = 0;
+            }
+        });
+        egui::Grid::new("breakpoint_settings").show(ui, |ui| {
+            ui.label("Log");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.log_field)
+                    .desired_width(200.0)
+                    .hint_text("hp={*C0A2:d} pc={PC}"),
+            );
+            ui.end_row();
+
+            ui.label("Dump");
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.dump_range_field)
+                        .desired_width(80.0)
+                        .hint_text("C000-DFFF"),
+                );
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.dump_path_field)
+                        .desired_width(112.0)
+                        .hint_text("wram_{hits:d}.bin"),
+                );
+            });
+            ui.end_row();
+
+            ui.label("Screenshot");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.screenshot_field)
+                    .desired_width(200.0)
+                    .hint_text("screen_{hits:d}.ppm"),
+            );
+            ui.end_row();
+        });
+        ui.horizontal(|ui| {
+            if ui.button("Apply").clicked() {
+                match self.parse_actions(&breakpoint.actions) {
+                    Ok(actions) => {
+                        breakpoint.actions = actions;
+                        self.error = None;
+                    }
+                    Err(e) => self.error = Some(e),
+                }
+            }
+            if ui.button("Close").clicked() {
+                self.selected = None;
+            }
+        });
+        if let Some(error) = &self.error {
+            ui.colored_label(Color32::RED, error);
+        }
+    }
+
+    /// Parse the fields, the flags are kept from `current`
+    fn parse_actions(&self, current: &Actions) -> Result<Actions, String> {
+        let log = parse_message(&self.log_field)?;
+        let screenshot = parse_message(&self.screenshot_field)?;
+        let dump = if self.dump_range_field.trim().is_empty() {
+            None
+        } else {
+            let range = parse_range(self.dump_range_field.trim())
+                .ok_or_else(|| format!("invalid dump range `{}`", self.dump_range_field))?;
+            let path = parse_message(&self.dump_path_field)?
+                .ok_or_else(|| "missing dump file".to_owned())?;
+            Some((range, path))
+        };
+
+        Ok(Actions {
+            stop: current.stop,
+            log,
+            dump,
+            screenshot,
+            arm_trace: current.arm_trace,
+        })
+    }
+}
+
+fn parse_message(field: &str) -> Result<Option<Message>, String> {
+    if field.trim().is_empty() {
+        Ok(None)
+    } else {
+        Message::from_str(field).map(Some)
+    }
+}
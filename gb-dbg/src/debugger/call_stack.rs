@@ -0,0 +1,86 @@
+use crate::dbg_interfaces::{CpuRegs, DebugOperations};
+use crate::symbols::SymbolTable;
+use egui::{Color32, Label, RichText, Sense, Ui};
+
+/// Show the frames of the shadow call stack, the innermost first
+#[derive(Default, Debug)]
+pub struct CallStackViewer;
+
+impl CallStackViewer {
+    /// Draw the backtrace, return the location of the frame clicked by the user
+    pub fn draw<DBG: DebugOperations>(&self, ui: &mut Ui, dbg: &DBG) -> Option<(usize, u16)> {
+        let mut clicked = None;
+
+        ui.vertical(|ui| {
+            ui.colored_label(Color32::LIGHT_BLUE, "Call stack");
+            ui.separator();
+            let call_stack = dbg.call_stack();
+            let symbols = dbg.symbols();
+            let pc: u16 = dbg.cpu_get(CpuRegs::PC).into();
+            let rom_bank = dbg.rom_bank();
+
+            egui::ScrollArea::vertical()
+                .id_source("call_stack")
+                .max_height(160.0)
+                .show(ui, |ui| {
+                    egui::Grid::new("call_stack_frames")
+                        .striped(true)
+                        .show(ui, |ui| {
+                            ui.colored_label(Color32::GOLD, "#");
+                            ui.colored_label(Color32::GOLD, "Kind");
+                            ui.colored_label(Color32::GOLD, "Location");
+                            ui.end_row();
+
+                            ui.label("0");
+                            ui.label("");
+                            if frame_label(ui, symbols, rom_bank, pc).clicked() {
+                                clicked = Some((rom_bank, pc));
+                            }
+                            ui.end_row();
+
+                            for (depth, frame) in call_stack.frames().iter().rev().enumerate() {
+                                let bank = frame.bank.unwrap_or(rom_bank);
+                                ui.label((depth + 1).to_string());
+                                ui.label(frame.kind.to_string()).on_hover_text(format!(
+                                    "from {:04X} to {:04X}, SP={:04X}",
+                                    frame.call_site, frame.target, frame.sp
+                                ));
+                                if frame_label(ui, symbols, bank, frame.return_address).clicked() {
+                                    clicked = Some((bank, frame.call_site));
+                                }
+                                ui.end_row();
+                            }
+                        });
+                });
+            if call_stack.desyncs != 0 {
+                ui.colored_label(
+                    Color32::YELLOW,
+                    format!("⚠ {} desynchronizations", call_stack.desyncs),
+                )
+                .on_hover_text("SP was modified without returning, some frames were dropped");
+            }
+        });
+        clicked
+    }
+}
+
+/// Draw `address` with its ROM bank and the closest label, as `BB:AAAA label+offset`
+fn frame_label(ui: &mut Ui, symbols: &SymbolTable, bank: usize, address: u16) -> egui::Response {
+    let bank = match address {
+        0x0000..=0x3fff => Some(0),
+        0x4000..=0x7fff => Some(bank as u16),
+        _ => None,
+    };
+    let mut text = match bank {
+        Some(bank) => format!("{:02X}:{:04X}", bank, address),
+        None => format!("{:04X}", address),
+    };
+    if let Some(location) = symbols.locate(bank, address) {
+        text.push_str(&format!(" {}", location.symbol.name));
+        if location.offset != 0 {
+            text.push_str(&format!("+{:X}", location.offset));
+        }
+    }
+    ui.add(Label::new(RichText::new(text).monospace()).sense(Sense::click()))
+        .on_hover_text("Show in the disassembler")
+}
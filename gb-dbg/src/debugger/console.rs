@@ -0,0 +1,142 @@
+pub mod command;
+
+use egui::{Color32, Context, Key, RichText, TextEdit};
+use std::collections::VecDeque;
+
+/// Number of lines kept in the output of the console
+const MAX_LINES: usize = 500;
+
+#[derive(Debug, Clone, Copy)]
+enum LineKind {
+    Input,
+    Output,
+    Error,
+}
+
+#[derive(Debug)]
+struct Line {
+    kind: LineKind,
+    text: String,
+}
+
+/// Window where the user type the commands of the debugger
+#[derive(Debug, Default)]
+pub struct Console {
+    pub open: bool,
+    input: String,
+    lines: VecDeque<Line>,
+    history: Vec<String>,
+    /// Position in the history while browsing it with the arrows
+    history_cursor: Option<usize>,
+    /// Lines of the script not executed yet
+    script: VecDeque<String>,
+}
+
+impl Console {
+    pub fn new(script: Vec<String>) -> Self {
+        Self {
+            open: !script.is_empty(),
+            script: script.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Draw the console, return the line entered by the user
+    pub fn draw(&mut self, ctx: &Context) -> Option<String> {
+        let mut entered = None;
+        let mut open = self.open;
+        egui::Window::new("Console")
+            .open(&mut open)
+            .default_width(520.0)
+            .show(ctx, |ui| {
+                egui::ScrollArea::vertical()
+                    .id_source("console_output")
+                    .max_height(240.0)
+                    .stick_to_bottom()
+                    .show(ui, |ui| {
+                        ui.set_min_width(500.0);
+                        for line in &self.lines {
+                            let color = match line.kind {
+                                LineKind::Input => Color32::GOLD,
+                                LineKind::Output => Color32::WHITE,
+                                LineKind::Error => Color32::RED,
+                            };
+                            ui.label(RichText::new(&line.text).monospace().color(color));
+                        }
+                    });
+                ui.separator();
+
+                let response = ui.add(
+                    TextEdit::singleline(&mut self.input)
+                        .desired_width(f32::INFINITY)
+                        .code_editor()
+                        .hint_text("help"),
+                );
+                if response.lost_focus() && ui.input().key_pressed(Key::Enter) {
+                    let line = std::mem::take(&mut self.input);
+                    if !line.trim().is_empty() {
+                        if self.history.last() != Some(&line) {
+                            self.history.push(line.clone());
+                        }
+                        entered = Some(line);
+                    }
+                    self.history_cursor = None;
+                    response.request_focus();
+                } else if response.has_focus() {
+                    if ui.input().key_pressed(Key::ArrowUp) {
+                        self.browse_history(true);
+                    } else if ui.input().key_pressed(Key::ArrowDown) {
+                        self.browse_history(false);
+                    }
+                }
+            });
+        self.open = open;
+        entered
+    }
+
+    fn browse_history(&mut self, backward: bool) {
+        let cursor = match (self.history_cursor, backward) {
+            (None, true) => self.history.len().checked_sub(1),
+            (None, false) => None,
+            (Some(cursor), true) => Some(cursor.saturating_sub(1)),
+            (Some(cursor), false) => Some(cursor + 1).filter(|c| *c < self.history.len()),
+        };
+        self.history_cursor = cursor;
+        self.input = cursor
+            .map(|cursor| self.history[cursor].clone())
+            .unwrap_or_default();
+    }
+
+    /// Return the next command of the script, the comments and empty lines are skipped
+    pub fn next_script_line(&mut self) -> Option<String> {
+        while let Some(line) = self.script.pop_front() {
+            let line = line.trim();
+            if !line.is_empty() && !line.starts_with('#') {
+                return Some(line.to_owned());
+            }
+        }
+        None
+    }
+
+    pub fn echo(&mut self, text: &str) {
+        self.push(LineKind::Input, format!("> {}", text));
+    }
+
+    pub fn print(&mut self, text: &str) {
+        for line in text.lines() {
+            self.push(LineKind::Output, line.to_owned());
+        }
+    }
+
+    pub fn error(&mut self, text: &str) {
+        log::warn!("console: {}", text);
+        self.push(LineKind::Error, text.to_owned());
+    }
+
+    fn push(&mut self, kind: LineKind, text: String) {
+        if self.lines.len() == MAX_LINES {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(Line { kind, text });
+    }
+}
@@ -0,0 +1,257 @@
+use crate::debugger::watchpoints::parse_range;
+use crate::until::Until;
+use gb_breakpoint::Node;
+use gb_bus::watchpoint::AccessMask;
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Number of bytes shown by `x` when no count is given
+const DEFAULT_EXAMINE_COUNT: usize = 16;
+
+pub const HELP: &str = "\
+break EXPR            add a breakpoint, `break PC == 0150`
+watch RANGE [r|w|rw|x] add a watchpoint, `watch C000-C0FF rw`
+continue, pause       resume or pause the execution
+step [N]              execute N instructions
+next, finish          step over a call, run until the function returns
+reverse-step          go back to the previous instruction
+reverse-continue      go back to the last breakpoint or watchpoint reached
+until frame|cycle|second N, until line LY, until vblank|interrupt
+until EXPR            run until PC reach the address
+x[/N] EXPR            show N bytes of memory, `x/16 C000`
+print EXPR            evaluate an expression, `print *w HL`
+set LVALUE=EXPR       change a register or the memory, `set A=5`, `set *C000=FF`
+trace on|off          start or stop the execution trace
+save-state [FILE], load-state [FILE]
+reset, help
+counts are decimal, expressions use the breakpoint syntax";
+
+/// Command accepted by the console, the expressions use the grammar of the breakpoints
+#[derive(Debug, PartialEq, Eq)]
+pub enum Command {
+    Break(Node),
+    Watch(RangeInclusive<u16>, AccessMask),
+    Continue,
+    Pause,
+    /// Change the execution flow, the emulation is resumed
+    Flow(Until),
+    /// Run until PC reach the address computed from the expression
+    RunTo(Node),
+    Examine(Node, usize),
+    Print(Node),
+    /// Write a register, an IO register or a byte of the memory
+    Set(Node, Node),
+    Trace(bool),
+    ReverseStep,
+    ReverseContinue,
+    SaveState(Option<PathBuf>),
+    LoadState(Option<PathBuf>),
+    Reset,
+    Help,
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (name, args) = s.split_once(char::is_whitespace).unwrap_or((s, ""));
+        let args = args.trim();
+        let no_args = |command| {
+            if args.is_empty() {
+                Ok(command)
+            } else {
+                Err(format!("`{}` does not take arguments", name))
+            }
+        };
+
+        match name {
+            "break" | "b" => Ok(Command::Break(expression(args)?)),
+            "watch" | "w" => watch(args),
+            "continue" | "c" => no_args(Command::Continue),
+            "pause" => no_args(Command::Pause),
+            "step" | "s" => Ok(Command::Flow(Until::Step(count(args, 1)?))),
+            "next" | "n" => no_args(Command::Flow(Until::StepOver)),
+            "finish" => no_args(Command::Flow(Until::StepOut)),
+            "until" | "u" => until(args),
+            "reverse-step" | "rs" => no_args(Command::ReverseStep),
+            "reverse-continue" | "rc" => no_args(Command::ReverseContinue),
+            "print" | "p" => Ok(Command::Print(expression(args)?)),
+            "set" => {
+                let (target, value) = args
+                    .split_once('=')
+                    .ok_or_else(|| "expected `set LVALUE=EXPR`".to_owned())?;
+                let target = expression(target)?;
+                if !matches!(
+                    target,
+                    Node::Register(_) | Node::IoRegister(_) | Node::Address(_)
+                ) {
+                    return Err(format!("cannot assign to `{}`", target));
+                }
+                Ok(Command::Set(target, expression(value)?))
+            }
+            "trace" => match args {
+                "on" => Ok(Command::Trace(true)),
+                "off" => Ok(Command::Trace(false)),
+                _ => Err("expected `trace on` or `trace off`".to_owned()),
+            },
+            "save-state" => Ok(Command::SaveState(path(args))),
+            "load-state" => Ok(Command::LoadState(path(args))),
+            "reset" => no_args(Command::Reset),
+            "help" | "?" => no_args(Command::Help),
+            _ => match name.strip_prefix('x') {
+                Some("") => Ok(Command::Examine(expression(args)?, DEFAULT_EXAMINE_COUNT)),
+                Some(suffix) => match suffix.strip_prefix('/') {
+                    Some(n) => Ok(Command::Examine(expression(args)?, count(n, 0)?)),
+                    None => Err(format!("unknown command `{}`", name)),
+                },
+                None => Err(format!("unknown command `{}`", name)),
+            },
+        }
+    }
+}
+
+fn expression(s: &str) -> Result<Node, String> {
+    if s.trim().is_empty() {
+        Err("expected an expression".to_owned())
+    } else {
+        Node::from_str(s)
+    }
+}
+
+fn count(s: &str, default: usize) -> Result<usize, String> {
+    if s.is_empty() {
+        Ok(default)
+    } else {
+        s.parse()
+            .map_err(|e| format!("invalid count `{}`: {}", s, e))
+    }
+}
+
+fn path(s: &str) -> Option<PathBuf> {
+    if s.is_empty() {
+        None
+    } else {
+        Some(PathBuf::from(s))
+    }
+}
+
+fn watch(args: &str) -> Result<Command, String> {
+    let (range, access) = args.split_once(' ').unwrap_or((args, "w"));
+    let range = parse_range(range).ok_or_else(|| format!("invalid range `{}`", range))?;
+    let access = match access.trim() {
+        "r" => AccessMask::READ,
+        "w" => AccessMask::WRITE,
+        "rw" => AccessMask {
+            read: true,
+            write: true,
+            execute: false,
+        },
+        "x" => AccessMask::EXECUTE,
+        access => {
+            return Err(format!(
+                "invalid access `{}`, expected r, w, rw or x",
+                access
+            ))
+        }
+    };
+    Ok(Command::Watch(range, access))
+}
+
+fn until(args: &str) -> Result<Command, String> {
+    let (kind, value) = args.split_once(' ').unwrap_or((args, ""));
+    let value = value.trim();
+    let until = match kind {
+        "frame" => Until::Frame(count(value, 1)?),
+        "cycle" => Until::Cycle(count(value, 1)?),
+        "second" => Until::Second(count(value, 1)?),
+        "line" => Until::Line(
+            value
+                .parse()
+                .ok()
+                .filter(|line| *line <= 153)
+                .ok_or_else(|| format!("invalid line `{}`", value))?,
+        ),
+        "vblank" if value.is_empty() => Until::VBlank,
+        "interrupt" if value.is_empty() => Until::Interrupt,
+        _ => return Ok(Command::RunTo(expression(args)?)),
+    };
+    Ok(Command::Flow(until))
+}
+
+#[cfg(test)]
+mod test_command {
+    use super::Command;
+    use crate::until::Until;
+    use gb_breakpoint::{register::Register, Node};
+    use gb_bus::watchpoint::AccessMask;
+
+    fn parse(s: &str) -> Result<Command, String> {
+        s.parse()
+    }
+
+    #[test]
+    fn flow() {
+        assert_eq!(parse("step 100"), Ok(Command::Flow(Until::Step(100))));
+        assert_eq!(parse("step"), Ok(Command::Flow(Until::Step(1))));
+        assert_eq!(parse("until frame 3"), Ok(Command::Flow(Until::Frame(3))));
+        assert_eq!(parse("until line 144"), Ok(Command::Flow(Until::Line(144))));
+        assert_eq!(parse("until vblank"), Ok(Command::Flow(Until::VBlank)));
+        assert_eq!(parse("until 0150"), Ok(Command::RunTo(Node::Raw(0x150))));
+        assert_eq!(parse("continue"), Ok(Command::Continue));
+        assert_eq!(parse("reverse-step"), Ok(Command::ReverseStep));
+        assert_eq!(parse("rc"), Ok(Command::ReverseContinue));
+        assert!(parse("until line 200").is_err());
+        assert!(parse("pause now").is_err());
+    }
+
+    #[test]
+    fn memory() {
+        assert_eq!(
+            parse("x/16 0xC000"),
+            Ok(Command::Examine(Node::Raw(0xc000), 16))
+        );
+        assert_eq!(
+            parse("x/16 wBuffer"),
+            Ok(Command::Examine(Node::Label("wBuffer".to_owned()), 16))
+        );
+        assert_eq!(
+            parse("x HL"),
+            Ok(Command::Examine(Node::Register(Register::HL), 16))
+        );
+        assert_eq!(
+            parse("watch C000-C0FF rw"),
+            Ok(Command::Watch(
+                0xc000..=0xc0ff,
+                AccessMask {
+                    read: true,
+                    write: true,
+                    execute: false
+                }
+            ))
+        );
+        assert_eq!(
+            parse("watch FF40"),
+            Ok(Command::Watch(0xff40..=0xff40, AccessMask::WRITE))
+        );
+        assert!(parse("x/a C000").is_err());
+        assert!(parse("xyz").is_err());
+    }
+
+    #[test]
+    fn set() {
+        assert_eq!(
+            parse("set A=5"),
+            Ok(Command::Set(Node::Register(Register::A), Node::Raw(5)))
+        );
+        assert!(matches!(
+            parse("set *0xC000 = 0xFF"),
+            Ok(Command::Set(Node::Address(_), Node::Raw(0xff)))
+        ));
+        assert!(parse("set 5=A").is_err());
+        assert!(parse("set A").is_err());
+        assert!(matches!(parse("break PC == 0150"), Ok(Command::Break(_))));
+        assert_eq!(parse("trace on"), Ok(Command::Trace(true)));
+    }
+}
@@ -0,0 +1,145 @@
+use crate::coverage::{self, Coverage, ExportFormat};
+use crate::dbg_interfaces::DebugOperations;
+use egui::{Color32, Context, Ui, Vec2};
+use gb_roms::code_data_log::CodeDataLog;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+
+/// Window recording which bytes of the ROM are executed or read, and exporting the result
+#[derive(Debug)]
+pub struct CoverageViewer {
+    pub open: bool,
+    format: ExportFormat,
+    path_field: String,
+    /// Result of the last export
+    status: Option<Result<String, String>>,
+}
+
+impl Default for CoverageViewer {
+    fn default() -> Self {
+        Self {
+            open: false,
+            format: ExportFormat::Cdl,
+            path_field: String::new(),
+            status: None,
+        }
+    }
+}
+
+/// Return `$LOG_DIR/coverage.cdl`, or a file of the temporary directory when it is not set
+fn default_path() -> PathBuf {
+    let mut path = std::env::var("LOG_DIR").map_or_else(|_| std::env::temp_dir(), PathBuf::from);
+    path.push("coverage.cdl");
+    path
+}
+
+impl CoverageViewer {
+    pub fn draw<DBG: DebugOperations>(&mut self, ctx: &Context, dbg: &mut DBG) {
+        let mut open = self.open;
+        egui::Window::new("Code/Data Logger")
+            .open(&mut open)
+            .default_width(360.0)
+            .show(ctx, |ui| self.draw_logger(ui, dbg));
+        self.open = open;
+    }
+
+    fn draw_logger<DBG: DebugOperations>(&mut self, ui: &mut Ui, dbg: &mut DBG) {
+        ui.horizontal(|ui| {
+            let mut recording = dbg.code_data_log().map_or(false, |log| log.is_recording());
+            if ui.checkbox(&mut recording, "Record").changed() {
+                dbg.set_code_data_logging(recording);
+            }
+            if ui.button("Reset").clicked() {
+                dbg.clear_code_data_log();
+            }
+        });
+
+        let log = match dbg.code_data_log() {
+            Some(log) => log,
+            None => {
+                ui.label("Nothing logged yet");
+                return;
+            }
+        };
+        let total = Coverage::of(&log, 0..log.rom_size());
+        ui.label(format!(
+            "{:.1}% executed, {:.1}% read as data, {} bytes unused",
+            total.code_percent(),
+            total.data_percent(),
+            total.unused()
+        ));
+        ui.separator();
+        draw_banks(ui, &log);
+        ui.separator();
+
+        if self.path_field.is_empty() {
+            self.path_field = default_path().display().to_string();
+        }
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_source("coverage_format")
+                .selected_text(self.format.to_string())
+                .show_ui(ui, |ui| {
+                    for format in ExportFormat::ALL {
+                        ui.selectable_value(&mut self.format, format, format.to_string());
+                    }
+                });
+            ui.add(
+                egui::TextEdit::singleline(&mut self.path_field)
+                    .desired_width(240.0)
+                    .hint_text("coverage.cdl"),
+            );
+        });
+        if ui.button("Export").clicked() {
+            let path =
+                PathBuf::from(self.path_field.trim()).with_extension(self.format.extension());
+            self.status = Some(
+                File::create(&path)
+                    .and_then(|file| {
+                        coverage::export(
+                            &mut BufWriter::new(file),
+                            &log,
+                            self.format,
+                            dbg.symbols(),
+                        )
+                    })
+                    .map(|_| format!("exported to {}", path.display()))
+                    .map_err(|e| format!("cannot export to {}: {}", path.display(), e)),
+            );
+        }
+        match &self.status {
+            Some(Ok(message)) => {
+                ui.label(message);
+            }
+            Some(Err(error)) => {
+                ui.colored_label(Color32::RED, error);
+            }
+            None => {}
+        }
+    }
+}
+
+fn draw_banks(ui: &mut Ui, log: &CodeDataLog) {
+    egui::ScrollArea::vertical()
+        .id_source("coverage_banks")
+        .max_height(240.0)
+        .show(ui, |ui| {
+            egui::Grid::new("coverage_banks_grid")
+                .striped(true)
+                .spacing(Vec2::new(16.0, 4.0))
+                .show(ui, |ui| {
+                    ui.colored_label(Color32::GOLD, "Bank");
+                    ui.colored_label(Color32::GOLD, "Code %");
+                    ui.colored_label(Color32::GOLD, "Data %");
+                    ui.colored_label(Color32::GOLD, "Unused");
+                    ui.end_row();
+                    for (bank, coverage) in coverage::banks(log).iter().enumerate() {
+                        ui.colored_label(Color32::from_rgb(191, 64, 191), format!("{:02X}", bank));
+                        ui.label(format!("{:.1}", coverage.code_percent()));
+                        ui.label(format!("{:.1}", coverage.data_percent()));
+                        ui.label(coverage.unused().to_string());
+                        ui.end_row();
+                    }
+                });
+        });
+}
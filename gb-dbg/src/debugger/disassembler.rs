@@ -1,26 +1,127 @@
-use crate::dbg_interfaces::MemoryDebugOperations;
-use egui::{Color32, Ui, Vec2};
+mod disassembly;
+
+use crate::dbg_interfaces::{CpuRegs, DebugOperations, MemoryDebugOperations};
+use crate::symbols::mapped_bank;
+use disassembly::{decode, successors, Disassembly, Line};
+use egui::{Color32, Label, RichText, Sense, Ui, Vec2};
+use gb_breakpoint::{native::hex_word, Node};
 use gb_roms::opcode::{error::Error, list::Opcode, OpcodeGenerator};
 
-#[derive(Default)]
+const ADDRESS_COLOR: Color32 = Color32::from_rgb(191, 64, 191);
+
+enum RowAction {
+    ToggleBreakpoint,
+    Follow(u16),
+    RunTo,
+}
+
+/// Request made from the listing to the rest of the debugger
+pub enum ListingAction {
+    ToggleBreakpoint(Node),
+    RunTo(u16),
+}
+
 pub struct DisassemblyViewer {
+    disassembly: Option<Disassembly>,
+    /// ROM bank listed after the bank 0
+    bank: usize,
+    follow_pc: bool,
+    goto_field: String,
+    goto_error: Option<String>,
+    /// Address to scroll to on the next draw
+    scroll_to: Option<u16>,
+    /// Lines left when following a jump
+    history: Vec<(usize, u16)>,
+    pc: Option<u16>,
+    /// Instructions decoded from PC when it is outside of the ROM
     cache: Vec<Result<(Opcode, Vec<u8>), Error>>,
+    /// Location of the cached instructions relative to the loaded labels
+    locations: Vec<String>,
     cache_pc_valid_range: Option<(u16, u16)>,
+    /// Changes of the code/data log already applied to the disassembly
+    log_changes: usize,
+}
+
+impl Default for DisassemblyViewer {
+    fn default() -> Self {
+        Self {
+            disassembly: None,
+            bank: 1,
+            follow_pc: true,
+            goto_field: String::with_capacity(16),
+            goto_error: None,
+            scroll_to: None,
+            history: Vec::new(),
+            pc: None,
+            cache: Vec::new(),
+            locations: Vec::new(),
+            cache_pc_valid_range: None,
+            log_changes: 0,
+        }
+    }
 }
 
 impl DisassemblyViewer {
-    fn update_cache<MEM: MemoryDebugOperations>(&mut self, pc: u16, memory: &MEM) {
+    /// Analyse the code reached by PC since the last update
+    pub fn update<DBG: DebugOperations>(&mut self, dbg: &DBG) {
+        let pc: u16 = dbg.cpu_get(CpuRegs::PC).into();
+        if pc >= 0x8000 {
+            self.may_update_cache(pc, dbg);
+        }
+
+        let disassembly = self
+            .disassembly
+            .get_or_insert_with(|| Disassembly::new(dbg.rom_bank_count()));
+        if pc < 0x8000 {
+            let bank = if pc < 0x4000 { 0 } else { dbg.rom_bank() };
+            disassembly.mark_executed(bank, pc);
+            if self.follow_pc && bank != 0 && bank < disassembly.bank_count() {
+                self.bank = bank;
+            }
+        }
+        if let Some(log) = dbg.code_data_log() {
+            // a cleared log starts over
+            if log.changes() < self.log_changes {
+                self.log_changes = 0;
+            }
+            if self.log_changes != log.changes() {
+                disassembly.apply_log(&log, self.log_changes);
+                self.log_changes = log.changes();
+            }
+        }
+        disassembly.analyse(|bank, offset| dbg.read_rom(bank, offset));
+        if self.follow_pc && self.pc != Some(pc) {
+            self.scroll_to = Some(pc);
+        }
+        self.pc = Some(pc);
+    }
+
+    fn update_cache<DBG: DebugOperations>(&mut self, pc: u16, memory: &DBG) {
         log::trace!("update opcode cache");
         let byte_it = ByteIterator::new(pc, memory);
         let generator = OpcodeGenerator::from(byte_it);
 
         self.cache = generator.take(8).collect::<Vec<Result<_, Error>>>();
+        let mut address = pc;
+        self.locations = self
+            .cache
+            .iter()
+            .map(|opcode| {
+                let location = memory
+                    .symbols()
+                    .locate(Some(mapped_bank(memory, address)), address)
+                    .map(|location| location.to_string())
+                    .unwrap_or_default();
+                address = address.wrapping_add(opcode_len(opcode));
+                location
+            })
+            .collect();
         let current_opcode = &self.cache[0];
         let next_instr_start_address = opcode_len(current_opcode) + pc;
         self.cache_pc_valid_range = Some((pc, next_instr_start_address));
     }
 
-    pub fn may_update_cache<MEM: MemoryDebugOperations>(&mut self, pc: u16, memory: &MEM) {
+    fn may_update_cache<DBG: DebugOperations>(&mut self, pc: u16, memory: &DBG) {
         if let Some(range) = self.cache_pc_valid_range {
             if pc < range.0 || pc >= range.1 {
                 self.update_cache(pc, memory)
@@ -30,39 +131,215 @@ impl DisassemblyViewer {
         }
     }
 
-    pub fn draw(&self, ui: &mut Ui) {
+    /// Draw the listing of the ROM, return the action requested on one of its lines
+    pub fn draw<DBG: DebugOperations>(
+        &mut self,
+        ui: &mut Ui,
+        dbg: &DBG,
+        has_breakpoint: impl Fn(&Node) -> bool,
+    ) -> Option<ListingAction> {
+        let mut toggled = None;
+
         ui.vertical(|ui| {
             ui.colored_label(Color32::LIGHT_BLUE, "Disassembler");
             ui.separator();
-            ui.vertical(|ui| {
-                egui::Grid::new("dissas_".to_owned())
-                    .striped(true)
-                    .spacing(Vec2::new(200.0, 2.5))
-                    .show(ui, |ui| {
-                        DisassemblyViewer::draw_labels(ui);
-                        let mut pc = self.cache_pc_valid_range.unwrap_or((0, 0)).0;
-                        for (index, row) in self.cache.iter().take(8).enumerate() {
-                            DisassemblyViewer::draw_row(ui, &mut pc, row, index);
-                        }
-                        ui.end_row();
-                    });
-            });
+            self.draw_toolbar(ui, dbg);
+            if matches!(self.pc, Some(pc) if pc >= 0x8000) {
+                self.draw_ram(ui);
+                ui.separator();
+            }
+            toggled = self.draw_listing(ui, dbg, has_breakpoint);
+        });
+        toggled
+    }
+
+    fn draw_toolbar<DBG: DebugOperations>(&mut self, ui: &mut Ui, dbg: &DBG) {
+        let bank_count = self.disassembly.as_ref().map_or(2, Disassembly::bank_count);
+
+        ui.horizontal(|ui| {
+            ui.label("Bank");
+            if ui
+                .add(egui::DragValue::new(&mut self.bank).clamp_range(1..=bank_count - 1))
+                .changed()
+            {
+                self.follow_pc = false;
+            }
+            ui.checkbox(&mut self.follow_pc, "Follow PC");
+            if ui
+                .add_enabled(!self.history.is_empty(), egui::Button::new("◀ Back"))
+                .clicked()
+            {
+                if let Some((bank, address)) = self.history.pop() {
+                    self.bank = bank;
+                    self.scroll_to = Some(address);
+                    self.follow_pc = false;
+                }
+            }
         });
+        ui.horizontal(|ui| {
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut self.goto_field)
+                    .desired_width(120.0)
+                    .hint_text("01:4000 or label"),
+            );
+            if ui.button("Go to").clicked()
+                || (response.lost_focus() && ui.input().key_pressed(egui::Key::Enter))
+            {
+                match self.parse_goto(dbg, bank_count) {
+                    Ok((bank, address)) => {
+                        self.goto(bank, address);
+                        self.goto_error = None;
+                    }
+                    Err(e) => self.goto_error = Some(e),
+                }
+            }
+            if let Some(error) = &self.goto_error {
+                ui.colored_label(Color32::RED, error);
+            }
+        });
+    }
+
+    /// Scroll the listing to `address`, `bank` select the ROM bank listed after the bank 0
+    pub fn goto(&mut self, bank: Option<usize>, address: u16) {
+        let bank_count = self.disassembly.as_ref().map_or(2, Disassembly::bank_count);
+        if let Some(bank) = bank.filter(|&bank| bank != 0 && bank < bank_count) {
+            self.bank = bank;
+        }
+        self.scroll_to = Some(address);
+        self.follow_pc = false;
+    }
+
+    /// Parse the go-to field, in the form `4000`, `01:4000`, `label` or `@label`,
+    /// like in the breakpoint expressions the hexadecimal values take precedence over the labels
+    fn parse_goto<DBG: DebugOperations>(
+        &self,
+        dbg: &DBG,
+        bank_count: usize,
+    ) -> Result<(Option<usize>, u16), String> {
+        let field = self.goto_field.trim();
+        let label = field
+            .strip_prefix('@')
+            .or_else(|| (hex_word(field).is_none() && !field.contains(':')).then(|| field));
+        let (bank, address) = if let Some(label) = label {
+            let symbol = dbg
+                .symbols()
+                .get(label)
+                .ok_or_else(|| format!("unknown label `{}`", label))?;
+            (Some(symbol.bank as usize), symbol.address)
+        } else if let Some((bank, address)) = field.split_once(':') {
+            let bank = usize::from_str_radix(bank, 16).map_err(|e| e.to_string())?;
+            let address = u16::from_str_radix(address, 16).map_err(|e| e.to_string())?;
+            (Some(bank), address)
+        } else {
+            let address = u16::from_str_radix(field, 16).map_err(|e| e.to_string())?;
+            (None, address)
+        };
+
+        match (bank, address) {
+            (_, 0x8000..=0xffff) => Err(format!("{:04X} is not in the ROM", address)),
+            (_, 0x0000..=0x3fff) => Ok((None, address)),
+            (Some(bank), _) if bank == 0 || bank >= bank_count => {
+                Err(format!("no ROM bank {:02X}", bank))
+            }
+            _ => Ok((bank, address)),
+        }
+    }
+
+    fn draw_listing<DBG: DebugOperations>(
+        &mut self,
+        ui: &mut Ui,
+        dbg: &DBG,
+        has_breakpoint: impl Fn(&Node) -> bool,
+    ) -> Option<ListingAction> {
+        let disassembly = self.disassembly.as_mut()?;
+        let bank0_len = disassembly.lines(0).len();
+        let total_rows = bank0_len + disassembly.lines(self.bank).len();
+        let row_height = ui.spacing().interact_size.y;
+        let mut scroll_area = egui::ScrollArea::vertical()
+            .id_source("disassembly")
+            .max_height(320.0)
+            .auto_shrink([false, false]);
+
+        if let Some(address) = self.scroll_to.take() {
+            let row = match address {
+                0x0000..=0x3fff => disassembly.line_index(0, address),
+                _ => disassembly
+                    .line_index(self.bank, address)
+                    .map(|index| bank0_len + index),
+            };
+            if let Some(row) = row {
+                let row_height_with_spacing = row_height + ui.spacing().item_spacing.y;
+                scroll_area = scroll_area
+                    .vertical_scroll_offset(row.saturating_sub(4) as f32 * row_height_with_spacing);
+            }
+        }
+
+        let mut clicked = None;
+        scroll_area.show_rows(ui, row_height, total_rows, |ui, rows| {
+            for row in rows {
+                let (bank, index) = if row < bank0_len {
+                    (0, row)
+                } else {
+                    (self.bank, row - bank0_len)
+                };
+                let line = disassembly.lines(bank)[index];
+                let expr = breakpoint_expr(bank, line.address);
+                let is_pc = self.pc == Some(line.address) && (bank == 0 || bank == dbg.rom_bank());
+
+                match draw_line(ui, dbg, bank, line, is_pc, has_breakpoint(&expr)) {
+                    Some(RowAction::ToggleBreakpoint) => {
+                        clicked = Some(ListingAction::ToggleBreakpoint(expr))
+                    }
+                    Some(RowAction::RunTo) => clicked = Some(ListingAction::RunTo(line.address)),
+                    Some(RowAction::Follow(target)) => {
+                        self.history.push((self.bank, line.address));
+                        self.scroll_to = Some(target);
+                        self.follow_pc = false;
+                    }
+                    None => {}
+                }
+            }
+        });
+        clicked
+    }
+
+    /// Draw the instructions decoded from PC when it run outside of the ROM
+    fn draw_ram(&self, ui: &mut Ui) {
+        egui::Grid::new("dissas_".to_owned())
+            .striped(true)
+            .spacing(Vec2::new(100.0, 2.5))
+            .show(ui, |ui| {
+                DisassemblyViewer::draw_labels(ui);
+                let mut pc = self.cache_pc_valid_range.unwrap_or((0, 0)).0;
+                for (index, row) in self.cache.iter().take(8).enumerate() {
+                    let location = self.locations.get(index).map_or("", String::as_str);
+                    DisassemblyViewer::draw_row(ui, &mut pc, row, location, index);
+                }
+                ui.end_row();
+            });
     }
 
     fn draw_labels(ui: &mut Ui) {
         ui.colored_label(Color32::GOLD, "Address");
+        ui.colored_label(Color32::GOLD, "Label");
         ui.colored_label(Color32::GOLD, "Instruction");
         ui.colored_label(Color32::GOLD, "Data");
         ui.end_row();
     }
 
-    fn draw_row(ui: &mut Ui, pc: &mut u16, row: &Result<(Opcode, Vec<u8>), Error>, index: usize) {
+    fn draw_row(
+        ui: &mut Ui,
+        pc: &mut u16,
+        row: &Result<(Opcode, Vec<u8>), Error>,
+        location: &str,
+        index: usize,
+    ) {
         let mut text_color = Color32::GRAY;
         if index == 0 {
             text_color = Color32::WHITE;
         }
         ui.colored_label(text_color, format!("0x{:04X}", pc));
+        ui.colored_label(ADDRESS_COLOR, location);
         *pc += opcode_len(row);
         let opcode = row
             .as_ref()
@@ -87,6 +364,138 @@ impl DisassemblyViewer {
     }
 }
 
+/// Expression of the breakpoint set by clicking on the line at `address` of `bank`
+fn breakpoint_expr(bank: usize, address: u16) -> Node {
+    if bank == 0 {
+        Node::simple(address)
+    } else {
+        Node::banked(bank as u16, address)
+    }
+}
+
+fn draw_line<DBG: DebugOperations>(
+    ui: &mut Ui,
+    dbg: &DBG,
+    bank: usize,
+    line: Line,
+    is_pc: bool,
+    has_breakpoint: bool,
+) -> Option<RowAction> {
+    let row_height = ui.spacing().interact_size.y;
+    let base = Disassembly::base(bank);
+    let bytes: Vec<u8> = (line.address..line.address + line.len as u16)
+        .map(|address| dbg.read_rom(bank, address - base))
+        .collect();
+    let text_color = match (is_pc, line.is_code) {
+        (true, _) => Color32::WHITE,
+        (false, true) => Color32::GRAY,
+        (false, false) => Color32::from_gray(110),
+    };
+    let mut action = None;
+
+    ui.horizontal(|ui| {
+        let marker = if has_breakpoint {
+            RichText::new("●").color(Color32::RED)
+        } else if is_pc {
+            RichText::new("▶").color(Color32::WHITE)
+        } else {
+            RichText::new("○").color(Color32::from_gray(60))
+        };
+        let marker = ui.add_sized([14.0, row_height], Label::new(marker).sense(Sense::click()));
+        let address = ui.add_sized(
+            [64.0, row_height],
+            Label::new(
+                RichText::new(format!("{:02X}:{:04X}", bank, line.address))
+                    .monospace()
+                    .color(ADDRESS_COLOR),
+            )
+            .sense(Sense::click()),
+        );
+        let address = address
+            .on_hover_text("toggle a breakpoint, right click for more")
+            .context_menu(|ui| {
+                if ui.button("Run to here").clicked() {
+                    action = Some(RowAction::RunTo);
+                    ui.close_menu();
+                }
+            });
+        if marker.clicked() || address.clicked() {
+            action = Some(RowAction::ToggleBreakpoint);
+        }
+
+        let location = dbg
+            .symbols()
+            .locate(Some(bank as u16), line.address)
+            .filter(|_| line.is_code)
+            .map(|location| location.to_string())
+            .unwrap_or_default();
+        ui.add_sized(
+            [150.0, row_height],
+            Label::new(RichText::new(location).color(ADDRESS_COLOR)),
+        );
+
+        if line.is_code {
+            let decoded = decode(
+                |bank, offset| dbg.read_rom(bank, offset),
+                bank,
+                line.address,
+            );
+            let (text, target) = match decoded {
+                Some(Ok((opcode, _))) => {
+                    let (target, _) = successors(&opcode, line.address + line.len as u16);
+                    (opcode.to_string(), target.filter(|&target| target < 0x8000))
+                }
+                _ => ("??".to_owned(), None),
+            };
+            let text = RichText::new(text).monospace().color(text_color);
+            match target {
+                Some(target) => {
+                    let response = ui.add_sized(
+                        [150.0, row_height],
+                        Label::new(text.underline()).sense(Sense::click()),
+                    );
+                    if response
+                        .on_hover_text(format!("follow to {:04X}", target))
+                        .clicked()
+                    {
+                        action = Some(RowAction::Follow(target));
+                    }
+                }
+                None => {
+                    ui.add_sized([150.0, row_height], Label::new(text));
+                }
+            }
+        } else {
+            let data = bytes
+                .iter()
+                .map(|byte| format!("${:02X}", byte))
+                .collect::<Vec<String>>()
+                .join(",");
+            ui.add_sized(
+                [150.0, row_height],
+                Label::new(
+                    RichText::new(format!("DB {}", data))
+                        .monospace()
+                        .color(text_color),
+                ),
+            );
+        }
+        if line.is_code {
+            let bytes = bytes
+                .iter()
+                .map(|byte| format!("{:02X}", byte))
+                .collect::<Vec<String>>()
+                .join(" ");
+            ui.label(
+                RichText::new(bytes)
+                    .monospace()
+                    .color(Color32::from_gray(110)),
+            );
+        }
+    });
+    action
+}
+
 fn opcode_len(opc: &Result<(Opcode, Vec<u8>), Error>) -> u16 {
     opc.as_ref().map_or(1, |(_, bytes)| bytes.len() as u16)
 }
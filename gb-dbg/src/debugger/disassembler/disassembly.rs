@@ -0,0 +1,339 @@
+use gb_roms::code_data_log::CodeDataLog;
+use gb_roms::opcode::{error::Error, list::Opcode, value::Value, OpcodeGenerator};
+
+const BANK_SIZE: u16 = 0x4000;
+
+/// Addresses executed by the hardware without being referenced by the code:
+/// the `RST` vectors, the interrupt vectors and the entry point of the cartridge
+const ENTRY_POINTS: [u16; 14] = [
+    0x0000, 0x0008, 0x0010, 0x0018, 0x0020, 0x0028, 0x0030, 0x0038, 0x0040, 0x0048, 0x0050, 0x0058,
+    0x0060, 0x0100,
+];
+
+/// Nature of a byte of a ROM bank
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Byte {
+    /// Not reached by the code analysis, considered as data
+    Unknown,
+    /// First byte of an instruction of `u8` bytes
+    Code(u8),
+    /// Byte following the first byte of an instruction
+    Operand,
+    /// Byte only read as data according to the code/data log, never analysed as code
+    Data,
+}
+
+/// A line of the listing of a bank
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Line {
+    pub address: u16,
+    pub len: u8,
+    pub is_code: bool,
+}
+
+#[derive(Debug)]
+struct Bank {
+    bytes: Vec<Byte>,
+    lines: Option<Vec<Line>>,
+}
+
+impl Bank {
+    fn new() -> Self {
+        Self {
+            bytes: vec![Byte::Unknown; BANK_SIZE as usize],
+            lines: None,
+        }
+    }
+}
+
+/// Disassembly of the whole ROM that separate the code from the data by recursive traversal
+///
+/// The traversal start from the hardware vectors and from every executed address,
+/// then follows the jumps and calls with a known destination.
+#[derive(Debug)]
+pub struct Disassembly {
+    banks: Vec<Bank>,
+    pending: Vec<(usize, u16)>,
+}
+
+impl Disassembly {
+    pub fn new(bank_count: usize) -> Self {
+        Self {
+            banks: (0..bank_count.max(2)).map(|_| Bank::new()).collect(),
+            pending: ENTRY_POINTS.iter().map(|&address| (0, address)).collect(),
+        }
+    }
+
+    pub fn bank_count(&self) -> usize {
+        self.banks.len()
+    }
+
+    /// Return the first address of `bank`
+    pub fn base(bank: usize) -> u16 {
+        if bank == 0 {
+            0
+        } else {
+            BANK_SIZE
+        }
+    }
+
+    /// Notify that the instruction at `address` of `bank` was executed
+    pub fn mark_executed(&mut self, bank: usize, address: u16) {
+        if let Some(byte) = self.byte(bank, address) {
+            if !matches!(byte, Byte::Code(_)) {
+                self.pending.push((bank, address));
+            }
+        }
+    }
+
+    /// Use the usage of the ROM bytes logged while the game was running:
+    /// the executed instructions are followed and the bytes only read as data
+    /// are excluded from the analysis.
+    ///
+    /// Only the bytes changed after the first `since` changes of the log are applied
+    pub fn apply_log(&mut self, log: &CodeDataLog, since: usize) {
+        for &offset in log.changed_since(since).iter() {
+            let bank = offset / BANK_SIZE as usize;
+            let index = offset % BANK_SIZE as usize;
+            if bank >= self.banks.len() {
+                continue;
+            }
+            let flags = log.flags(offset);
+            let listing = &mut self.banks[bank];
+            if flags & CodeDataLog::CODE != 0 && listing.bytes[index] == Byte::Data {
+                listing.bytes[index] = Byte::Unknown;
+                listing.lines = None;
+            }
+            if flags & CodeDataLog::OPCODE != 0 {
+                self.mark_executed(bank, Self::base(bank) + index as u16);
+            } else if flags & CodeDataLog::CODE == 0
+                && flags & (CodeDataLog::DATA | CodeDataLog::DMA) != 0
+                && listing.bytes[index] == Byte::Unknown
+            {
+                listing.bytes[index] = Byte::Data;
+                listing.lines = None;
+            }
+        }
+    }
+
+    /// Follow the code from the pending addresses,
+    /// `read_rom` return the byte at an offset of a ROM bank
+    pub fn analyse(&mut self, read_rom: impl Fn(usize, u16) -> u8) {
+        while let Some((bank, address)) = self.pending.pop() {
+            if self.byte(bank, address) != Some(Byte::Unknown) {
+                continue;
+            }
+            let opcode = match decode(&read_rom, bank, address) {
+                Some(Ok(opcode)) => opcode,
+                _ => continue,
+            };
+            let len = opcode.1.len() as u16;
+            let end = address + len;
+            if end > Self::base(bank) + BANK_SIZE
+                || (address + 1..end).any(|a| self.byte(bank, a) != Some(Byte::Unknown))
+            {
+                continue;
+            }
+
+            let base = Self::base(bank);
+            let listing = &mut self.banks[bank];
+            listing.bytes[(address - base) as usize] = Byte::Code(len as u8);
+            for a in address + 1..end {
+                listing.bytes[(a - base) as usize] = Byte::Operand;
+            }
+            listing.lines = None;
+
+            let (target, fallthrough) = successors(&opcode.0, end);
+            if fallthrough {
+                self.pending.push((bank, end));
+            }
+            if let Some(target) = target {
+                if let Some(target_bank) = target_bank(bank, target) {
+                    self.pending.push((target_bank, target));
+                }
+            }
+        }
+    }
+
+    /// Return the listing of `bank`, the instructions are on their own line
+    /// and the data are grouped by 8 bytes
+    pub fn lines(&mut self, bank: usize) -> &[Line] {
+        let base = Self::base(bank);
+        let listing = &mut self.banks[bank];
+
+        listing.lines.get_or_insert_with(|| {
+            let mut lines = Vec::new();
+            let mut offset = 0;
+            while offset < BANK_SIZE {
+                let line = match listing.bytes[offset as usize] {
+                    Byte::Code(len) => Line {
+                        address: base + offset,
+                        len,
+                        is_code: true,
+                    },
+                    _ => {
+                        let len = listing.bytes[offset as usize..]
+                            .iter()
+                            .take(8)
+                            .take_while(|byte| !matches!(byte, Byte::Code(_)))
+                            .count();
+                        Line {
+                            address: base + offset,
+                            len: len as u8,
+                            is_code: false,
+                        }
+                    }
+                };
+                offset += line.len as u16;
+                lines.push(line);
+            }
+            lines
+        })
+    }
+
+    /// Return the index of the line containing `address`
+    pub fn line_index(&mut self, bank: usize, address: u16) -> Option<usize> {
+        let lines = self.lines(bank);
+        let index = lines.partition_point(|line| line.address <= address);
+
+        index
+            .checked_sub(1)
+            .filter(|&index| address < lines[index].address + lines[index].len as u16)
+    }
+
+    fn byte(&self, bank: usize, address: u16) -> Option<Byte> {
+        let offset = address.checked_sub(Self::base(bank))?;
+        self.banks.get(bank)?.bytes.get(offset as usize).copied()
+    }
+}
+
+/// Decode the instruction at `address` of `bank`
+pub fn decode(
+    read_rom: impl Fn(usize, u16) -> u8,
+    bank: usize,
+    address: u16,
+) -> Option<Result<(Opcode, Vec<u8>), Error>> {
+    let base = Disassembly::base(bank);
+    let bytes = (address..base + BANK_SIZE).map(|address| read_rom(bank, address - base));
+
+    OpcodeGenerator::from(bytes).next()
+}
+
+/// Return the destination of the instruction, if known,
+/// and whether the execution can continue on the next instruction at `next`
+pub fn successors(opcode: &Opcode, next: u16) -> (Option<u16>, bool) {
+    let relative = |offset: i8| next.wrapping_add(offset as u16);
+
+    match opcode {
+        Opcode::Jump(Value::Nn(address)) => (Some(*address), false),
+        Opcode::Jump(_) | Opcode::Return | Opcode::ReturnI => (None, false),
+        Opcode::JumpRelative(offset) => (Some(relative(*offset)), false),
+        Opcode::JumpConditional(_, address)
+        | Opcode::Call(address)
+        | Opcode::CallConditional(_, address) => (Some(*address), true),
+        Opcode::JumpRelativeConditional(_, offset) => (Some(relative(*offset)), true),
+        Opcode::Restart(address) => (Some(*address as u16), true),
+        _ => (None, true),
+    }
+}
+
+/// Return the bank where lie `target` when jumping from `bank`,
+/// the banked area is unknown from the bank 0
+fn target_bank(bank: usize, target: u16) -> Option<usize> {
+    match target {
+        0x0000..=0x3fff => Some(0),
+        0x4000..=0x7fff if bank != 0 => Some(bank),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test_disassembly {
+    use super::{Disassembly, Line};
+    use gb_roms::code_data_log::CodeDataLog;
+
+    fn rom(code: &[(usize, &[u8])]) -> Vec<u8> {
+        // filled with `RST 38`, which jump on a `JR -2` infinite loop
+        let mut data = vec![0xff; 0x8000];
+        data[0x38] = 0x18;
+        data[0x39] = 0xfe;
+        for (address, bytes) in code {
+            data[*address..*address + bytes.len()].copy_from_slice(bytes);
+        }
+        data
+    }
+
+    #[test]
+    fn recursive_traversal() {
+        let rom = rom(&[
+            (0x100, &[0x00, 0xc3, 0x50, 0x01]),       // NOP; JP 0150
+            (0x104, &[0xce, 0xed]),                   // logo
+            (0x150, &[0xcd, 0x60, 0x01, 0x18, 0xfe]), // CALL 0160; JR -2
+            (0x160, &[0x3e, 0x01, 0xc9]),             // LD A, 1; RET
+        ]);
+        let mut disassembly = Disassembly::new(2);
+        disassembly.analyse(|bank, offset| rom[bank * 0x4000 + offset as usize]);
+
+        let lines = disassembly.lines(0);
+        let line = |address| lines.iter().find(|line| line.address == address).copied();
+        assert_eq!(
+            line(0x101),
+            Some(Line {
+                address: 0x101,
+                len: 3,
+                is_code: true
+            })
+        );
+        assert_eq!(line(0x104).map(|line| line.is_code), Some(false));
+        assert_eq!(line(0x153).map(|line| line.is_code), Some(true));
+        assert_eq!(line(0x162).map(|line| line.is_code), Some(true));
+        assert_eq!(line(0x163).map(|line| line.is_code), Some(false));
+        assert_eq!(
+            disassembly.line_index(0, 0x152),
+            disassembly.line_index(0, 0x150)
+        );
+    }
+
+    #[test]
+    fn executed_banked_code() {
+        let rom = rom(&[(0x4010, &[0xc3, 0x20, 0x40]), (0x4020, &[0xc9])]);
+        let read_rom = |bank: usize, offset: u16| rom[bank * 0x4000 + offset as usize];
+        let mut disassembly = Disassembly::new(2);
+        disassembly.analyse(read_rom);
+        assert!(!disassembly.lines(1).iter().any(|line| line.is_code));
+
+        disassembly.mark_executed(1, 0x4010);
+        disassembly.analyse(read_rom);
+        let code: Vec<u16> = disassembly
+            .lines(1)
+            .iter()
+            .filter(|line| line.is_code)
+            .map(|line| line.address)
+            .collect();
+        assert_eq!(code, vec![0x4010, 0x4020]);
+    }
+
+    #[test]
+    fn code_data_log() {
+        // RST 38 at 0150 never return, the jump table following it is data
+        let rom = rom(&[
+            (0x100, &[0xc3, 0x50, 0x01]), // JP 0150
+            (0x150, &[0xff, 0x60, 0x01]), // RST 38; DW 0160
+            (0x160, &[0x18, 0xfe]),       // JR -2
+        ]);
+        let read_rom = |bank: usize, offset: u16| rom[bank * 0x4000 + offset as usize];
+        let log = CodeDataLog::new(rom.len());
+        log.mark(0x151, CodeDataLog::DATA);
+        log.mark(0x152, CodeDataLog::DATA);
+        log.mark(0x160, CodeDataLog::CODE | CodeDataLog::OPCODE);
+        log.mark(0x161, CodeDataLog::CODE | CodeDataLog::OPERAND);
+
+        let mut disassembly = Disassembly::new(2);
+        disassembly.apply_log(&log, 0);
+        disassembly.analyse(read_rom);
+        let lines = disassembly.lines(0);
+        let line = |address| lines.iter().find(|line| line.address == address).copied();
+        assert_eq!(line(0x151).map(|line| line.is_code), Some(false));
+        assert_eq!(line(0x160).map(|line| line.is_code), Some(true));
+    }
+}
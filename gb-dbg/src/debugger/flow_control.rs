@@ -2,9 +2,23 @@ use crate::until::Until;
 use egui::Ui;
 use std::ops::ControlFlow;
 
-pub struct FlowController;
+/// Move backward in the execution, using the history of the emulation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reverse {
+    /// Go back to the previous instruction
+    Step,
+    /// Go back to the last instruction that triggered a breakpoint or a watchpoint
+    Continue,
+}
+
+#[derive(Default)]
+pub struct FlowController {
+    /// Line used by the `Until LY` action
+    line: u8,
+}
+
 impl FlowController {
-    pub fn draw(&self, ui: &mut Ui) -> Option<ControlFlow<Until>> {
+    pub fn draw(&mut self, ui: &mut Ui) -> Option<ControlFlow<Until>> {
         let mut ret: Option<ControlFlow<Until>> = None;
         if ui.button("Continue").clicked() {
             log::debug!("clicked on continue");
@@ -18,6 +32,14 @@ impl FlowController {
             log::debug!("clicked on step");
             ret = Some(ControlFlow::Break(Until::Step(1)));
         }
+        if ui.button("Step Over").clicked() {
+            log::debug!("clicked on step over");
+            ret = Some(ControlFlow::Break(Until::StepOver));
+        }
+        if ui.button("Step Out").clicked() {
+            log::debug!("clicked on step out");
+            ret = Some(ControlFlow::Break(Until::StepOut));
+        }
         if ui.button("Cycle").clicked() {
             log::debug!("clicked on cycle");
             ret = Some(ControlFlow::Break(Until::Cycle(1)));
@@ -30,6 +52,43 @@ impl FlowController {
             log::debug!("clicked on one second");
             ret = Some(ControlFlow::Break(Until::Second(1)));
         }
+        ui.separator();
+        ui.menu_button("Until", |ui| {
+            if ui.button("Next interrupt").clicked() {
+                log::debug!("clicked on until interrupt");
+                ret = Some(ControlFlow::Break(Until::Interrupt));
+                ui.close_menu();
+            }
+            if ui.button("Next VBlank").clicked() {
+                log::debug!("clicked on until vblank");
+                ret = Some(ControlFlow::Break(Until::VBlank));
+                ui.close_menu();
+            }
+            ui.horizontal(|ui| {
+                if ui.button("LY =").clicked() {
+                    log::debug!("clicked on until LY = {}", self.line);
+                    ret = Some(ControlFlow::Break(Until::Line(self.line)));
+                    ui.close_menu();
+                }
+                ui.add(egui::DragValue::new(&mut self.line).clamp_range(0..=153));
+            });
+        });
+        ret
+    }
+    pub fn draw_reverse(&mut self, ui: &mut Ui) -> Option<Reverse> {
+        let mut ret = None;
+        if ui.button("Step Back").clicked() {
+            log::debug!("clicked on step back");
+            ret = Some(Reverse::Step);
+        }
+        if ui
+            .button("Reverse Continue")
+            .on_hover_text("go back to the last breakpoint or watchpoint reached")
+            .clicked()
+        {
+            log::debug!("clicked on reverse continue");
+            ret = Some(Reverse::Continue);
+        }
         ret
     }
 }
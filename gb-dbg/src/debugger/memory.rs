@@ -1,20 +1,34 @@
-use crate::dbg_interfaces::MemoryDebugOperations;
-use egui::{Color32, Ui};
+use crate::dbg_interfaces::{BankedArea, DebugOperations};
+use crate::symbols::mapped_bank;
+use egui::{Color32, Label, RichText, Ui};
 use egui_memory_editor::option_data::MemoryEditorOptions;
 use egui_memory_editor::MemoryEditor;
 use std::ops::Range;
 
+const ADDRESS_COLOR: Color32 = Color32::from_rgb(191, 64, 191);
+const LABEL_COLOR: Color32 = Color32::from_rgb(255, 204, 0);
+/// Number of bytes of a row of the memory annotated with the labels
+const ROW_LEN: u16 = 0x10;
+
 pub struct MemoryViewer {
     memory_editor: MemoryEditor,
+    options: MemoryEditorOptions,
+    /// Area shown bank by bank instead of the address space
+    area: Option<BankedArea>,
+    bank: usize,
+    /// Editor of the selected bank, rebuilt when the selection change
+    bank_editor: MemoryEditor,
+    /// Show the memory annotated with the labels of the symbol file instead of the editor
+    show_labels: bool,
 }
 
 impl MemoryViewer {
     pub fn new(address_ranges: Vec<(&'static str, Range<u16>)>) -> Self {
         let mut mem_options = MemoryEditorOptions::default();
-        mem_options.address_text_colour = Color32::from_rgb(191, 64, 191);
+        mem_options.address_text_colour = ADDRESS_COLOR;
         mem_options.is_resizable_column = false;
         mem_options.is_options_collapsed = false;
-        let mut mem_edit = MemoryEditor::new().with_options(mem_options);
+        let mut mem_edit = MemoryEditor::new().with_options(mem_options.clone());
         for (range_name, range) in address_ranges {
             mem_edit = mem_edit
                 .with_address_range(range_name, range.start as usize..range.end as usize + 1);
@@ -22,14 +36,143 @@ impl MemoryViewer {
 
         Self {
             memory_editor: mem_edit,
+            bank_editor: MemoryEditor::new().with_options(mem_options.clone()),
+            options: mem_options,
+            area: None,
+            bank: 0,
+            show_labels: false,
         }
     }
 
-    pub fn draw<MEM: MemoryDebugOperations>(&mut self, ui: &mut Ui, memory: &mut MEM) {
+    pub fn draw<DBG: DebugOperations>(&mut self, ui: &mut Ui, memory: &mut DBG) {
         ui.colored_label(Color32::LIGHT_BLUE, "Memory Editor");
-        self.memory_editor
-            .draw_editor_contents_read_only(ui, memory, |mem, address| {
-                mem.read(address as u16).into()
-            });
+        self.draw_bank_selector(ui, memory);
+        let show_labels = self.show_labels && !memory.symbols().is_empty();
+        match self.area {
+            None if show_labels => {
+                draw_labeled_rows(ui, memory, 0x0000, 0x1_0000, |mem, address| {
+                    (mapped_bank(mem, address), mem.read(address))
+                })
+            }
+            None => {
+                self.memory_editor
+                    .draw_editor_contents_read_only(ui, memory, |mem, address| {
+                        mem.read(address as u16).into()
+                    })
+            }
+            Some(area) if memory.bank_count(area) == 0 => {
+                ui.label(format!("No {} on this system", area));
+            }
+            Some(area) if show_labels => {
+                let (bank, start) = (self.bank, area.start(self.bank));
+                draw_labeled_rows(ui, memory, start, u32::from(area.size()), |mem, address| {
+                    (bank as u16, mem.read_bank(area, bank, address - start))
+                })
+            }
+            Some(area) => {
+                let (bank, start) = (self.bank, area.start(self.bank) as usize);
+                self.bank_editor
+                    .draw_editor_contents_read_only(ui, memory, |mem, address| {
+                        Some(mem.read_bank(area, bank, (address - start) as u16))
+                    })
+            }
+        }
+    }
+
+    /// Select the memory shown, either the mapped address space or a bank of an area
+    fn draw_bank_selector<DBG: DebugOperations>(&mut self, ui: &mut Ui, memory: &DBG) {
+        let (mut area, mut bank) = (self.area, self.bank);
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_source("memory_area")
+                .selected_text(area.map_or("Address space".to_string(), |area| area.to_string()))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut area, None, "Address space");
+                    for banked in BankedArea::ALL {
+                        ui.selectable_value(&mut area, Some(banked), banked.to_string());
+                    }
+                });
+            if let Some(area) = area {
+                let count = memory.bank_count(area);
+                bank = bank.min(count.saturating_sub(1));
+                if count > 1 {
+                    ui.add(
+                        egui::DragValue::new(&mut bank)
+                            .clamp_range(0..=count - 1)
+                            .prefix("bank "),
+                    );
+                    ui.label(format!("of {}", count));
+                }
+            }
+            if !memory.symbols().is_empty() {
+                ui.checkbox(&mut self.show_labels, "Labels");
+            }
+        });
+
+        if (area, bank) != (self.area, self.bank) {
+            self.area = area;
+            self.bank = bank;
+            if let Some(area) = area {
+                let start = area.start(bank) as usize;
+                self.bank_editor = MemoryEditor::new()
+                    .with_options(self.options.clone())
+                    .with_address_range(
+                        format!("{} {}", area, bank),
+                        start..start + area.size() as usize,
+                    );
+            }
+        }
     }
 }
+
+/// Draw `len` bytes from `start` in rows annotated with the labels placed in them,
+/// `read` return the bank of an address, used to find its labels, and its value
+fn draw_labeled_rows<DBG: DebugOperations>(
+    ui: &mut Ui,
+    memory: &DBG,
+    start: u16,
+    len: u32,
+    read: impl Fn(&DBG, u16) -> (u16, u8),
+) {
+    let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+    let row_count = (len / u32::from(ROW_LEN)) as usize;
+
+    egui::ScrollArea::vertical()
+        .id_source("memory_labels")
+        .show_rows(ui, row_height, row_count, |ui, rows| {
+            for row in rows {
+                let row_start = start + row as u16 * ROW_LEN;
+                let bank = read(memory, row_start).0;
+                let labels = memory
+                    .symbols()
+                    .labels_in(bank, row_start..=row_start + (ROW_LEN - 1))
+                    .collect::<Vec<_>>();
+
+                ui.horizontal(|ui| {
+                    ui.label(
+                        RichText::new(format!("{:02X}:{:04X}", bank, row_start))
+                            .monospace()
+                            .color(ADDRESS_COLOR),
+                    );
+                    for address in row_start..=row_start + (ROW_LEN - 1) {
+                        let value =
+                            RichText::new(format!("{:02X}", read(memory, address).1)).monospace();
+                        match labels.iter().find(|label| label.address == address) {
+                            Some(label) => {
+                                ui.add(Label::new(value.color(LABEL_COLOR)))
+                                    .on_hover_text(&label.name);
+                            }
+                            None => {
+                                ui.add(Label::new(value));
+                            }
+                        }
+                    }
+                    let names = labels
+                        .iter()
+                        .map(|label| label.name.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    ui.label(RichText::new(names).monospace().color(LABEL_COLOR));
+                });
+            }
+        });
+}
@@ -8,6 +8,8 @@ use std::ops::Range;
 pub struct DebuggerOptions {
     pub address_ranges: Vec<(&'static str, Range<u16>)>,
     pub breakpoints: Vec<String>,
+    /// Commands run by the console once the debugger is opened
+    pub script: Vec<String>,
 }
 
 impl Default for DebuggerOptions {
@@ -21,6 +23,7 @@ impl Default for DebuggerOptions {
                 ("HRAM", HRAM_START..HRAM_STOP),
             ],
             breakpoints: Default::default(),
+            script: Default::default(),
         }
     }
 }
@@ -0,0 +1,212 @@
+use crate::dbg_interfaces::DebugOperations;
+use crate::profiler::{Hotspot, Profiler, Routine, FRAME_HISTORY};
+use crate::symbols::SymbolTable;
+use egui::{pos2, vec2, Color32, Context, Rect, Sense, Stroke, Ui, Vec2};
+
+const GRAPH_HEIGHT: f32 = 80.0;
+const BAR_WIDTH: f32 = 3.0;
+/// Number of routines listed
+const MAX_SHOWN: usize = 100;
+
+/// Window showing the cycles spent in each routine and the CPU usage of the last frames
+#[derive(Default, Debug)]
+pub struct ProfilerViewer {
+    pub open: bool,
+    sort_inclusive: bool,
+    /// List the instructions instead of the routines
+    show_hotspots: bool,
+}
+
+impl ProfilerViewer {
+    pub fn draw<DBG: DebugOperations>(&mut self, ctx: &Context, dbg: &mut DBG) {
+        let mut open = self.open;
+        egui::Window::new("Profiler")
+            .open(&mut open)
+            .default_width(420.0)
+            .show(ctx, |ui| self.draw_profiler(ui, dbg));
+        self.open = open;
+    }
+
+    fn draw_profiler<DBG: DebugOperations>(&mut self, ui: &mut Ui, dbg: &mut DBG) {
+        let profiler = dbg.profiler();
+        ui.horizontal(|ui| {
+            let mut enabled = profiler.is_enabled();
+            if ui.checkbox(&mut enabled, "Record").changed() {
+                if enabled {
+                    profiler.start();
+                } else {
+                    profiler.stop();
+                }
+            }
+            if ui.button("Reset").clicked() {
+                profiler.reset();
+            }
+        });
+        let (busy, idle) = (profiler.busy(), profiler.idle());
+        ui.label(format!(
+            "{} cycles executed, {} idle ({:.1}% CPU)",
+            busy,
+            idle,
+            percent(busy, busy + idle)
+        ));
+        ui.separator();
+        draw_usage(ui, profiler);
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            ui.selectable_value(&mut self.show_hotspots, false, "Routines");
+            ui.selectable_value(&mut self.show_hotspots, true, "Hotspots")
+                .on_hover_text("cycles executed by each instruction");
+        });
+        if self.show_hotspots {
+            let hotspots = dbg.profiler().hotspots();
+            draw_hotspots(ui, dbg.symbols(), &hotspots, busy);
+            return;
+        }
+
+        let mut routines = dbg.profiler().routines();
+        if self.sort_inclusive {
+            routines.sort_by_key(|(_, cycles)| std::cmp::Reverse(cycles.inclusive));
+        }
+        ui.horizontal(|ui| {
+            ui.label("Sort by");
+            ui.selectable_value(&mut self.sort_inclusive, false, "exclusive");
+            ui.selectable_value(&mut self.sort_inclusive, true, "inclusive");
+        });
+        let symbols = dbg.symbols();
+        egui::ScrollArea::vertical()
+            .id_source("profiler_routines")
+            .max_height(300.0)
+            .show(ui, |ui| {
+                egui::Grid::new("profiler_routines_grid")
+                    .striped(true)
+                    .spacing(Vec2::new(16.0, 4.0))
+                    .show(ui, |ui| {
+                        ui.colored_label(Color32::GOLD, "Routine");
+                        ui.colored_label(Color32::GOLD, "Inclusive");
+                        ui.colored_label(Color32::GOLD, "%");
+                        ui.colored_label(Color32::GOLD, "Exclusive");
+                        ui.colored_label(Color32::GOLD, "%");
+                        ui.end_row();
+                        for (routine, cycles) in routines.iter().take(MAX_SHOWN) {
+                            ui.colored_label(
+                                Color32::from_rgb(191, 64, 191),
+                                routine_name(symbols, routine),
+                            );
+                            ui.label(cycles.inclusive.to_string());
+                            ui.label(format!("{:.1}", percent(cycles.inclusive, busy)));
+                            ui.label(cycles.exclusive.to_string());
+                            ui.label(format!("{:.1}", percent(cycles.exclusive, busy)));
+                            ui.end_row();
+                        }
+                    });
+                if routines.len() > MAX_SHOWN {
+                    ui.label(format!("{} more", routines.len() - MAX_SHOWN));
+                }
+            });
+    }
+}
+
+/// List the instructions executing the most cycles
+fn draw_hotspots(ui: &mut Ui, symbols: &SymbolTable, hotspots: &[(Hotspot, u64)], busy: u64) {
+    egui::ScrollArea::vertical()
+        .id_source("profiler_hotspots")
+        .max_height(300.0)
+        .show(ui, |ui| {
+            egui::Grid::new("profiler_hotspots_grid")
+                .striped(true)
+                .spacing(Vec2::new(16.0, 4.0))
+                .show(ui, |ui| {
+                    ui.colored_label(Color32::GOLD, "Address");
+                    ui.colored_label(Color32::GOLD, "Label");
+                    ui.colored_label(Color32::GOLD, "Cycles");
+                    ui.colored_label(Color32::GOLD, "%");
+                    ui.end_row();
+                    for (hotspot, cycles) in hotspots.iter().take(MAX_SHOWN) {
+                        ui.colored_label(Color32::from_rgb(191, 64, 191), hotspot.to_string());
+                        ui.label(hotspot_label(symbols, hotspot));
+                        ui.label(cycles.to_string());
+                        ui.label(format!("{:.1}", percent(*cycles, busy)));
+                        ui.end_row();
+                    }
+                });
+            if hotspots.len() > MAX_SHOWN {
+                ui.label(format!("{} more", hotspots.len() - MAX_SHOWN));
+            }
+        });
+}
+
+/// Draw the part of each frame spent executing instructions, the rest being spent halted
+fn draw_usage(ui: &mut Ui, profiler: &Profiler) {
+    let size = vec2(FRAME_HISTORY as f32 * BAR_WIDTH, GRAPH_HEIGHT);
+    let (response, painter) = ui.allocate_painter(size, Sense::hover());
+    let rect = response.rect;
+    painter.rect_filled(rect, 0.0, Color32::from_gray(10));
+
+    let frames = profiler.frames();
+    for (i, frame) in frames.iter().enumerate() {
+        let usage = frame.usage();
+        let height = usage * GRAPH_HEIGHT;
+        let x = rect.min.x + i as f32 * BAR_WIDTH;
+        let color = if usage >= 1.0 {
+            Color32::RED
+        } else if usage >= 0.9 {
+            Color32::YELLOW
+        } else {
+            Color32::GREEN
+        };
+        painter.rect_filled(
+            Rect::from_min_max(
+                pos2(x, rect.max.y - height),
+                pos2(x + BAR_WIDTH, rect.max.y),
+            ),
+            0.0,
+            color,
+        );
+    }
+    // mark 90% of the frame, the frames above have almost no idle time left
+    let y = rect.min.y + GRAPH_HEIGHT * 0.1;
+    painter.line_segment(
+        [pos2(rect.min.x, y), pos2(rect.max.x, y)],
+        Stroke::new(1.0, Color32::from_white_alpha(40)),
+    );
+
+    if let Some(pointer) = response.hover_pos() {
+        let i = ((pointer.x - rect.min.x) / BAR_WIDTH) as usize;
+        if let Some(frame) = frames.get(i) {
+            response.on_hover_ui_at_pointer(|ui| {
+                ui.label(format!(
+                    "{:.1}% CPU, {} cycles executed, {} idle",
+                    frame.usage() * 100.0,
+                    frame.busy,
+                    frame.idle
+                ));
+            });
+        }
+    }
+}
+
+/// Return the label of the routine, or its address when it has none
+fn routine_name(symbols: &SymbolTable, routine: &Routine) -> String {
+    match symbols.locate(Some(routine.bank as u16), routine.address) {
+        Some(location) if location.offset == 0 => location.symbol.name.clone(),
+        _ => routine.to_string(),
+    }
+}
+
+/// Return the label containing the instruction with the offset from it, empty without label
+fn hotspot_label(symbols: &SymbolTable, hotspot: &Hotspot) -> String {
+    match symbols.locate(Some(hotspot.bank as u16), hotspot.address) {
+        Some(found) if found.offset == 0 => found.symbol.name.clone(),
+        Some(found) => format!("{}+{}", found.symbol.name, found.offset),
+        None => String::new(),
+    }
+}
+
+fn percent(part: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 * 100.0 / total as f64
+    }
+}
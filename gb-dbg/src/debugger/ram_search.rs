@@ -0,0 +1,313 @@
+use crate::dbg_interfaces::DebugOperations;
+use crate::search::{parse_value, Filter, Region, Search, ValueSize};
+use crate::symbols::mapped_bank;
+use egui::{Color32, Context, RichText, Ui, Vec2};
+use gb_bus::watchpoint::{AccessMask, Trigger};
+use gb_bus::Watchpoint;
+
+/// Number of candidates listed, the search must be narrowed to see the others
+const MAX_SHOWN: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FilterKind {
+    Unchanged,
+    Changed,
+    Increased,
+    Decreased,
+    IncreasedBy,
+    DecreasedBy,
+    Value,
+}
+
+impl FilterKind {
+    const ALL: [FilterKind; 7] = [
+        FilterKind::Unchanged,
+        FilterKind::Changed,
+        FilterKind::Increased,
+        FilterKind::Decreased,
+        FilterKind::IncreasedBy,
+        FilterKind::DecreasedBy,
+        FilterKind::Value,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            FilterKind::Unchanged => "unchanged",
+            FilterKind::Changed => "changed",
+            FilterKind::Increased => "increased",
+            FilterKind::Decreased => "decreased",
+            FilterKind::IncreasedBy => "increased by",
+            FilterKind::DecreasedBy => "decreased by",
+            FilterKind::Value => "equal to",
+        }
+    }
+
+    fn need_value(&self) -> bool {
+        matches!(
+            self,
+            FilterKind::IncreasedBy | FilterKind::DecreasedBy | FilterKind::Value
+        )
+    }
+
+    fn to_filter(self, value: u16) -> Filter {
+        match self {
+            FilterKind::Unchanged => Filter::Unchanged,
+            FilterKind::Changed => Filter::Changed,
+            FilterKind::Increased => Filter::Increased,
+            FilterKind::Decreased => Filter::Decreased,
+            FilterKind::IncreasedBy => Filter::IncreasedBy(value),
+            FilterKind::DecreasedBy => Filter::DecreasedBy(value),
+            FilterKind::Value => Filter::Value(value),
+        }
+    }
+}
+
+/// Window searching the addresses of the variables of the game,
+/// the results can be watched or frozen
+#[derive(Debug)]
+pub struct RamSearch {
+    pub open: bool,
+    regions: [bool; 3],
+    size: ValueSize,
+    filter: FilterKind,
+    value_field: String,
+    search: Option<Search>,
+    /// Bytes written at the end of each frame
+    frozen: Vec<(u16, u8)>,
+}
+
+impl Default for RamSearch {
+    fn default() -> Self {
+        Self {
+            open: false,
+            regions: [true, true, false],
+            size: ValueSize::U8,
+            filter: FilterKind::Changed,
+            value_field: String::new(),
+            search: None,
+            frozen: Vec::new(),
+        }
+    }
+}
+
+impl RamSearch {
+    /// Draw the window, return the watchpoint requested on a candidate
+    pub fn draw<DBG: DebugOperations>(
+        &mut self,
+        ctx: &Context,
+        dbg: &mut DBG,
+    ) -> Option<Watchpoint> {
+        let mut watch = None;
+        let mut open = self.open;
+        egui::Window::new("RAM Search")
+            .open(&mut open)
+            .default_width(360.0)
+            .show(ctx, |ui| {
+                self.draw_search(ui, dbg);
+                ui.separator();
+                watch = self.draw_candidates(ui, dbg);
+                ui.separator();
+                self.draw_frozen(ui, dbg);
+            });
+        self.open = open;
+
+        if dbg.frozen() != self.frozen.as_slice() {
+            dbg.set_frozen(self.frozen.clone());
+        }
+        watch
+    }
+
+    fn draw_search<DBG: DebugOperations>(&mut self, ui: &mut Ui, dbg: &DBG) {
+        let read = |region, bank, address| read_bank(dbg, region, bank, address);
+        ui.horizontal(|ui| {
+            for (region, selected) in Region::ALL.iter().zip(self.regions.iter_mut()) {
+                ui.checkbox(selected, region.to_string());
+            }
+        });
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_source("ram_search_size")
+                .selected_text(self.size.to_string())
+                .show_ui(ui, |ui| {
+                    for size in ValueSize::ALL {
+                        ui.selectable_value(&mut self.size, size, size.to_string());
+                    }
+                });
+            let regions: Vec<(Region, usize)> = Region::ALL
+                .into_iter()
+                .zip(self.regions)
+                .filter(|(_, selected)| *selected)
+                .map(|(region, _)| {
+                    let banks = region.area().map_or(0, |area| dbg.bank_count(area));
+                    (region, banks)
+                })
+                .collect();
+            let label = if self.search.is_some() {
+                "Restart"
+            } else {
+                "Start"
+            };
+            if ui
+                .add_enabled(!regions.is_empty(), egui::Button::new(label))
+                .on_hover_text("snapshot the memory, every address of every bank is a candidate")
+                .clicked()
+            {
+                self.search = Some(Search::start(&regions, self.size, read));
+            }
+            if ui
+                .add_enabled(self.search.is_some(), egui::Button::new("Clear"))
+                .clicked()
+            {
+                self.search = None;
+            }
+        });
+
+        let value = parse_value(&self.value_field).filter(|value| *value <= self.size.max());
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_source("ram_search_filter")
+                .selected_text(self.filter.label())
+                .width(100.0)
+                .show_ui(ui, |ui| {
+                    for kind in FilterKind::ALL {
+                        ui.selectable_value(&mut self.filter, kind, kind.label());
+                    }
+                });
+            if self.filter.need_value() {
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.value_field)
+                        .desired_width(60.0)
+                        .hint_text("10, 0x0A"),
+                );
+            }
+            let ready = self.search.is_some() && (value.is_some() || !self.filter.need_value());
+            if ui
+                .add_enabled(ready, egui::Button::new("Filter"))
+                .on_hover_text("take a new snapshot and keep the matching candidates")
+                .clicked()
+            {
+                if let Some(search) = &mut self.search {
+                    search.filter(self.filter.to_filter(value.unwrap_or_default()), read);
+                }
+            }
+        });
+    }
+
+    fn draw_candidates<DBG: DebugOperations>(
+        &mut self,
+        ui: &mut Ui,
+        dbg: &DBG,
+    ) -> Option<Watchpoint> {
+        let search = match &mut self.search {
+            Some(search) => search,
+            None => {
+                ui.label("No search in progress");
+                return None;
+            }
+        };
+        let size = search.size();
+        ui.label(format!(
+            "{} candidates after {} filters",
+            search.candidates().len(),
+            search.steps()
+        ));
+
+        let mut watch = None;
+        let mut discarded = None;
+        egui::ScrollArea::vertical()
+            .id_source("ram_search_candidates")
+            .max_height(200.0)
+            .show(ui, |ui| {
+                egui::Grid::new("ram_search_candidates_grid")
+                    .striped(true)
+                    .spacing(Vec2::new(12.0, 4.0))
+                    .show(ui, |ui| {
+                        ui.colored_label(Color32::GOLD, "Bank:Address");
+                        ui.colored_label(Color32::GOLD, "Previous");
+                        ui.colored_label(Color32::GOLD, "Current");
+                        ui.end_row();
+                        for candidate in search.candidates().iter().take(MAX_SHOWN) {
+                            let current = candidate.read(size, |region, bank, address| {
+                                read_bank(dbg, region, bank, address)
+                            });
+                            ui.colored_label(
+                                Color32::from_rgb(191, 64, 191),
+                                format!("{:02X}:{:04X}", candidate.bank, candidate.address),
+                            );
+                            ui.label(candidate.value.to_string());
+                            ui.label(current.to_string());
+                            // the watchpoints and the frozen bytes apply to the mapped bank
+                            let mapped =
+                                usize::from(mapped_bank(dbg, candidate.address)) == candidate.bank;
+                            if ui
+                                .add_enabled(mapped, egui::Button::new("Watch"))
+                                .on_hover_text("break on write")
+                                .on_disabled_hover_text("the bank isn't mapped")
+                                .clicked()
+                            {
+                                let end = candidate.address + (size.byte_count() - 1);
+                                watch = Some(Watchpoint::new(
+                                    candidate.address..=end,
+                                    AccessMask::WRITE,
+                                    Trigger::Any,
+                                ));
+                            }
+                            if ui
+                                .add_enabled(mapped, egui::Button::new("Freeze"))
+                                .on_disabled_hover_text("the bank isn't mapped")
+                                .clicked()
+                            {
+                                for (offset, byte) in size.bytes(current).into_iter().enumerate() {
+                                    let address = candidate.address + offset as u16;
+                                    self.frozen.retain(|(frozen, _)| *frozen != address);
+                                    self.frozen.push((address, byte));
+                                }
+                            }
+                            if ui.button(RichText::new("-").color(Color32::RED)).clicked() {
+                                discarded = Some(*candidate);
+                            }
+                            ui.end_row();
+                        }
+                    });
+                if search.candidates().len() > MAX_SHOWN {
+                    ui.label(format!("{} more", search.candidates().len() - MAX_SHOWN));
+                }
+            });
+        if let Some(candidate) = discarded {
+            search.discard(&candidate);
+        }
+        watch
+    }
+
+    fn draw_frozen<DBG: DebugOperations>(&mut self, ui: &mut Ui, dbg: &DBG) {
+        ui.colored_label(Color32::LIGHT_BLUE, "Frozen");
+        if self.frozen.is_empty() {
+            ui.label("No frozen address");
+            return;
+        }
+        let mut deletion = None;
+        egui::Grid::new("ram_search_frozen")
+            .striped(true)
+            .spacing(Vec2::new(12.0, 4.0))
+            .show(ui, |ui| {
+                for (i, (address, value)) in self.frozen.iter_mut().enumerate() {
+                    if ui.button(RichText::new("-").color(Color32::RED)).clicked() {
+                        deletion = Some(i);
+                    }
+                    ui.colored_label(Color32::from_rgb(191, 64, 191), format!("{:04X}", address));
+                    ui.add(egui::DragValue::new(value));
+                    ui.label(format!("now {}", dbg.read(*address)));
+                    ui.end_row();
+                }
+            });
+        if let Some(i) = deletion {
+            self.frozen.remove(i);
+        }
+    }
+}
+
+/// Read a byte of `bank` of `region`, the bank doesn't need to be mapped
+fn read_bank<DBG: DebugOperations>(dbg: &DBG, region: Region, bank: usize, address: u16) -> u8 {
+    match region.area() {
+        Some(area) => dbg.read_bank(area, bank, address - area.start(bank)),
+        None => dbg.read(address),
+    }
+}
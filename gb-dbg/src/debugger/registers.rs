@@ -1,9 +1,17 @@
+mod bitfields;
+
 use crate::dbg_interfaces::{RegisterDebugOperations, RegisterMap, RegisterValue};
+use bitfields::Bitfields;
 
 use egui::style::Margin;
-use egui::{Color32, Ui, Vec2};
+use egui::{Color32, Label, RichText, Sense, Ui, Vec2};
+use std::collections::HashSet;
 
-pub struct RegisterEditor;
+#[derive(Default)]
+pub struct RegisterEditor {
+    /// Registers whose fields are shown, by table and name
+    expanded: HashSet<(String, String)>,
+}
 
 impl RegisterEditor {
     pub fn draw<REG: RegisterDebugOperations>(&mut self, ui: &mut Ui, register: &REG) {
@@ -30,8 +38,8 @@ impl RegisterEditor {
         });
     }
 
-    fn draw_register_table<T: std::fmt::Display + std::fmt::Debug>(
-        &self,
+    fn draw_register_table<T: std::fmt::Display + std::fmt::Debug + Bitfields>(
+        &mut self,
         registers: Vec<RegisterMap<T>>,
         name: &str,
         ui: &mut Ui,
@@ -53,17 +61,51 @@ impl RegisterEditor {
                                     RegisterValue::U16(v) => format!("0x{:04X}", v),
                                 };
 
+                                let fields = match row.1 {
+                                    RegisterValue::U8(v) => row.0.bitfields(v),
+                                    RegisterValue::U16(_) => Vec::new(),
+                                };
+                                let key = (name.to_owned(), format!("{:?}", &row.0));
+                                let expanded = self.expanded.contains(&key);
+
                                 // Only display tooltip if tooltip string is different from register name
-                                if format!("{}", &row.0).to_lowercase()
-                                    != format!("{:?}", &row.0).to_lowercase()
-                                {
-                                    ui.colored_label(Color32::WHITE, format!("{:?}", &row.0))
-                                        .on_hover_text(row.0.to_string());
+                                let has_tooltip = format!("{}", &row.0).to_lowercase()
+                                    != format!("{:?}", &row.0).to_lowercase();
+                                let mut label = if has_tooltip {
+                                    format!("{:?}", &row.0)
                                 } else {
-                                    ui.colored_label(Color32::WHITE, format!("{}", &row.0));
+                                    format!("{}", &row.0)
+                                };
+                                if !fields.is_empty() {
+                                    label.insert_str(0, if expanded { "⏷ " } else { "⏵ " });
+                                }
+                                let response = ui.add(
+                                    Label::new(RichText::new(label).color(Color32::WHITE))
+                                        .sense(Sense::click()),
+                                );
+                                if !fields.is_empty() && response.clicked() {
+                                    if expanded {
+                                        self.expanded.remove(&key);
+                                    } else {
+                                        self.expanded.insert(key);
+                                    }
+                                }
+                                if has_tooltip {
+                                    response.on_hover_text(row.0.to_string());
                                 }
                                 ui.colored_label(Color32::WHITE, format);
                                 ui.end_row();
+
+                                if expanded {
+                                    for field in fields {
+                                        ui.colored_label(
+                                            Color32::GRAY,
+                                            format!("  {}", field.name),
+                                        );
+                                        ui.colored_label(Color32::LIGHT_GRAY, field.value);
+                                        ui.end_row();
+                                    }
+                                }
                             }
                         });
                 });
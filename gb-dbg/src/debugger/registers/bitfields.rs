@@ -0,0 +1,233 @@
+use crate::dbg_interfaces::{AudioRegs, CpuRegs, IORegs, PpuRegs};
+use gb_ppu::{Control, Mode, Stat};
+
+/// Named part of a register, decoded from its value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    pub name: &'static str,
+    pub value: String,
+}
+
+impl Field {
+    fn new(name: &'static str, value: impl ToString) -> Self {
+        Self {
+            name,
+            value: value.to_string(),
+        }
+    }
+
+    fn flag(name: &'static str, value: u8, bit: u8) -> Self {
+        Self::new(name, if value & (1 << bit) != 0 { "on" } else { "off" })
+    }
+
+    /// Field of the bit `bit`, `values` are the values when the bit is reset and set
+    fn select(name: &'static str, value: u8, bit: u8, values: [&str; 2]) -> Self {
+        Self::new(name, values[usize::from(value & (1 << bit) != 0)])
+    }
+}
+
+/// Register made of several fields
+pub trait Bitfields {
+    /// Return the fields of the register holding `value`,
+    /// nothing when the register is a single number
+    fn bitfields(&self, value: u8) -> Vec<Field>;
+}
+
+impl Bitfields for CpuRegs {
+    fn bitfields(&self, _value: u8) -> Vec<Field> {
+        Vec::new()
+    }
+}
+
+impl Bitfields for PpuRegs {
+    fn bitfields(&self, value: u8) -> Vec<Field> {
+        match self {
+            PpuRegs::Control => {
+                let control = Control::from(value);
+                let area = |high: bool, areas: [&'static str; 2]| areas[usize::from(high)];
+                vec![
+                    Field::new("LCD & PPU", on_off(control.ppu_enable())),
+                    Field::new(
+                        "Window map",
+                        area(control.win_tilemap_area(), ["9800-9BFF", "9C00-9FFF"]),
+                    ),
+                    Field::new("Window", on_off(control.win_enable())),
+                    Field::new(
+                        "BG & Window tiles",
+                        area(control.bg_win_tiledata_area(), ["8800-97FF", "8000-8FFF"]),
+                    ),
+                    Field::new(
+                        "BG map",
+                        area(control.bg_tilemap_area(), ["9800-9BFF", "9C00-9FFF"]),
+                    ),
+                    Field::new("OBJ size", area(control.obj_size(), ["8x8", "8x16"])),
+                    Field::new("OBJ", on_off(control.obj_enable())),
+                    Field::new("BG & Window", on_off(control.bg_win_enable())),
+                ]
+            }
+            PpuRegs::Status => {
+                let stat = Stat::from(value);
+                let mode = match stat.mode() {
+                    Ok(Mode::HBlank) => "0 HBlank",
+                    Ok(Mode::VBlank) => "1 VBlank",
+                    Ok(Mode::OAMFetch) => "2 OAM scan",
+                    Ok(Mode::PixelDrawing) => "3 Drawing",
+                    Err(_) => "?",
+                };
+                vec![
+                    Field::new("LYC interrupt", on_off(stat.lyc_eq_ly_interrupt())),
+                    Field::new("Mode 2 interrupt", on_off(stat.mode_2_interrupt())),
+                    Field::new("Mode 1 interrupt", on_off(stat.mode_1_interrupt())),
+                    Field::new("Mode 0 interrupt", on_off(stat.mode_0_interrupt())),
+                    Field::new("LYC = LY", on_off(stat.lyc_eq_ly())),
+                    Field::new("Mode", mode),
+                ]
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl Bitfields for IORegs {
+    fn bitfields(&self, value: u8) -> Vec<Field> {
+        match self {
+            IORegs::Tac => {
+                let clock = match value & 0b11 {
+                    0b00 => "4096 Hz",
+                    0b01 => "262144 Hz",
+                    0b10 => "65536 Hz",
+                    _ => "16384 Hz",
+                };
+                vec![Field::flag("Timer", value, 2), Field::new("Clock", clock)]
+            }
+            IORegs::If | IORegs::Ie => vec![
+                Field::flag("Joypad", value, 4),
+                Field::flag("Serial", value, 3),
+                Field::flag("Timer", value, 2),
+                Field::flag("LCD STAT", value, 1),
+                Field::flag("VBlank", value, 0),
+            ],
+            IORegs::Key1 => vec![
+                Field::select("Speed", value, 7, ["normal", "double"]),
+                Field::select("Switch", value, 0, ["no", "armed"]),
+            ],
+            IORegs::Hdma5 => {
+                // bit 7 is reset while a HBlank transfer is active
+                let active = value & 0x80 == 0;
+                vec![
+                    Field::new("Transfer", if active { "active" } else { "inactive" }),
+                    Field::new(
+                        "Remaining",
+                        format!("{} bytes", (usize::from(value & 0x7f) + 1) * 0x10),
+                    ),
+                ]
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl Bitfields for AudioRegs {
+    fn bitfields(&self, value: u8) -> Vec<Field> {
+        match self {
+            AudioRegs::Env1 | AudioRegs::Env2 | AudioRegs::Vol4 => {
+                let pace = value & 0b111;
+                vec![
+                    Field::new("Initial volume", value >> 4),
+                    Field::select("Envelope", value, 3, ["decrease", "increase"]),
+                    Field::new(
+                        "Envelope pace",
+                        if pace == 0 {
+                            "disabled".to_owned()
+                        } else {
+                            format!("{}/64 s", pace)
+                        },
+                    ),
+                    // the DAC is off when the upper 5 bits are reset
+                    Field::new("DAC", on_off(value & 0xf8 != 0)),
+                ]
+            }
+            AudioRegs::AudMap => vec![
+                Field::flag("VIN left", value, 7),
+                Field::new("Left volume", (value >> 4) & 0b111),
+                Field::flag("VIN right", value, 3),
+                Field::new("Right volume", value & 0b111),
+            ],
+            AudioRegs::AudChanCtl => vec![
+                Field::flag("CH4 left", value, 7),
+                Field::flag("CH3 left", value, 6),
+                Field::flag("CH2 left", value, 5),
+                Field::flag("CH1 left", value, 4),
+                Field::flag("CH4 right", value, 3),
+                Field::flag("CH3 right", value, 2),
+                Field::flag("CH2 right", value, 1),
+                Field::flag("CH1 right", value, 0),
+            ],
+            _ => Vec::new(),
+        }
+    }
+}
+
+fn on_off(flag: bool) -> &'static str {
+    if flag {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+#[cfg(test)]
+mod test_bitfields {
+    use super::{Bitfields, Field};
+    use crate::dbg_interfaces::{AudioRegs, IORegs, PpuRegs};
+
+    fn value<T: Bitfields>(register: T, value: u8, name: &str) -> Option<String> {
+        register
+            .bitfields(value)
+            .into_iter()
+            .find(|field| field.name == name)
+            .map(|field| field.value)
+    }
+
+    #[test]
+    fn lcd() {
+        let fields = PpuRegs::Control.bitfields(0x91);
+        assert_eq!(fields.len(), 8);
+        assert_eq!(fields[0], Field::new("LCD & PPU", "on"));
+        assert_eq!(
+            value(PpuRegs::Control, 0x91, "BG & Window tiles").as_deref(),
+            Some("8000-8FFF")
+        );
+        assert_eq!(value(PpuRegs::Control, 0x91, "OBJ").as_deref(), Some("off"));
+        assert_eq!(
+            value(PpuRegs::Status, 0x85, "Mode").as_deref(),
+            Some("1 VBlank")
+        );
+        assert!(PpuRegs::Ly.bitfields(0x90).is_empty());
+    }
+
+    #[test]
+    fn io_and_audio() {
+        assert_eq!(
+            value(IORegs::Tac, 0x05, "Clock").as_deref(),
+            Some("262144 Hz")
+        );
+        assert_eq!(value(IORegs::Ie, 0x04, "Timer").as_deref(), Some("on"));
+        assert_eq!(
+            value(IORegs::Hdma5, 0x01, "Remaining").as_deref(),
+            Some("32 bytes")
+        );
+        assert_eq!(
+            value(AudioRegs::Env1, 0xf3, "Initial volume").as_deref(),
+            Some("15")
+        );
+        assert_eq!(
+            value(AudioRegs::Vol4, 0x08, "Envelope").as_deref(),
+            Some("increase")
+        );
+        assert_eq!(
+            value(AudioRegs::AudChanCtl, 0x21, "CH2 left").as_deref(),
+            Some("on")
+        );
+    }
+}
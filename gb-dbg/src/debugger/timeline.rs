@@ -0,0 +1,227 @@
+use crate::dbg_interfaces::DebugOperations;
+use crate::timeline::{Event, Frame, CYCLES_PER_LINE, LINES_PER_FRAME};
+use egui::{pos2, vec2, Color32, Context, Rect, Sense, Ui};
+
+const CYCLE_WIDTH: f32 = 4.0;
+const LINE_HEIGHT: f32 = 3.0;
+/// Distance in cycles under which the events are listed when hovering the timeline
+const HOVER_DISTANCE: usize = 2;
+
+/// Kinds of event drawn on the timeline
+#[derive(Debug)]
+struct Filter {
+    interrupts: bool,
+    ppu: bool,
+    timer: bool,
+    dma: bool,
+    speed: bool,
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Self {
+            interrupts: true,
+            ppu: true,
+            timer: true,
+            dma: true,
+            speed: true,
+        }
+    }
+}
+
+impl Filter {
+    fn shows(&self, event: &Event) -> bool {
+        match event {
+            Event::Request(_) | Event::Dispatch(_) => self.interrupts,
+            Event::Mode(_) | Event::LycMatch => self.ppu,
+            Event::TimerOverflow => self.timer,
+            Event::OamDma { .. } | Event::Hdma { .. } => self.dma,
+            Event::SpeedSwitch { .. } => self.speed,
+        }
+    }
+}
+
+/// Window showing the events of a frame on a grid of scanlines
+#[derive(Default, Debug)]
+pub struct TimelineViewer {
+    pub open: bool,
+    show_current: bool,
+    filter: Filter,
+}
+
+impl TimelineViewer {
+    pub fn draw<DBG: DebugOperations>(&mut self, ctx: &Context, dbg: &mut DBG) {
+        let mut open = self.open;
+        egui::Window::new("Timeline")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| self.draw_timeline(ui, dbg));
+        self.open = open;
+    }
+
+    fn draw_timeline<DBG: DebugOperations>(&mut self, ui: &mut Ui, dbg: &mut DBG) {
+        let timeline = dbg.timeline();
+        ui.horizontal(|ui| {
+            let mut enabled = timeline.is_enabled();
+            if ui.checkbox(&mut enabled, "Record").changed() {
+                if enabled {
+                    timeline.start();
+                } else {
+                    timeline.stop();
+                }
+            }
+            ui.checkbox(&mut self.show_current, "Current frame")
+                .on_hover_text("show the frame being recorded instead of the last complete one");
+        });
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.filter.interrupts, "Interrupts");
+            ui.checkbox(&mut self.filter.ppu, "STAT");
+            ui.checkbox(&mut self.filter.timer, "Timer");
+            ui.checkbox(&mut self.filter.dma, "DMA");
+            ui.checkbox(&mut self.filter.speed, "Speed");
+        });
+        ui.separator();
+
+        let frame = if self.show_current {
+            Some(timeline.current_frame())
+        } else {
+            timeline.last_frame()
+        };
+        match frame {
+            Some(frame) => {
+                ui.label(format!(
+                    "Frame {}, {} cycles{}, {} events",
+                    frame.number,
+                    frame.cycles,
+                    if frame.double_speed {
+                        " in double speed"
+                    } else {
+                        ""
+                    },
+                    frame.events.len()
+                ));
+                self.draw_lines(ui, frame);
+                ui.separator();
+                self.draw_list(ui, frame);
+            }
+            None => {
+                ui.label("No frame recorded");
+            }
+        }
+    }
+
+    fn draw_lines(&self, ui: &mut Ui, frame: &Frame) {
+        let size = vec2(
+            CYCLES_PER_LINE as f32 * CYCLE_WIDTH,
+            LINES_PER_FRAME as f32 * LINE_HEIGHT,
+        );
+        // the lines keep their width in double speed, with twice as many cycles
+        let per_line = frame.cycles_per_line();
+        let cycle_width = size.x / per_line as f32;
+        let (response, painter) = ui.allocate_painter(size, Sense::hover());
+        let origin = response.rect.min;
+        let cell = |cycle: usize, len: usize| {
+            let (line, x) = (cycle / per_line, cycle % per_line);
+            Rect::from_min_size(
+                origin + vec2(x as f32 * cycle_width, line as f32 * LINE_HEIGHT),
+                vec2(len as f32 * cycle_width, LINE_HEIGHT),
+            )
+        };
+        painter.rect_filled(response.rect, 0.0, Color32::from_gray(10));
+
+        // the background of the lines shows the mode of the ppu
+        let mut mode = frame.start_mode;
+        let mut start = 0;
+        let changes = frame.events.iter().filter_map(|e| match e.event {
+            Event::Mode(mode) => Some((e.cycle, mode)),
+            _ => None,
+        });
+        for (end, next_mode) in changes.chain(std::iter::once((frame.cycles, mode))) {
+            let end = end.min(frame.cycles_per_frame());
+            let mut cycle = start;
+            while cycle < end {
+                let len = (per_line - cycle % per_line).min(end - cycle);
+                painter.rect_filled(cell(cycle, len), 0.0, mode_color(mode));
+                cycle += len;
+            }
+            start = end;
+            mode = next_mode;
+        }
+
+        for event in frame
+            .events
+            .iter()
+            .filter(|e| !matches!(e.event, Event::Mode(_)) && self.filter.shows(&e.event))
+        {
+            painter.rect_filled(cell(event.cycle, 1), 0.0, event_color(&event.event));
+        }
+
+        if let Some(pointer) = response.hover_pos() {
+            let offset = pointer - origin;
+            let line = (offset.y / LINE_HEIGHT) as usize;
+            let x = (offset.x / cycle_width) as usize;
+            let cycle = line * per_line + x;
+            painter.rect_filled(
+                Rect::from_min_size(
+                    pos2(origin.x, origin.y + line as f32 * LINE_HEIGHT),
+                    vec2(size.x, LINE_HEIGHT),
+                ),
+                0.0,
+                Color32::from_white_alpha(40),
+            );
+            response.on_hover_ui_at_pointer(|ui| {
+                ui.label(format!("line {}, cycle {}", line, x));
+                for event in frame.events.iter().filter(|e| {
+                    e.cycle / per_line == line
+                        && e.cycle.abs_diff(cycle) <= HOVER_DISTANCE
+                        && self.filter.shows(&e.event)
+                }) {
+                    ui.colored_label(event_color(&event.event), event.event.to_string());
+                }
+            });
+        }
+    }
+
+    fn draw_list(&self, ui: &mut Ui, frame: &Frame) {
+        egui::ScrollArea::vertical()
+            .id_source("timeline_events")
+            .max_height(200.0)
+            .show(ui, |ui| {
+                egui::Grid::new("timeline_events_grid")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.colored_label(Color32::GOLD, "Cycle");
+                        ui.colored_label(Color32::GOLD, "LY");
+                        ui.colored_label(Color32::GOLD, "Event");
+                        ui.end_row();
+                        for event in frame.events.iter().filter(|e| self.filter.shows(&e.event)) {
+                            ui.label(event.cycle.to_string());
+                            ui.label(event.line.to_string());
+                            ui.colored_label(event_color(&event.event), event.event.to_string());
+                            ui.end_row();
+                        }
+                    });
+            });
+    }
+}
+
+fn mode_color(mode: u8) -> Color32 {
+    match mode {
+        0 => Color32::from_gray(35),
+        1 => Color32::from_rgb(20, 25, 60),
+        2 => Color32::from_rgb(20, 60, 30),
+        _ => Color32::from_rgb(70, 45, 15),
+    }
+}
+
+fn event_color(event: &Event) -> Color32 {
+    match event {
+        Event::Request(_) => Color32::YELLOW,
+        Event::Dispatch(_) => Color32::RED,
+        Event::Mode(_) => Color32::LIGHT_GRAY,
+        Event::LycMatch => Color32::LIGHT_BLUE,
+        Event::TimerOverflow => Color32::GREEN,
+        Event::OamDma { .. } | Event::Hdma { .. } => Color32::from_rgb(230, 80, 230),
+        Event::SpeedSwitch { .. } => Color32::WHITE,
+    }
+}
@@ -0,0 +1,148 @@
+use crate::dbg_interfaces::DebugOperations;
+use crate::debugger::watchpoints::parse_range;
+use crate::trace::{TraceFilter, TraceFormat};
+use egui::{Color32, Ui};
+
+const DEFAULT_RING_SIZE: usize = 10_000;
+
+/// Configure the execution trace
+#[derive(Debug)]
+pub struct TraceEditor {
+    path_field: String,
+    pc_range_field: String,
+    bank_field: String,
+    ring_size: usize,
+    error: Option<String>,
+}
+
+impl Default for TraceEditor {
+    fn default() -> Self {
+        Self {
+            path_field: String::new(),
+            pc_range_field: String::with_capacity(9),
+            bank_field: String::with_capacity(2),
+            ring_size: DEFAULT_RING_SIZE,
+            error: None,
+        }
+    }
+}
+
+impl TraceEditor {
+    pub fn draw<DBG: DebugOperations>(&mut self, ui: &mut Ui, dbg: &mut DBG) {
+        let tracer = dbg.tracer();
+        if self.path_field.is_empty() {
+            self.path_field = tracer.path().display().to_string();
+        }
+
+        egui::CollapsingHeader::new("Trace").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                let mut enabled = tracer.is_enabled();
+                if ui.checkbox(&mut enabled, "Enabled").changed() {
+                    if enabled {
+                        tracer.start();
+                    } else {
+                        tracer.stop();
+                    }
+                }
+                egui::ComboBox::from_id_source("trace_format")
+                    .selected_text(tracer.format.to_string())
+                    .show_ui(ui, |ui| {
+                        for format in TraceFormat::ALL {
+                            ui.selectable_value(&mut tracer.format, format, format.to_string());
+                        }
+                    });
+            });
+            ui.horizontal(|ui| {
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut self.path_field)
+                        .desired_width(240.0)
+                        .hint_text("trace.log"),
+                );
+                if response.lost_focus() && tracer.path().to_str() != Some(&self.path_field) {
+                    tracer.set_path(self.path_field.trim());
+                }
+            });
+
+            ui.horizontal(|ui| {
+                let mut use_ring = tracer.ring_size().is_some();
+                ui.checkbox(&mut use_ring, "Keep the last");
+                ui.add(
+                    egui::DragValue::new(&mut self.ring_size)
+                        .clamp_range(1..=1_000_000)
+                        .speed(100),
+                );
+                ui.label("instructions");
+                tracer.set_ring_size(if use_ring { Some(self.ring_size) } else { None });
+            });
+
+            egui::Grid::new("trace_filter").show(ui, |ui| {
+                ui.label("PC");
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.pc_range_field)
+                        .desired_width(85.0)
+                        .hint_text("4000-7FFF"),
+                );
+                ui.end_row();
+                ui.label("Bank");
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.bank_field)
+                        .desired_width(30.0)
+                        .hint_text("01"),
+                );
+                ui.end_row();
+            });
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut tracer.filter.armed_only, "Only when armed")
+                    .on_hover_text("wait for a breakpoint with the `Arm trace` action");
+                if tracer.is_armed() {
+                    ui.colored_label(Color32::GREEN, "armed");
+                    if ui.button("Disarm").clicked() {
+                        tracer.disarm();
+                    }
+                }
+            });
+
+            ui.horizontal(|ui| {
+                if ui.button("Apply filter").clicked() {
+                    match self.parse_filter(tracer.filter.armed_only) {
+                        Ok(filter) => {
+                            tracer.filter = filter;
+                            self.error = None;
+                        }
+                        Err(e) => self.error = Some(e),
+                    }
+                }
+                if ui.button("Dump").clicked() {
+                    if let Err(e) = tracer.dump() {
+                        self.error = Some(format!("cannot write trace: {}", e));
+                    }
+                }
+                ui.label(format!("{} traced", tracer.count()));
+            });
+            if let Some(error) = &self.error {
+                ui.colored_label(Color32::RED, error);
+            }
+        });
+    }
+
+    fn parse_filter(&self, armed_only: bool) -> Result<TraceFilter, String> {
+        let pc_range = self.pc_range_field.trim();
+        let pc_range = if pc_range.is_empty() {
+            None
+        } else {
+            Some(parse_range(pc_range).ok_or_else(|| format!("invalid range `{}`", pc_range))?)
+        };
+        let bank = self.bank_field.trim();
+        let bank = if bank.is_empty() {
+            None
+        } else {
+            Some(usize::from_str_radix(bank, 16).map_err(|e| format!("invalid bank: {}", e))?)
+        };
+
+        Ok(TraceFilter {
+            pc_range,
+            bank,
+            armed_only,
+        })
+    }
+}
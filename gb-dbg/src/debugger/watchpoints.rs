@@ -0,0 +1,226 @@
+use crate::dbg_interfaces::DebugOperations;
+use gb_bus::watchpoint::{AccessMask, Owner, Trigger};
+use gb_bus::{Watchpoint, WatchpointHit};
+
+use egui::{Color32, RichText, Ui, Vec2};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TriggerKind {
+    Any,
+    Changed,
+    Equal,
+    NotEqual,
+    Greater,
+    Lower,
+}
+
+impl TriggerKind {
+    const ALL: [TriggerKind; 6] = [
+        TriggerKind::Any,
+        TriggerKind::Changed,
+        TriggerKind::Equal,
+        TriggerKind::NotEqual,
+        TriggerKind::Greater,
+        TriggerKind::Lower,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            TriggerKind::Any => "any",
+            TriggerKind::Changed => "changed",
+            TriggerKind::Equal => "==",
+            TriggerKind::NotEqual => "!=",
+            TriggerKind::Greater => ">",
+            TriggerKind::Lower => "<",
+        }
+    }
+
+    fn need_value(&self) -> bool {
+        !matches!(self, TriggerKind::Any | TriggerKind::Changed)
+    }
+
+    fn to_trigger(self, value: u8) -> Trigger {
+        match self {
+            TriggerKind::Any => Trigger::Any,
+            TriggerKind::Changed => Trigger::Changed,
+            TriggerKind::Equal => Trigger::Equal(value),
+            TriggerKind::NotEqual => Trigger::NotEqual(value),
+            TriggerKind::Greater => Trigger::Greater(value),
+            TriggerKind::Lower => Trigger::Lower(value),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct WatchpointEditor {
+    watchpoints: Vec<Watchpoint>,
+    range_field: String,
+    access: AccessMask,
+    trigger: TriggerKind,
+    value_field: String,
+    last_hit: Option<WatchpointHit>,
+}
+
+impl Default for WatchpointEditor {
+    fn default() -> Self {
+        Self {
+            watchpoints: Vec::with_capacity(20),
+            range_field: String::with_capacity(9),
+            access: AccessMask::WRITE,
+            trigger: TriggerKind::Any,
+            value_field: String::with_capacity(2),
+            last_hit: None,
+        }
+    }
+}
+
+impl WatchpointEditor {
+    pub fn draw<DBG: DebugOperations>(&mut self, ui: &mut Ui, dbg: &mut DBG) {
+        ui.vertical(|ui| {
+            ui.colored_label(Color32::LIGHT_BLUE, "Watchpoints");
+            ui.separator();
+            self.draw_watchpoint_widget(ui);
+
+            let mut deletion_list: Vec<usize> = Vec::with_capacity(20);
+            egui::Grid::new("watchpoints_".to_owned())
+                .striped(true)
+                .spacing(Vec2::new(47.0, 7.0))
+                .show(ui, |ui| {
+                    ui.label("Delete");
+                    ui.label("Enabled");
+                    ui.label("Watch");
+                    ui.end_row();
+
+                    for (i, watchpoint) in self.watchpoints.iter_mut().enumerate() {
+                        if ui.button(RichText::new("-").color(Color32::RED)).clicked() {
+                            deletion_list.push(i)
+                        }
+                        ui.checkbox(&mut watchpoint.enabled, "");
+                        if self.last_hit.as_ref().map(|hit| hit.index) == Some(i) {
+                            ui.label(RichText::new(watchpoint.to_string()).color(Color32::RED));
+                        } else {
+                            ui.label(watchpoint.to_string());
+                        }
+                        ui.end_row();
+                    }
+                });
+            if !deletion_list.is_empty() {
+                self.last_hit = None;
+            }
+            deletion_list.into_iter().rev().for_each(|i| {
+                self.watchpoints.remove(i);
+            });
+            if let Some(hit) = &self.last_hit {
+                ui.label(RichText::new(hit.to_string()).color(Color32::from_gray(160)));
+            }
+        });
+
+        if dbg.watchpoints(Owner::Debugger) != self.watchpoints.as_slice() {
+            dbg.set_watchpoints(Owner::Debugger, self.watchpoints.clone());
+        }
+    }
+
+    pub fn add_watchpoint(&mut self, watchpoint: Watchpoint) {
+        self.watchpoints.push(watchpoint);
+    }
+
+    /// Check if a watchpoint was hit since the last call, the hits are logged
+    pub fn are_watchpoints_triggered<DBG: DebugOperations>(&mut self, dbg: &mut DBG) -> bool {
+        let hits = dbg.take_watchpoint_hits(Owner::Debugger);
+        for hit in hits.iter() {
+            log::info!("watchpoint triggered: {}", hit);
+        }
+        match hits.into_iter().next() {
+            Some(hit) => {
+                self.last_hit = Some(hit);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn draw_watchpoint_widget(&mut self, ui: &mut Ui) {
+        self.range_field
+            .retain(|c| c.is_ascii_hexdigit() || c == '-');
+        self.value_field.retain(|c| c.is_ascii_hexdigit());
+        self.value_field.truncate(2);
+        let watchpoint = self.parse_fields();
+
+        ui.horizontal(|ui| {
+            let add_button_response = ui.add_enabled(watchpoint.is_some(), egui::Button::new("+"));
+            ui.label(RichText::new("0x").color(Color32::from_gray(90)).weak());
+            let text_field_response = ui.add(
+                egui::TextEdit::singleline(&mut self.range_field)
+                    .desired_width(85.0)
+                    .hint_text("C000-C0FF"),
+            );
+            if let Some(watchpoint) = watchpoint {
+                if add_button_response.clicked()
+                    || (text_field_response.lost_focus()
+                        && ui.input().key_pressed(egui::Key::Enter))
+                {
+                    self.watchpoints.push(watchpoint);
+                    self.range_field.clear();
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.access.read, "R");
+            ui.checkbox(&mut self.access.write, "W");
+            ui.checkbox(&mut self.access.execute, "X");
+            egui::ComboBox::from_id_source("watchpoint_trigger")
+                .selected_text(self.trigger.label())
+                .width(70.0)
+                .show_ui(ui, |ui| {
+                    for kind in TriggerKind::ALL {
+                        ui.selectable_value(&mut self.trigger, kind, kind.label());
+                    }
+                });
+            if self.trigger.need_value() {
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.value_field)
+                        .desired_width(30.0)
+                        .hint_text("FF"),
+                );
+            }
+        });
+    }
+
+    fn parse_fields(&self) -> Option<Watchpoint> {
+        let range = parse_range(&self.range_field)?;
+        let value = if self.trigger.need_value() {
+            u8::from_str_radix(&self.value_field, 16).ok()?
+        } else {
+            0
+        };
+        if self.access == AccessMask::default() {
+            return None;
+        }
+        Some(Watchpoint::new(
+            range,
+            self.access,
+            self.trigger.to_trigger(value),
+        ))
+    }
+}
+
+/// Parse an address range in the form `C000` or `C000-C0FF`
+pub(crate) fn parse_range(range: &str) -> Option<std::ops::RangeInclusive<u16>> {
+    let (start, end) = range.split_once('-').unwrap_or((range, range));
+    let start = u16::from_str_radix(start, 16).ok()?;
+    let end = u16::from_str_radix(end, 16).ok()?;
+    if start <= end {
+        Some(start..=end)
+    } else {
+        None
+    }
+}
+
+#[test]
+fn test_parse_range() {
+    assert_eq!(parse_range("c000"), Some(0xc000..=0xc000));
+    assert_eq!(parse_range("C000-C0FF"), Some(0xc000..=0xc0ff));
+    assert_eq!(parse_range("C0FF-C000"), None);
+    assert_eq!(parse_range("C000-"), None);
+    assert_eq!(parse_range(""), None);
+}
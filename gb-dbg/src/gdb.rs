@@ -0,0 +1,488 @@
+mod command;
+mod packet;
+
+use crate::dbg_interfaces::{CpuRegs, DebugOperations};
+use crate::debugger::breakpoints::breakpoint::Breakpoint;
+use crate::until::Until;
+use command::{encode_hex, BreakpointKind, Command};
+use gb_breakpoint::Node;
+use gb_bus::watchpoint::{Access, AccessMask, Owner, Trigger};
+use gb_bus::Watchpoint;
+use packet::{encode, Incoming, PacketReader};
+use std::{
+    io::{self, ErrorKind, Read, Write},
+    net::{Ipv4Addr, TcpListener, TcpStream},
+    ops::ControlFlow,
+};
+
+/// Order of the registers in the `g` and `p` packets
+const REGISTERS: [CpuRegs; 6] = [
+    CpuRegs::AF,
+    CpuRegs::BC,
+    CpuRegs::DE,
+    CpuRegs::HL,
+    CpuRegs::SP,
+    CpuRegs::PC,
+];
+
+/// Maximum size of a memory read, to keep the replies under the packet size
+const MAX_READ: usize = 0x400;
+
+const TARGET_XML: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE target SYSTEM "gdb-target.dtd">
+<target version="1.0">
+  <feature name="org.gbmu.sm83.core">
+    <reg name="af" bitsize="16" type="int" regnum="0"/>
+    <reg name="bc" bitsize="16" type="int"/>
+    <reg name="de" bitsize="16" type="int"/>
+    <reg name="hl" bitsize="16" type="int"/>
+    <reg name="sp" bitsize="16" type="data_ptr"/>
+    <reg name="pc" bitsize="16" type="code_ptr"/>
+  </feature>
+</target>
+"#;
+
+/// Why the target stopped, sent to GDB in the stop reply
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StopReason {
+    /// Stopped after a step or by the debugger window
+    Trap,
+    Interrupted,
+    /// Hit a breakpoint inserted by a `Z0` or a `Z1` packet
+    Breakpoint(BreakpointKind),
+    Watchpoint(Access, bool, u16),
+}
+
+impl StopReason {
+    fn reply(&self) -> String {
+        match self {
+            StopReason::Trap => "S05".to_owned(),
+            StopReason::Interrupted => "S02".to_owned(),
+            StopReason::Breakpoint(BreakpointKind::Hardware) => "T05hwbreak:;".to_owned(),
+            StopReason::Breakpoint(_) => "T05swbreak:;".to_owned(),
+            StopReason::Watchpoint(access, both, address) => {
+                let kind = match (access, both) {
+                    (_, true) => "awatch",
+                    (Access::Write, false) => "watch",
+                    _ => "rwatch",
+                };
+                format!("T05{}:{:x};", kind, address)
+            }
+        }
+    }
+}
+
+struct Client {
+    stream: TcpStream,
+    reader: PacketReader,
+    no_ack: bool,
+    /// Last packet sent, sent again when GDB nack it
+    last_packet: Vec<u8>,
+}
+
+/// Stub of the GDB remote serial protocol listening on a local TCP port
+///
+/// The software and hardware breakpoints are evaluated like the breakpoints of the debugger,
+/// the watchpoints are installed on the address bus.
+/// The registers are exposed as `af`, `bc`, `de`, `hl`, `sp` and `pc`.
+pub struct GdbServer {
+    listener: TcpListener,
+    client: Option<Client>,
+    /// Breakpoints with the type of the `Z` packet that inserted them
+    breakpoints: Vec<(BreakpointKind, Breakpoint)>,
+    watchpoints: Vec<Watchpoint>,
+    /// GDB resumed the target and wait for a stop reply
+    running: bool,
+    last_stop: StopReason,
+}
+
+impl GdbServer {
+    pub fn bind(port: u16) -> io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+        listener.set_nonblocking(true)?;
+        log::info!("gdb server listening on {}", listener.local_addr()?);
+
+        Ok(Self {
+            listener,
+            client: None,
+            breakpoints: Vec::new(),
+            watchpoints: Vec::new(),
+            running: false,
+            last_stop: StopReason::Trap,
+        })
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.client.is_some()
+    }
+
+    /// Accept a new connection and handle the packets received since the last call.
+    /// Return the change of execution requested by GDB.
+    pub fn poll<DBG: DebugOperations>(&mut self, dbg: &mut DBG) -> Option<ControlFlow<Until>> {
+        let mut flow = None;
+
+        if self.client.is_none() {
+            match self.accept() {
+                Ok(true) => {
+                    // GDB expect the target to be stopped once attached
+                    self.running = false;
+                    self.last_stop = StopReason::Trap;
+                    flow = Some(ControlFlow::Break(Until::Null));
+                }
+                Ok(false) => return None,
+                Err(e) => {
+                    log::error!("cannot accept gdb connection: {}", e);
+                    return None;
+                }
+            }
+        }
+
+        if let Err(e) = self.receive() {
+            log::warn!("gdb connection lost: {}", e);
+            return self.detach(dbg);
+        }
+        while let Some(incoming) = self.client.as_mut().and_then(|client| client.reader.pop()) {
+            let result = match incoming {
+                Incoming::Packet(payload) => self.handle_packet(dbg, &payload),
+                Incoming::Corrupted => self.send_raw(b"-").map(|_| None),
+                Incoming::Interrupt => {
+                    self.running = false;
+                    self.stop(StopReason::Interrupted)
+                        .map(|_| Some(ControlFlow::Break(Until::Null)))
+                }
+                Incoming::Nack => self.resend().map(|_| None),
+                Incoming::Ack => Ok(None),
+            };
+            match result {
+                Ok(Some(requested)) => flow = Some(requested),
+                Ok(None) => {}
+                Err(e) => {
+                    log::warn!("gdb connection lost: {}", e);
+                    return self.detach(dbg);
+                }
+            }
+        }
+        flow
+    }
+
+    /// Check the breakpoints and the watchpoints while the target run,
+    /// return the change of execution once one of them is hit
+    pub fn updated_flow_status<DBG: DebugOperations>(
+        &mut self,
+        dbg: &mut DBG,
+    ) -> Option<ControlFlow<Until>> {
+        if !self.running {
+            return None;
+        }
+
+        let mut hit = None;
+        for (kind, breakpoint) in &mut self.breakpoints {
+            if breakpoint.fire(dbg) {
+                hit = hit.or(Some(*kind));
+            }
+        }
+        let reason = if let Some(kind) = hit {
+            Some(StopReason::Breakpoint(kind))
+        } else if let Some(watch) = dbg.take_watchpoint_hits(Owner::Gdb).into_iter().next() {
+            let both = self
+                .watchpoints
+                .get(watch.index)
+                .map_or(false, |watchpoint| {
+                    watchpoint.access.read && watchpoint.access.write
+                });
+            Some(StopReason::Watchpoint(watch.access, both, watch.address))
+        } else if dbg.is_stopped() {
+            Some(StopReason::Trap)
+        } else {
+            None
+        }?;
+
+        self.running = false;
+        if let Err(e) = self.stop(reason) {
+            log::warn!("gdb connection lost: {}", e);
+            self.detach(dbg);
+        }
+        Some(ControlFlow::Break(Until::Null))
+    }
+
+    fn accept(&mut self) -> io::Result<bool> {
+        let (stream, address) = match self.listener.accept() {
+            Ok(connection) => connection,
+            Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(false),
+            Err(e) => return Err(e),
+        };
+        stream.set_nonblocking(true)?;
+        stream.set_nodelay(true)?;
+        log::info!("gdb connected from {}", address);
+        self.client = Some(Client {
+            stream,
+            reader: PacketReader::default(),
+            no_ack: false,
+            last_packet: Vec::new(),
+        });
+        Ok(true)
+    }
+
+    fn receive(&mut self) -> io::Result<()> {
+        let client = match self.client.as_mut() {
+            Some(client) => client,
+            None => return Ok(()),
+        };
+        let mut buffer = [0; 1024];
+        loop {
+            match client.stream.read(&mut buffer) {
+                Ok(0) => return Err(ErrorKind::UnexpectedEof.into()),
+                Ok(len) => client.reader.push(&buffer[..len]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(()),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    fn handle_packet<DBG: DebugOperations>(
+        &mut self,
+        dbg: &mut DBG,
+        payload: &[u8],
+    ) -> io::Result<Option<ControlFlow<Until>>> {
+        if !self.client.as_ref().map_or(false, |client| client.no_ack) {
+            self.send_raw(b"+")?;
+        }
+        let command = match Command::parse(payload) {
+            Some(command) => command,
+            None => {
+                log::warn!("malformed gdb packet: {}", String::from_utf8_lossy(payload));
+                self.send("E01")?;
+                return Ok(None);
+            }
+        };
+        log::trace!("gdb command: {:?}", command);
+
+        let mut flow = None;
+        let reply = match command {
+            Command::StopReason => self.last_stop.reply(),
+            Command::ReadRegisters => REGISTERS
+                .iter()
+                .map(|&reg| encode_hex(&u16::from(dbg.cpu_get(reg)).to_le_bytes()))
+                .collect(),
+            Command::WriteRegisters(values) => {
+                for (&reg, value) in REGISTERS.iter().zip(values) {
+                    dbg.cpu_set(reg, value);
+                }
+                "OK".to_owned()
+            }
+            Command::ReadRegister(index) => match REGISTERS.get(index) {
+                Some(&reg) => encode_hex(&u16::from(dbg.cpu_get(reg)).to_le_bytes()),
+                None => "E01".to_owned(),
+            },
+            Command::WriteRegister(index, value) => match REGISTERS.get(index) {
+                Some(&reg) => {
+                    dbg.cpu_set(reg, value);
+                    "OK".to_owned()
+                }
+                None => "E01".to_owned(),
+            },
+            Command::ReadMemory { address, len } => {
+                let data: Vec<u8> = (0..len.min(MAX_READ) as u16)
+                    .map(|offset| dbg.read(address.wrapping_add(offset)))
+                    .collect();
+                encode_hex(&data)
+            }
+            Command::WriteMemory { address, data } => {
+                for (offset, value) in data.into_iter().enumerate() {
+                    dbg.write(address.wrapping_add(offset as u16), value);
+                }
+                "OK".to_owned()
+            }
+            Command::Continue(address) | Command::Step(address) => {
+                if let Some(address) = address {
+                    dbg.cpu_set(CpuRegs::PC, address);
+                }
+                self.running = true;
+                self.last_stop = StopReason::Trap;
+                flow = Some(if matches!(command, Command::Step(_)) {
+                    ControlFlow::Break(Until::Step(1))
+                } else {
+                    ControlFlow::Continue(())
+                });
+                // the reply is sent once the target stop
+                return Ok(flow);
+            }
+            Command::InsertBreakpoint(kind, address, len) => {
+                self.insert_breakpoint(dbg, kind, address, len);
+                "OK".to_owned()
+            }
+            Command::RemoveBreakpoint(kind, address, len) => {
+                self.remove_breakpoint(dbg, kind, address, len);
+                "OK".to_owned()
+            }
+            Command::Supported => {
+                "PacketSize=1000;qXfer:features:read+;swbreak+;hwbreak+;QStartNoAckMode+".to_owned()
+            }
+            Command::TargetDescription { offset, len } => {
+                let chunk = TARGET_XML.get(offset..).unwrap_or_default();
+                if chunk.len() > len {
+                    format!("m{}", &chunk[..len])
+                } else {
+                    format!("l{}", chunk)
+                }
+            }
+            Command::ContinueActions => "vCont;c;C;s;S".to_owned(),
+            Command::StartNoAckMode => {
+                self.send("OK")?;
+                if let Some(client) = self.client.as_mut() {
+                    client.no_ack = true;
+                }
+                return Ok(None);
+            }
+            Command::Attached => "1".to_owned(),
+            Command::FirstThreadInfo => "m1".to_owned(),
+            Command::NextThreadInfo => "l".to_owned(),
+            Command::Thread => "OK".to_owned(),
+            Command::Detach => {
+                self.send("OK")?;
+                return Ok(self.detach(dbg));
+            }
+            Command::Kill => return Ok(self.detach(dbg)),
+            Command::Unsupported => String::new(),
+        };
+        self.send(&reply)?;
+        Ok(flow)
+    }
+
+    fn insert_breakpoint<DBG: DebugOperations>(
+        &mut self,
+        dbg: &mut DBG,
+        kind: BreakpointKind,
+        address: u16,
+        len: u16,
+    ) {
+        match watch_mask(kind) {
+            None => {
+                if !self.has_breakpoint(kind, address) {
+                    self.breakpoints
+                        .push((kind, Breakpoint::from_address(address)));
+                }
+            }
+            Some(access) => {
+                self.watchpoints.push(
+                    Watchpoint::new(watch_range(address, len), access, Trigger::Any)
+                        .owned_by(Owner::Gdb),
+                );
+                dbg.set_watchpoints(Owner::Gdb, self.watchpoints.clone());
+            }
+        }
+    }
+
+    fn remove_breakpoint<DBG: DebugOperations>(
+        &mut self,
+        dbg: &mut DBG,
+        kind: BreakpointKind,
+        address: u16,
+        len: u16,
+    ) {
+        match watch_mask(kind) {
+            None => {
+                let expr = Node::simple(address);
+                self.breakpoints
+                    .retain(|(k, b)| *k != kind || b.expr() != &expr);
+            }
+            Some(access) => {
+                let range = watch_range(address, len);
+                self.watchpoints
+                    .retain(|w| w.range != range || w.access != access);
+                dbg.set_watchpoints(Owner::Gdb, self.watchpoints.clone());
+            }
+        }
+    }
+
+    fn has_breakpoint(&self, kind: BreakpointKind, address: u16) -> bool {
+        let expr = Node::simple(address);
+        self.breakpoints
+            .iter()
+            .any(|(k, b)| *k == kind && b.expr() == &expr)
+    }
+
+    /// Close the connection and let the target run freely
+    fn detach<DBG: DebugOperations>(&mut self, dbg: &mut DBG) -> Option<ControlFlow<Until>> {
+        log::info!("gdb detached");
+        self.client = None;
+        self.running = false;
+        self.breakpoints.clear();
+        if !self.watchpoints.is_empty() {
+            self.watchpoints.clear();
+            dbg.set_watchpoints(Owner::Gdb, Vec::new());
+        }
+        Some(ControlFlow::Continue(()))
+    }
+
+    fn stop(&mut self, reason: StopReason) -> io::Result<()> {
+        self.last_stop = reason;
+        self.send(&reason.reply())
+    }
+
+    fn send(&mut self, payload: &str) -> io::Result<()> {
+        let packet = encode(payload.as_bytes());
+        self.send_raw(&packet)?;
+        if let Some(client) = self.client.as_mut() {
+            client.last_packet = packet;
+        }
+        Ok(())
+    }
+
+    fn resend(&mut self) -> io::Result<()> {
+        let packet = self
+            .client
+            .as_ref()
+            .map(|client| client.last_packet.clone())
+            .unwrap_or_default();
+        self.send_raw(&packet)
+    }
+
+    fn send_raw(&mut self, data: &[u8]) -> io::Result<()> {
+        match self.client.as_mut() {
+            Some(client) => {
+                // the replies are small, wait for them to be fully written
+                client.stream.set_nonblocking(false)?;
+                client.stream.write_all(data)?;
+                client.stream.set_nonblocking(true)
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+/// Return the accesses watched by a `Z` packet, `None` for the breakpoints
+fn watch_mask(kind: BreakpointKind) -> Option<AccessMask> {
+    let (read, write) = match kind {
+        BreakpointKind::Software | BreakpointKind::Hardware => return None,
+        BreakpointKind::Write => (false, true),
+        BreakpointKind::Read => (true, false),
+        BreakpointKind::Access => (true, true),
+    };
+    Some(AccessMask {
+        read,
+        write,
+        execute: false,
+    })
+}
+
+fn watch_range(address: u16, len: u16) -> std::ops::RangeInclusive<u16> {
+    address..=address.saturating_add(len.max(1) - 1)
+}
+
+#[cfg(test)]
+mod test_gdb {
+    use super::{BreakpointKind, StopReason};
+
+    #[test]
+    fn breakpoint_reply() {
+        assert_eq!(
+            StopReason::Breakpoint(BreakpointKind::Software).reply(),
+            "T05swbreak:;"
+        );
+        assert_eq!(
+            StopReason::Breakpoint(BreakpointKind::Hardware).reply(),
+            "T05hwbreak:;"
+        );
+    }
+}
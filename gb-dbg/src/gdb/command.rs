@@ -0,0 +1,274 @@
+/// Type of breakpoint of the `Z` and `z` packets
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakpointKind {
+    Software,
+    Hardware,
+    Write,
+    Read,
+    Access,
+}
+
+/// Request of GDB handled by the server
+#[derive(Debug, PartialEq, Eq)]
+pub enum Command {
+    /// `?`: report why the target stopped
+    StopReason,
+    /// `g`
+    ReadRegisters,
+    /// `G`: the registers in the order of `g`
+    WriteRegisters(Vec<u16>),
+    /// `p`
+    ReadRegister(usize),
+    /// `P`
+    WriteRegister(usize, u16),
+    /// `m`
+    ReadMemory { address: u16, len: usize },
+    /// `M` or `X`
+    WriteMemory { address: u16, data: Vec<u8> },
+    /// `c` or `vCont;c`, optionally resuming at another address
+    Continue(Option<u16>),
+    /// `s` or `vCont;s`, optionally resuming at another address
+    Step(Option<u16>),
+    /// `Z`
+    InsertBreakpoint(BreakpointKind, u16, u16),
+    /// `z`
+    RemoveBreakpoint(BreakpointKind, u16, u16),
+    /// `qSupported`
+    Supported,
+    /// `qXfer:features:read:target.xml`
+    TargetDescription { offset: usize, len: usize },
+    /// `vCont?`
+    ContinueActions,
+    /// `QStartNoAckMode`
+    StartNoAckMode,
+    /// `qAttached`
+    Attached,
+    /// `qfThreadInfo`
+    FirstThreadInfo,
+    /// `qsThreadInfo`
+    NextThreadInfo,
+    /// `H`, `T` and `qC`: there is a single thread
+    Thread,
+    /// `D`
+    Detach,
+    /// `k`
+    Kill,
+    /// Any request that is not supported, answered by an empty packet
+    Unsupported,
+}
+
+impl Command {
+    /// Parse the payload of a packet, return `None` when a supported request is malformed
+    pub fn parse(payload: &[u8]) -> Option<Self> {
+        let (&kind, args) = match payload.split_first() {
+            Some(split) => split,
+            None => return Some(Command::Unsupported),
+        };
+        // binary data is only present in `X` packets
+        let text = std::str::from_utf8(args).unwrap_or_default();
+
+        Some(match kind {
+            b'?' => Command::StopReason,
+            b'g' => Command::ReadRegisters,
+            b'G' => Command::WriteRegisters(
+                text.as_bytes()
+                    .chunks(4)
+                    .map(|chunk| std::str::from_utf8(chunk).ok().and_then(parse_le))
+                    .collect::<Option<_>>()?,
+            ),
+            b'p' => Command::ReadRegister(usize::from_str_radix(text, 16).ok()?),
+            b'P' => {
+                let (index, value) = text.split_once('=')?;
+                Command::WriteRegister(usize::from_str_radix(index, 16).ok()?, parse_le(value)?)
+            }
+            b'm' => {
+                let (address, len) = text.split_once(',')?;
+                Command::ReadMemory {
+                    address: hex(address)?,
+                    len: usize::from_str_radix(len, 16).ok()?,
+                }
+            }
+            b'M' => {
+                let (header, data) = text.split_once(':')?;
+                let (address, _) = header.split_once(',')?;
+                Command::WriteMemory {
+                    address: hex(address)?,
+                    data: decode_hex(data)?,
+                }
+            }
+            b'X' => {
+                let colon = args.iter().position(|&b| b == b':')?;
+                let header = std::str::from_utf8(&args[..colon]).ok()?;
+                let (address, _) = header.split_once(',')?;
+                Command::WriteMemory {
+                    address: hex(address)?,
+                    data: args[colon + 1..].to_vec(),
+                }
+            }
+            b'c' => Command::Continue(optional_address(text)?),
+            b's' => Command::Step(optional_address(text)?),
+            b'Z' | b'z' => {
+                let mut fields = text.split(',');
+                let kind = match fields.next()? {
+                    "0" => BreakpointKind::Software,
+                    "1" => BreakpointKind::Hardware,
+                    "2" => BreakpointKind::Write,
+                    "3" => BreakpointKind::Read,
+                    "4" => BreakpointKind::Access,
+                    _ => return Some(Command::Unsupported),
+                };
+                let address = hex(fields.next()?)?;
+                let len = hex(fields.next()?.split(';').next()?)?;
+                if payload[0] == b'Z' {
+                    Command::InsertBreakpoint(kind, address, len)
+                } else {
+                    Command::RemoveBreakpoint(kind, address, len)
+                }
+            }
+            b'v' => match text {
+                "Cont?" => Command::ContinueActions,
+                _ => match text.strip_prefix("Cont;") {
+                    // a single action is applied to the single thread
+                    Some(actions) => {
+                        let action = actions.split(';').next()?.split(':').next()?;
+                        match action.as_bytes().first()? {
+                            b'c' | b'C' => Command::Continue(None),
+                            b's' | b'S' => Command::Step(None),
+                            _ => Command::Unsupported,
+                        }
+                    }
+                    None => Command::Unsupported,
+                },
+            },
+            b'q' => {
+                if text.starts_with("Supported") {
+                    Command::Supported
+                } else if let Some(range) = text.strip_prefix("Xfer:features:read:target.xml:") {
+                    let (offset, len) = range.split_once(',')?;
+                    Command::TargetDescription {
+                        offset: usize::from_str_radix(offset, 16).ok()?,
+                        len: usize::from_str_radix(len, 16).ok()?,
+                    }
+                } else if text.starts_with("Attached") {
+                    Command::Attached
+                } else {
+                    match text {
+                        "fThreadInfo" => Command::FirstThreadInfo,
+                        "sThreadInfo" => Command::NextThreadInfo,
+                        "C" => Command::Thread,
+                        _ => Command::Unsupported,
+                    }
+                }
+            }
+            b'Q' if text == "StartNoAckMode" => Command::StartNoAckMode,
+            b'H' | b'T' => Command::Thread,
+            b'D' => Command::Detach,
+            b'k' => Command::Kill,
+            _ => Command::Unsupported,
+        })
+    }
+}
+
+fn hex(s: &str) -> Option<u16> {
+    u16::from_str_radix(s, 16).ok()
+}
+
+/// Parse a register value, sent in the byte order of the target
+fn parse_le(s: &str) -> Option<u16> {
+    match decode_hex(s)?.as_slice() {
+        [low, high] => Some(u16::from_le_bytes([*low, *high])),
+        _ => None,
+    }
+}
+
+fn optional_address(text: &str) -> Option<Option<u16>> {
+    if text.is_empty() {
+        Some(None)
+    } else {
+        hex(text).map(Some)
+    }
+}
+
+fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    if s.len() % 2 != 0 {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+pub fn encode_hex(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod test_command {
+    use super::{BreakpointKind, Command};
+
+    fn parse(payload: &str) -> Option<Command> {
+        Command::parse(payload.as_bytes())
+    }
+
+    #[test]
+    fn registers() {
+        assert_eq!(parse("g"), Some(Command::ReadRegisters));
+        assert_eq!(parse("p5"), Some(Command::ReadRegister(5)));
+        assert_eq!(parse("P5=5001"), Some(Command::WriteRegister(5, 0x0150)));
+        assert_eq!(
+            parse("Gb0011300d8004d01feff0001"),
+            Some(Command::WriteRegisters(vec![
+                0x01b0, 0x0013, 0x00d8, 0x014d, 0xfffe, 0x0100
+            ]))
+        );
+        assert_eq!(parse("P5=50"), None);
+    }
+
+    #[test]
+    fn memory() {
+        assert_eq!(
+            parse("mc000,10"),
+            Some(Command::ReadMemory {
+                address: 0xc000,
+                len: 16
+            })
+        );
+        assert_eq!(
+            parse("Mc000,2:2a00"),
+            Some(Command::WriteMemory {
+                address: 0xc000,
+                data: vec![0x2a, 0]
+            })
+        );
+        assert_eq!(
+            Command::parse(b"Xc000,1:\x7d\x03"),
+            Some(Command::WriteMemory {
+                address: 0xc000,
+                data: vec![0x7d, 0x03]
+            })
+        );
+        assert_eq!(parse("mzz,1"), None);
+    }
+
+    #[test]
+    fn execution() {
+        assert_eq!(parse("c"), Some(Command::Continue(None)));
+        assert_eq!(parse("s150"), Some(Command::Step(Some(0x150))));
+        assert_eq!(parse("vCont;s:1"), Some(Command::Step(None)));
+        assert_eq!(parse("vCont?"), Some(Command::ContinueActions));
+        assert_eq!(
+            parse("Z0,150,1"),
+            Some(Command::InsertBreakpoint(
+                BreakpointKind::Software,
+                0x150,
+                1
+            ))
+        );
+        assert_eq!(
+            parse("z2,c000,2"),
+            Some(Command::RemoveBreakpoint(BreakpointKind::Write, 0xc000, 2))
+        );
+        assert_eq!(parse("vMustReplyEmpty"), Some(Command::Unsupported));
+    }
+}
@@ -0,0 +1,135 @@
+/// Byte sent by GDB to interrupt the target
+const INTERRUPT: u8 = 0x03;
+
+/// Message received from GDB
+#[derive(Debug, PartialEq, Eq)]
+pub enum Incoming {
+    /// Payload of a packet with a valid checksum
+    Packet(Vec<u8>),
+    /// Packet with an invalid checksum, it need to be sent again
+    Corrupted,
+    /// Request to stop the execution
+    Interrupt,
+    Ack,
+    Nack,
+}
+
+/// Split the byte stream received from GDB into messages
+#[derive(Debug, Default)]
+pub struct PacketReader {
+    buffer: Vec<u8>,
+}
+
+impl PacketReader {
+    pub fn push(&mut self, data: &[u8]) {
+        self.buffer.extend_from_slice(data);
+    }
+
+    /// Extract the next complete message, if any
+    pub fn pop(&mut self) -> Option<Incoming> {
+        let start = self
+            .buffer
+            .iter()
+            .position(|b| matches!(b, b'$' | b'+' | b'-' | &INTERRUPT));
+        let start = match start {
+            Some(start) => start,
+            None => {
+                self.buffer.clear();
+                return None;
+            }
+        };
+        self.buffer.drain(..start);
+
+        let incoming = match self.buffer[0] {
+            b'+' => Incoming::Ack,
+            b'-' => Incoming::Nack,
+            INTERRUPT => Incoming::Interrupt,
+            _ => {
+                let end = self.buffer.iter().position(|&b| b == b'#')?;
+                if self.buffer.len() < end + 3 {
+                    return None;
+                }
+                let payload = unescape(&self.buffer[1..end]);
+                let valid = std::str::from_utf8(&self.buffer[end + 1..end + 3])
+                    .ok()
+                    .and_then(|checksum| u8::from_str_radix(checksum, 16).ok())
+                    == Some(checksum(&self.buffer[1..end]));
+                self.buffer.drain(..end + 3);
+                return Some(if valid {
+                    Incoming::Packet(payload)
+                } else {
+                    Incoming::Corrupted
+                });
+            }
+        };
+        self.buffer.remove(0);
+        Some(incoming)
+    }
+}
+
+/// Frame `payload` into a packet, escaping the reserved characters
+pub fn encode(payload: &[u8]) -> Vec<u8> {
+    let mut escaped = Vec::with_capacity(payload.len());
+    for &b in payload {
+        if matches!(b, b'#' | b'$' | b'}' | b'*') {
+            escaped.push(b'}');
+            escaped.push(b ^ 0x20);
+        } else {
+            escaped.push(b);
+        }
+    }
+
+    let mut packet = Vec::with_capacity(escaped.len() + 4);
+    packet.push(b'$');
+    packet.extend_from_slice(&escaped);
+    packet.extend_from_slice(format!("#{:02x}", checksum(&escaped)).as_bytes());
+    packet
+}
+
+fn checksum(data: &[u8]) -> u8 {
+    data.iter().fold(0, |sum, &b| sum.wrapping_add(b))
+}
+
+fn unescape(data: &[u8]) -> Vec<u8> {
+    let mut payload = Vec::with_capacity(data.len());
+    let mut bytes = data.iter();
+    while let Some(&b) = bytes.next() {
+        if b == b'}' {
+            if let Some(&escaped) = bytes.next() {
+                payload.push(escaped ^ 0x20);
+            }
+        } else {
+            payload.push(b);
+        }
+    }
+    payload
+}
+
+#[cfg(test)]
+mod test_packet {
+    use super::{encode, Incoming, PacketReader};
+
+    #[test]
+    fn read() {
+        let mut reader = PacketReader::default();
+        reader.push(b"+$g#67$m0100,");
+        assert_eq!(reader.pop(), Some(Incoming::Ack));
+        assert_eq!(reader.pop(), Some(Incoming::Packet(b"g".to_vec())));
+        assert_eq!(reader.pop(), None);
+        reader.push(b"4#8e\x03$g#00");
+        assert_eq!(reader.pop(), Some(Incoming::Packet(b"m0100,4".to_vec())));
+        assert_eq!(reader.pop(), Some(Incoming::Interrupt));
+        assert_eq!(reader.pop(), Some(Incoming::Corrupted));
+        assert_eq!(reader.pop(), None);
+    }
+
+    #[test]
+    fn escape() {
+        let packet = encode(b"a#b");
+        assert_eq!(packet, b"$a}\x03b#43".to_vec());
+
+        let mut reader = PacketReader::default();
+        reader.push(&packet);
+        assert_eq!(reader.pop(), Some(Incoming::Packet(b"a#b".to_vec())));
+    }
+}
@@ -0,0 +1,108 @@
+use std::collections::VecDeque;
+
+/// Number of snapshots kept by default
+pub const DEFAULT_CAPACITY: usize = 60;
+
+/// Snapshots of the machine indexed by the number of instructions executed,
+/// the oldest ones are dropped when the capacity is reached
+#[derive(Debug)]
+pub struct History<S> {
+    snapshots: VecDeque<(u64, S)>,
+    capacity: usize,
+}
+
+impl<S> Default for History<S> {
+    fn default() -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY)
+    }
+}
+
+impl<S> History<S> {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            snapshots: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Add the snapshot taken before the execution of the instruction `instruction`,
+    /// it is ignored if it isn't newer than the last one
+    pub fn push(&mut self, instruction: u64, snapshot: S) {
+        if self
+            .snapshots
+            .back()
+            .map_or(false, |(last, _)| *last >= instruction)
+        {
+            return;
+        }
+        if self.snapshots.len() == self.capacity {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back((instruction, snapshot));
+    }
+
+    pub fn oldest(&self) -> Option<&(u64, S)> {
+        self.snapshots.front()
+    }
+
+    /// Return the last snapshot taken at or before `instruction`
+    pub fn latest(&self, instruction: u64) -> Option<&(u64, S)> {
+        self.snapshots
+            .iter()
+            .rev()
+            .find(|(taken, _)| *taken <= instruction)
+    }
+
+    /// Keep only the snapshots taken before the instruction `end`
+    pub fn truncate(&mut self, end: u64) {
+        while self
+            .snapshots
+            .back()
+            .map_or(false, |(taken, _)| *taken >= end)
+        {
+            self.snapshots.pop_back();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+    }
+}
+
+#[cfg(test)]
+mod test_history {
+    use super::History;
+
+    #[test]
+    fn latest() {
+        let mut history = History::with_capacity(3);
+        history.push(0, 'a');
+        history.push(10, 'b');
+        history.push(10, 'x');
+        history.push(5, 'x');
+        history.push(20, 'c');
+
+        assert_eq!(history.oldest(), Some(&(0, 'a')));
+        assert_eq!(history.latest(15), Some(&(10, 'b')));
+        assert_eq!(history.latest(20), Some(&(20, 'c')));
+        assert_eq!(history.latest(9), Some(&(0, 'a')));
+
+        history.push(30, 'd');
+        assert_eq!(history.oldest(), Some(&(10, 'b')));
+        assert_eq!(history.latest(9), None);
+    }
+
+    #[test]
+    fn truncate() {
+        let mut history = History::default();
+        for instruction in [0, 10, 20, 30] {
+            history.push(instruction, instruction);
+        }
+        history.truncate(21);
+        assert_eq!(history.latest(100), Some(&(20, 20)));
+        history.truncate(10);
+        assert_eq!(history.latest(100), Some(&(0, 0)));
+        history.push(1, 1);
+        assert_eq!(history.latest(100), Some(&(1, 1)));
+    }
+}
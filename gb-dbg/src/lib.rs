@@ -1,6 +1,18 @@
+pub mod audio;
+pub mod coverage;
 pub mod dbg_interfaces;
 pub mod debugger;
+pub mod gdb;
+pub mod history;
+pub mod png;
+pub mod profiler;
+pub mod recording;
+pub mod search;
+pub mod symbols;
+pub mod timeline;
+pub mod trace;
 pub mod until;
+pub mod wav;
 
 pub const DEBUGGER_WIDTH: f64 = 1650.0;
 pub const DEBUGGER_HEIGHT: f64 = 700.0;
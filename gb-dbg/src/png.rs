@@ -0,0 +1,136 @@
+use std::io::{self, Write};
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+/// Biggest payload of an uncompressed deflate block
+const MAX_STORED_BLOCK: usize = 0xffff;
+
+/// Write a RGB image in the PNG format, the pixels are listed row by row.
+///
+/// The image data isn't compressed, which keep the encoder small
+/// at the cost of the file size.
+pub fn write_rgb(
+    writer: &mut impl Write,
+    width: u32,
+    height: u32,
+    pixels: &[[u8; 3]],
+) -> io::Result<()> {
+    if pixels.len() != width as usize * height as usize {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "{} pixels for an image of {}x{}",
+                pixels.len(),
+                width,
+                height
+            ),
+        ));
+    }
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    // 8 bits per channel, RGB, deflate, no filter, no interlace
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+    let mut scanlines = Vec::with_capacity(pixels.len() * 3 + height as usize);
+    if width > 0 {
+        for row in pixels.chunks(width as usize) {
+            // filter type None
+            scanlines.push(0);
+            scanlines.extend(row.iter().flatten());
+        }
+    }
+
+    writer.write_all(&SIGNATURE)?;
+    write_chunk(writer, b"IHDR", &header)?;
+    write_chunk(writer, b"IDAT", &zlib_stored(&scanlines))?;
+    write_chunk(writer, b"IEND", &[])
+}
+
+fn write_chunk(writer: &mut impl Write, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    writer.write_all(&(data.len() as u32).to_be_bytes())?;
+    writer.write_all(kind)?;
+    writer.write_all(data)?;
+    let crc = crc32(kind.iter().chain(data));
+    writer.write_all(&crc.to_be_bytes())
+}
+
+/// Wrap `data` in a zlib stream made of uncompressed deflate blocks
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut stream = Vec::with_capacity(data.len() + data.len() / MAX_STORED_BLOCK * 5 + 16);
+    // deflate with a 32K window, no preset dictionary, fastest compression
+    stream.extend_from_slice(&[0x78, 0x01]);
+
+    let mut blocks = data.chunks(MAX_STORED_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        stream.extend_from_slice(&[0x01, 0x00, 0x00, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        let len = block.len() as u16;
+        stream.push(u8::from(last));
+        stream.extend_from_slice(&len.to_le_bytes());
+        stream.extend_from_slice(&(!len).to_le_bytes());
+        stream.extend_from_slice(block);
+    }
+    stream.extend_from_slice(&adler32(data).to_be_bytes());
+    stream
+}
+
+fn crc32<'a>(bytes: impl IntoIterator<Item = &'a u8>) -> u32 {
+    let mut crc = !0_u32;
+    for byte in bytes {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+    let (mut a, mut b) = (1_u32, 0_u32);
+    for byte in bytes {
+        a = (a + u32::from(*byte)) % MOD;
+        b = (b + a) % MOD;
+    }
+    b << 16 | a
+}
+
+#[cfg(test)]
+mod test_png {
+    use super::{adler32, crc32, write_rgb, zlib_stored};
+
+    #[test]
+    fn checksums() {
+        assert_eq!(crc32(b"IEND"), 0xae42_6082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    }
+
+    #[test]
+    fn stored_blocks() {
+        let data = vec![0xaa; 0x10000];
+        let stream = zlib_stored(&data);
+        // header, 2 blocks of 5 bytes of header, adler32
+        assert_eq!(stream.len(), 2 + 5 + 0xffff + 5 + 1 + 4);
+        assert_eq!(&stream[2..7], &[0x00, 0xff, 0xff, 0x00, 0x00]);
+        assert_eq!(&stream[0x10006..0x1000b], &[0x01, 0x01, 0x00, 0xfe, 0xff]);
+    }
+
+    #[test]
+    fn image() {
+        let mut png = Vec::new();
+        write_rgb(&mut png, 2, 1, &[[255, 0, 0], [0, 0, 255]]).unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(&png[16..24], &[0, 0, 0, 2, 0, 0, 0, 1]);
+        assert_eq!(&png[png.len() - 12..], b"\0\0\0\0IEND\xae\x42\x60\x82");
+
+        assert!(write_rgb(&mut Vec::new(), 2, 2, &[[0, 0, 0]]).is_err());
+    }
+}
@@ -0,0 +1,295 @@
+use crate::symbols::SymbolTable;
+use gb_cpu::microcode::call_stack::Frame;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::{self, Display};
+
+/// Number of frames kept for the CPU usage graph
+pub const FRAME_HISTORY: usize = 120;
+
+/// Entry point of a routine, the cycles executed outside of any call
+/// are attributed to the entry point of the ROM
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Routine {
+    pub bank: usize,
+    pub address: u16,
+}
+
+impl Routine {
+    pub const ENTRY_POINT: Routine = Routine {
+        bank: 0,
+        address: 0x100,
+    };
+
+    /// Return the routine containing `address`, identified by the label of the symbol file.
+    /// The local labels are attributed to their parent label.
+    pub fn from_label(symbols: &SymbolTable, bank: usize, address: u16) -> Option<Routine> {
+        let location = symbols.locate(Some(bank as u16), address)?;
+        let symbol = match location.symbol.name.split_once('.') {
+            Some((parent, _)) if !parent.is_empty() => {
+                symbols.get(parent).unwrap_or(location.symbol)
+            }
+            _ => location.symbol,
+        };
+        Some(Routine {
+            bank: symbol.bank as usize,
+            address: symbol.address,
+        })
+    }
+
+    /// Return the routine called by a frame of the call stack
+    pub fn from_frame(symbols: &SymbolTable, frame: &Frame, rom_bank: usize) -> Routine {
+        let bank = match frame.target {
+            0x0000..=0x3fff => 0,
+            _ => frame.bank.unwrap_or(rom_bank),
+        };
+        Routine::from_label(symbols, bank, frame.target).unwrap_or(Routine {
+            bank,
+            address: frame.target,
+        })
+    }
+}
+
+impl Display for Routine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:02X}:{:04X}", self.bank, self.address)
+    }
+}
+
+/// Address of an instruction with the bank it is executed from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Hotspot {
+    pub bank: usize,
+    pub address: u16,
+}
+
+impl Display for Hotspot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:02X}:{:04X}", self.bank, self.address)
+    }
+}
+
+/// Cycles spent in a routine
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RoutineCycles {
+    /// Cycles spent in the routine and the routines it called
+    pub inclusive: u64,
+    /// Cycles spent in the routine itself
+    pub exclusive: u64,
+}
+
+/// Cycles of a frame spent executing instructions or halted
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FrameUsage {
+    pub busy: usize,
+    pub idle: usize,
+}
+
+impl FrameUsage {
+    /// Return the part of the frame spent executing instructions
+    pub fn usage(&self) -> f32 {
+        match self.busy + self.idle {
+            0 => 0.0,
+            total => self.busy as f32 / total as f32,
+        }
+    }
+}
+
+/// Attribute the cycles executed by the cpu to the routines of the game
+///
+/// The instruction and the routines active during it are set with [Profiler::enter],
+/// then each cycle is counted with [Profiler::tick]. The executed cycles are attributed
+/// to the routines and to the instruction itself, the hotspots.
+#[derive(Debug, Default)]
+pub struct Profiler {
+    enabled: bool,
+    routines: HashMap<Routine, RoutineCycles>,
+    /// Routines active for the current instruction, the innermost last
+    active: Vec<Routine>,
+    hotspots: HashMap<Hotspot, u64>,
+    /// Instruction being executed
+    instruction: Option<Hotspot>,
+    busy: u64,
+    idle: u64,
+    current: FrameUsage,
+    frames: VecDeque<FrameUsage>,
+}
+
+impl Profiler {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn start(&mut self) {
+        self.enabled = true;
+    }
+
+    pub fn stop(&mut self) {
+        self.enabled = false;
+        self.active.clear();
+        self.instruction = None;
+    }
+
+    /// Forget the cycles counted so far
+    pub fn reset(&mut self) {
+        self.routines.clear();
+        self.hotspots.clear();
+        self.busy = 0;
+        self.idle = 0;
+        self.current = FrameUsage::default();
+        self.frames.clear();
+    }
+
+    /// Set the instruction about to be executed and its routines, the innermost last.
+    /// A recursive routine is counted once in the inclusive cycles.
+    pub fn enter(&mut self, instruction: Hotspot, routines: impl IntoIterator<Item = Routine>) {
+        self.instruction = Some(instruction);
+        self.active.clear();
+        for routine in routines {
+            self.active.retain(|active| *active != routine);
+            self.active.push(routine);
+        }
+    }
+
+    /// Count a cycle, a halted cpu is idle
+    pub fn tick(&mut self, halted: bool) {
+        if halted {
+            self.idle += 1;
+            self.current.idle += 1;
+            return;
+        }
+
+        self.busy += 1;
+        self.current.busy += 1;
+        if let Some(instruction) = self.instruction {
+            *self.hotspots.entry(instruction).or_default() += 1;
+        }
+        if let Some((innermost, callers)) = self.active.split_last() {
+            let cycles = self.routines.entry(*innermost).or_default();
+            cycles.inclusive += 1;
+            cycles.exclusive += 1;
+            for caller in callers {
+                self.routines.entry(*caller).or_default().inclusive += 1;
+            }
+        }
+    }
+
+    /// Close the current frame of the usage graph
+    pub fn end_frame(&mut self) {
+        if self.frames.len() == FRAME_HISTORY {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(std::mem::take(&mut self.current));
+    }
+
+    /// Return the cycles of every routine, sorted by exclusive cycles
+    pub fn routines(&self) -> Vec<(Routine, RoutineCycles)> {
+        let mut routines: Vec<_> = self
+            .routines
+            .iter()
+            .map(|(routine, cycles)| (*routine, *cycles))
+            .collect();
+        routines.sort_by(|(a, a_cycles), (b, b_cycles)| {
+            b_cycles.exclusive.cmp(&a_cycles.exclusive).then(a.cmp(b))
+        });
+        routines
+    }
+
+    /// Return the cycles executed by every instruction, the most expensive first
+    pub fn hotspots(&self) -> Vec<(Hotspot, u64)> {
+        let mut hotspots: Vec<_> = self
+            .hotspots
+            .iter()
+            .map(|(hotspot, cycles)| (*hotspot, *cycles))
+            .collect();
+        hotspots.sort_by(|(a, a_cycles), (b, b_cycles)| b_cycles.cmp(a_cycles).then(a.cmp(b)));
+        hotspots
+    }
+
+    /// Return the usage of the last frames, the oldest first
+    pub fn frames(&self) -> &VecDeque<FrameUsage> {
+        &self.frames
+    }
+
+    /// Return the cycles spent executing instructions
+    pub fn busy(&self) -> u64 {
+        self.busy
+    }
+
+    /// Return the cycles spent halted
+    pub fn idle(&self) -> u64 {
+        self.idle
+    }
+}
+
+#[cfg(test)]
+mod test_profiler {
+    use super::{FrameUsage, Hotspot, Profiler, Routine, RoutineCycles};
+    use crate::symbols::SymbolTable;
+
+    fn routine(address: u16) -> Routine {
+        Routine { bank: 0, address }
+    }
+
+    fn at(address: u16) -> Hotspot {
+        Hotspot { bank: 0, address }
+    }
+
+    #[test]
+    fn inclusive_exclusive() {
+        let mut profiler = Profiler::default();
+        profiler.enter(at(0x150), [routine(0x100)]);
+        profiler.tick(false);
+        profiler.enter(at(0x210), [routine(0x100), routine(0x200), routine(0x200)]);
+        profiler.tick(false);
+        profiler.tick(false);
+        profiler.tick(true);
+        profiler.end_frame();
+
+        assert_eq!(
+            profiler.routines(),
+            vec![
+                (
+                    routine(0x200),
+                    RoutineCycles {
+                        inclusive: 2,
+                        exclusive: 2
+                    }
+                ),
+                (
+                    routine(0x100),
+                    RoutineCycles {
+                        inclusive: 3,
+                        exclusive: 1
+                    }
+                ),
+            ]
+        );
+        assert_eq!((profiler.busy(), profiler.idle()), (3, 1));
+        assert_eq!(profiler.hotspots(), vec![(at(0x210), 2), (at(0x150), 1)]);
+        assert_eq!(
+            profiler.frames().back(),
+            Some(&FrameUsage { busy: 3, idle: 1 })
+        );
+        assert_eq!(profiler.frames()[0].usage(), 0.75);
+    }
+
+    #[test]
+    fn labels() {
+        let symbols = SymbolTable::parse(
+            "00:0150 Main\n00:0160 Main.loop\n01:4000 Update\n01:4010 .skip\n".as_bytes(),
+        )
+        .unwrap();
+        assert_eq!(
+            Routine::from_label(&symbols, 0, 0x0165),
+            Some(routine(0x150))
+        );
+        assert_eq!(
+            Routine::from_label(&symbols, 1, 0x4012),
+            Some(Routine {
+                bank: 1,
+                address: 0x4010
+            })
+        );
+        assert_eq!(Routine::from_label(&symbols, 2, 0x4012), None);
+    }
+}
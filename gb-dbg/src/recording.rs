@@ -0,0 +1,157 @@
+use crate::png;
+use crate::wav::WavWriter;
+use gb_clock::Clock;
+use gb_ppu::ImageRGB;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, SyncSender};
+use std::thread::{self, JoinHandle};
+
+const AUDIO_FILE: &str = "audio.wav";
+const INFO_FILE: &str = "recording.txt";
+/// Frames waiting to be encoded before the emulation is blocked
+const PENDING_FRAMES: usize = 60;
+
+/// Screen at the end of a frame, sent to the thread encoding it
+struct Frame {
+    path: PathBuf,
+    width: u32,
+    height: u32,
+    pixels: Vec<[u8; 3]>,
+}
+
+/// Recording of the screen and of the sound of the emulation, written in a directory:
+/// - `audio.wav`: the samples of the APU, in 16 bits stereo
+/// - `frame_NNNNNN.png`: the screen at the end of each frame
+/// - `recording.txt`: the timing of the recording
+///
+/// The frames last exactly [Clock::CYCLES_PER_FRAME] cycles, so the frame `N`
+/// starts at the sample `N * sample_rate * CYCLES_PER_FRAME / CYCLES_PER_SECOND`
+/// whatever the speed of the emulation was.
+///
+/// The frames are encoded by another thread, out of the emulation.
+pub struct Recorder {
+    directory: PathBuf,
+    audio: WavWriter<BufWriter<File>>,
+    sample_rate: u32,
+    frames: usize,
+    /// Frames sent to the encoder, closed when the recording is finished
+    sender: SyncSender<Frame>,
+    /// Thread writing the frames, it stops at the first error
+    encoder: JoinHandle<io::Result<()>>,
+}
+
+impl Recorder {
+    /// Create `directory` and start the recording of a sound sampled at `sample_rate`
+    pub fn start(directory: impl AsRef<Path>, sample_rate: u32) -> io::Result<Self> {
+        let directory = directory.as_ref().to_path_buf();
+        fs::create_dir_all(&directory)?;
+        let audio = File::create(directory.join(AUDIO_FILE))?;
+        let (sender, receiver) = mpsc::sync_channel(PENDING_FRAMES);
+        let encoder = thread::spawn(move || receiver.iter().try_for_each(write_frame));
+        Ok(Self {
+            audio: WavWriter::new(BufWriter::new(audio), sample_rate)?,
+            directory,
+            sample_rate,
+            frames: 0,
+            sender,
+            encoder,
+        })
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// Return the number of frames recorded
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    /// Add the screen at the end of a frame and the stereo samples produced during this frame.
+    ///
+    /// The error of the encoder is returned by [Recorder::finish]
+    pub fn push_frame<const WIDTH: usize, const HEIGHT: usize>(
+        &mut self,
+        image: &ImageRGB<WIDTH, HEIGHT>,
+        samples: &[[f32; 2]],
+    ) -> io::Result<()> {
+        let frame = Frame {
+            path: self.directory.join(format!("frame_{:06}.png", self.frames)),
+            width: WIDTH as u32,
+            height: HEIGHT as u32,
+            pixels: image.iter().flatten().copied().collect(),
+        };
+        self.sender.send(frame).map_err(|_| {
+            io::Error::new(io::ErrorKind::Other, "the encoder of the frames stopped")
+        })?;
+
+        self.audio.write_frames(samples)?;
+        self.frames += 1;
+        Ok(())
+    }
+
+    /// Wait for the encoding of the frames, complete the audio file and describe the recording
+    pub fn finish(self) -> io::Result<()> {
+        drop(self.sender);
+        self.encoder.join().map_err(|_| {
+            io::Error::new(io::ErrorKind::Other, "the encoder of the frames panicked")
+        })??;
+        let samples = self.audio.frames();
+        self.audio.finish()?;
+
+        let framerate = format!("{}/{}", Clock::CYCLES_PER_SECOND, Clock::CYCLES_PER_FRAME);
+        let mut info = BufWriter::new(File::create(self.directory.join(INFO_FILE))?);
+        writeln!(info, "frames: {}", self.frames)?;
+        writeln!(
+            info,
+            "frame rate: {} ({:.4} fps)",
+            framerate,
+            Clock::CYCLES_PER_SECOND as f64 / Clock::CYCLES_PER_FRAME as f64
+        )?;
+        writeln!(info, "samples: {}", samples)?;
+        writeln!(info, "sample rate: {} Hz", self.sample_rate)?;
+        writeln!(info, "lossless video:")?;
+        writeln!(
+            info,
+            "ffmpeg -framerate {} -i frame_%06d.png -i {} -c:v ffv1 -c:a copy recording.mkv",
+            framerate, AUDIO_FILE
+        )?;
+        info.flush()
+    }
+}
+
+fn write_frame(frame: Frame) -> io::Result<()> {
+    let mut file = BufWriter::new(File::create(frame.path)?);
+    png::write_rgb(&mut file, frame.width, frame.height, &frame.pixels)?;
+    file.flush()
+}
+
+#[cfg(test)]
+mod test_recording {
+    use super::Recorder;
+    use std::fs;
+
+    #[test]
+    fn directory() {
+        let directory = std::env::temp_dir().join(format!("gbmu_recording_{}", std::process::id()));
+        let mut recorder = Recorder::start(&directory, 48000).unwrap();
+        let image = [[[0xff, 0x00, 0x00]; 4]; 2];
+        recorder.push_frame(&image, &[[0.5, -0.5]; 804]).unwrap();
+        recorder.push_frame(&image, &[[0.0, 0.0]; 803]).unwrap();
+        assert_eq!(recorder.frames(), 2);
+        recorder.finish().unwrap();
+
+        let audio = fs::read(directory.join("audio.wav")).unwrap();
+        assert_eq!(audio.len(), 44 + (804 + 803) * 4);
+        let frame = fs::read(directory.join("frame_000001.png")).unwrap();
+        assert_eq!(&frame[16..24], &[0, 0, 0, 4, 0, 0, 0, 2]);
+        assert!(!directory.join("frame_000002.png").exists());
+        let info = fs::read_to_string(directory.join("recording.txt")).unwrap();
+        assert!(info.contains("frame rate: 1048576/17556"));
+        assert!(info.contains("samples: 1607"));
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
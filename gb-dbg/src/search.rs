@@ -0,0 +1,335 @@
+use crate::dbg_interfaces::BankedArea;
+use std::fmt::{self, Display};
+use std::ops::RangeInclusive;
+
+/// Memory where the variables of a game are searched
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Region {
+    Wram,
+    Hram,
+    CartRam,
+}
+
+impl Region {
+    pub const ALL: [Region; 3] = [Region::Wram, Region::Hram, Region::CartRam];
+
+    /// Return the banked area of the region, HRAM has no bank
+    pub fn area(self) -> Option<BankedArea> {
+        match self {
+            Region::Wram => Some(BankedArea::Wram),
+            Region::Hram => None,
+            Region::CartRam => Some(BankedArea::CartRam),
+        }
+    }
+
+    /// Return each bank of the region with the addresses where it is mapped,
+    /// `bank_count` is the number of banks of its area
+    pub fn banks(self, bank_count: usize) -> Vec<(usize, RangeInclusive<u16>)> {
+        match self.area() {
+            Some(area) => (0..bank_count)
+                .map(|bank| {
+                    let start = area.start(bank);
+                    (bank, start..=start + (area.size() - 1))
+                })
+                .collect(),
+            None => vec![(0, 0xff80..=0xfffe)],
+        }
+    }
+}
+
+impl Display for Region {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Region::Wram => "WRAM",
+            Region::Hram => "HRAM",
+            Region::CartRam => "Cartridge RAM",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Size and byte order of the searched values
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueSize {
+    U8,
+    U16Le,
+    U16Be,
+}
+
+impl ValueSize {
+    pub const ALL: [ValueSize; 3] = [ValueSize::U8, ValueSize::U16Le, ValueSize::U16Be];
+
+    pub fn byte_count(self) -> u16 {
+        match self {
+            ValueSize::U8 => 1,
+            ValueSize::U16Le | ValueSize::U16Be => 2,
+        }
+    }
+
+    pub fn max(self) -> u16 {
+        match self {
+            ValueSize::U8 => 0xff,
+            ValueSize::U16Le | ValueSize::U16Be => 0xffff,
+        }
+    }
+
+    /// Read the value at `address`
+    pub fn read<F: Fn(u16) -> u8>(self, address: u16, read: F) -> u16 {
+        match self {
+            ValueSize::U8 => read(address) as u16,
+            ValueSize::U16Le => u16::from_le_bytes([read(address), read(address.wrapping_add(1))]),
+            ValueSize::U16Be => u16::from_be_bytes([read(address), read(address.wrapping_add(1))]),
+        }
+    }
+
+    /// Return the bytes of `value` in the order they are stored in memory
+    pub fn bytes(self, value: u16) -> Vec<u8> {
+        match self {
+            ValueSize::U8 => vec![value as u8],
+            ValueSize::U16Le => value.to_le_bytes().to_vec(),
+            ValueSize::U16Be => value.to_be_bytes().to_vec(),
+        }
+    }
+}
+
+impl Display for ValueSize {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            ValueSize::U8 => "8-bit",
+            ValueSize::U16Le => "16-bit LE",
+            ValueSize::U16Be => "16-bit BE",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Condition kept by the candidates, comparing their value with the previous snapshot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Filter {
+    Unchanged,
+    Changed,
+    Increased,
+    Decreased,
+    IncreasedBy(u16),
+    DecreasedBy(u16),
+    Value(u16),
+}
+
+impl Filter {
+    fn keeps(self, previous: u16, current: u16) -> bool {
+        match self {
+            Filter::Unchanged => current == previous,
+            Filter::Changed => current != previous,
+            Filter::Increased => current > previous,
+            Filter::Decreased => current < previous,
+            Filter::IncreasedBy(n) => current.wrapping_sub(previous) == n,
+            Filter::DecreasedBy(n) => previous.wrapping_sub(current) == n,
+            Filter::Value(value) => current == value,
+        }
+    }
+}
+
+impl Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Filter::Unchanged => write!(f, "unchanged"),
+            Filter::Changed => write!(f, "changed"),
+            Filter::Increased => write!(f, "increased"),
+            Filter::Decreased => write!(f, "decreased"),
+            Filter::IncreasedBy(n) => write!(f, "increased by {}", n),
+            Filter::DecreasedBy(n) => write!(f, "decreased by {}", n),
+            Filter::Value(value) => write!(f, "== {:#X}", value),
+        }
+    }
+}
+
+/// Parse a value typed by the user, decimal by default or hexadecimal with a `0x` or `$` prefix
+pub fn parse_value(s: &str) -> Option<u16> {
+    let s = s.trim();
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix('$')) {
+        Some(hex) => u16::from_str_radix(hex, 16).ok(),
+        None => s.parse().ok(),
+    }
+}
+
+/// Address still matching the filters, with its value at the last snapshot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Candidate {
+    pub region: Region,
+    /// Bank of the region holding the value, whether it is mapped or not
+    pub bank: usize,
+    pub address: u16,
+    pub value: u16,
+}
+
+impl Candidate {
+    /// Read the current value of the candidate with `read(region, bank, address)`
+    pub fn read<F: Fn(Region, usize, u16) -> u8>(&self, size: ValueSize, read: F) -> u16 {
+        size.read(self.address, |address| {
+            read(self.region, self.bank, address)
+        })
+    }
+}
+
+/// Search of the addresses of a variable by successive snapshots of the memory
+#[derive(Debug)]
+pub struct Search {
+    size: ValueSize,
+    candidates: Vec<Candidate>,
+    /// Filters applied since the start of the search
+    steps: usize,
+}
+
+impl Search {
+    /// Snapshot every bank of the regions, every value is a candidate.
+    /// `regions` are given with the number of banks of their area and
+    /// the banks are read with `read(region, bank, address)`, mapped or not
+    pub fn start<F: Fn(Region, usize, u16) -> u8>(
+        regions: &[(Region, usize)],
+        size: ValueSize,
+        read: F,
+    ) -> Self {
+        let mut candidates = Vec::new();
+        for &(region, bank_count) in regions {
+            for (bank, range) in region.banks(bank_count) {
+                // the values must fit in the bank
+                for address in *range.start()..=(range.end() + 1 - size.byte_count()) {
+                    let mut candidate = Candidate {
+                        region,
+                        bank,
+                        address,
+                        value: 0,
+                    };
+                    candidate.value = candidate.read(size, &read);
+                    candidates.push(candidate);
+                }
+            }
+        }
+        Self {
+            size,
+            candidates,
+            steps: 0,
+        }
+    }
+
+    pub fn size(&self) -> ValueSize {
+        self.size
+    }
+
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    pub fn candidates(&self) -> &[Candidate] {
+        &self.candidates
+    }
+
+    /// Take a new snapshot and keep the candidates matching the filter
+    pub fn filter<F: Fn(Region, usize, u16) -> u8>(&mut self, filter: Filter, read: F) {
+        let size = self.size;
+        let mut kept = Vec::with_capacity(self.candidates.len());
+        for mut candidate in self.candidates.drain(..) {
+            let current = candidate.read(size, &read);
+            if filter.keeps(candidate.value, current) {
+                candidate.value = current;
+                kept.push(candidate);
+            }
+        }
+        self.candidates = kept;
+        self.steps += 1;
+    }
+
+    /// Remove a candidate
+    pub fn discard(&mut self, discarded: &Candidate) {
+        self.candidates.retain(|candidate| {
+            (candidate.region, candidate.bank, candidate.address)
+                != (discarded.region, discarded.bank, discarded.address)
+        });
+    }
+}
+
+#[cfg(test)]
+mod test_search {
+    use super::{Filter, Region, Search, ValueSize};
+    use std::cell::RefCell;
+
+    #[test]
+    fn filters() {
+        let memory = RefCell::new(vec![0_u8; 0x10000]);
+        let read = |_, _, address: u16| memory.borrow()[address as usize];
+        memory.borrow_mut()[0xc010] = 3;
+        memory.borrow_mut()[0xff90] = 3;
+
+        let mut search =
+            Search::start(&[(Region::Wram, 2), (Region::Hram, 0)], ValueSize::U8, read);
+        assert_eq!(search.candidates().len(), 0x2000 + 0x7f);
+        search.filter(Filter::Value(3), read);
+        assert_eq!(search.candidates().len(), 2);
+
+        memory.borrow_mut()[0xc010] = 2;
+        search.filter(Filter::Unchanged, read);
+        assert_eq!(search.candidates()[0].address, 0xff90);
+
+        memory.borrow_mut()[0xff90] = 1;
+        search.filter(Filter::DecreasedBy(2), read);
+        assert_eq!(search.candidates()[0].value, 1);
+        search.filter(Filter::Changed, read);
+        assert!(search.candidates().is_empty());
+        assert_eq!(search.steps(), 4);
+    }
+
+    #[test]
+    fn words() {
+        let memory = RefCell::new(vec![0_u8; 0x10000]);
+        let read = |_, _, address: u16| memory.borrow()[address as usize];
+
+        let mut search = Search::start(&[(Region::Hram, 0)], ValueSize::U16Le, read);
+        assert_eq!(search.candidates().last().unwrap().address, 0xfffd);
+        memory.borrow_mut()[0xff80] = 0xff;
+        memory.borrow_mut()[0xff81] = 0x00;
+        search.filter(Filter::IncreasedBy(0xff), read);
+        assert_eq!(search.candidates().len(), 1);
+        memory.borrow_mut()[0xff80] = 0x00;
+        memory.borrow_mut()[0xff81] = 0x01;
+        search.filter(Filter::Increased, read);
+        assert_eq!(search.candidates()[0].value, 0x100);
+
+        assert_eq!(
+            ValueSize::U16Be.read(0xff80, |address| read(Region::Hram, 0, address)),
+            0x0001
+        );
+        assert_eq!(super::parse_value("0x1F"), Some(0x1f));
+        assert_eq!(super::parse_value("$ff"), Some(0xff));
+        assert_eq!(super::parse_value(" 42"), Some(42));
+        assert_eq!(super::parse_value("ff"), None);
+        assert_eq!(ValueSize::U16Be.bytes(0x1234), vec![0x12, 0x34]);
+    }
+
+    #[test]
+    fn banks() {
+        // every bank holds its number at its first byte
+        let read = |_, bank: usize, address: u16| {
+            if address & 0xfff == 0 {
+                bank as u8
+            } else {
+                0
+            }
+        };
+        let mut search = Search::start(
+            &[(Region::Wram, 8), (Region::CartRam, 4)],
+            ValueSize::U8,
+            read,
+        );
+        assert_eq!(search.candidates().len(), 8 * 0x1000 + 4 * 0x2000);
+        search.filter(Filter::Value(5), read);
+        let found = search.candidates()[0];
+        assert_eq!(search.candidates().len(), 1);
+        assert_eq!(
+            (found.region, found.bank, found.address),
+            (Region::Wram, 5, 0xd000)
+        );
+
+        search.discard(&found);
+        assert!(search.candidates().is_empty());
+    }
+}
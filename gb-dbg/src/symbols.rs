@@ -0,0 +1,227 @@
+use crate::dbg_interfaces::{BankedArea, DebugOperations};
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+    fs::File,
+    io::{self, BufRead, BufReader},
+    ops::RangeInclusive,
+    path::Path,
+};
+
+/// A label of a symbol file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub bank: u16,
+    pub address: u16,
+    pub name: String,
+}
+
+/// Position of an address relative to the closest label before it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location<'a> {
+    pub symbol: &'a Symbol,
+    pub offset: u16,
+}
+
+impl Display for Location<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:02X}:{}", self.symbol.bank, self.symbol.name)?;
+        if self.offset != 0 {
+            write!(f, "+{:X}", self.offset)?;
+        }
+        Ok(())
+    }
+}
+
+/// Labels loaded from a `.sym` file, as generated by RGBDS and used by no$gmb and BGB
+///
+/// Each line is in the form `BB:AAAA Label`, everything after a `;` is a comment.
+#[derive(Debug, Default)]
+pub struct SymbolTable {
+    /// Symbols sorted by address then by bank
+    symbols: Vec<Symbol>,
+    by_name: HashMap<String, usize>,
+}
+
+impl SymbolTable {
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::parse(BufReader::new(File::open(path)?))
+    }
+
+    pub fn parse(reader: impl BufRead) -> io::Result<Self> {
+        let mut symbols = Vec::new();
+
+        for line in reader.lines() {
+            let line = line?;
+            let line = line.split(';').next().unwrap_or_default().trim();
+            if line.is_empty() || line.starts_with('[') {
+                continue;
+            }
+            match parse_line(line) {
+                Some(symbol) => symbols.push(symbol),
+                None => log::warn!("invalid symbol line: {}", line),
+            }
+        }
+        symbols.sort_by_key(|s| (s.address, s.bank));
+        let by_name = symbols
+            .iter()
+            .enumerate()
+            .map(|(index, symbol)| (symbol.name.clone(), index))
+            .collect();
+        Ok(Self { symbols, by_name })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn symbols(&self) -> impl Iterator<Item = &Symbol> {
+        self.symbols.iter()
+    }
+
+    /// Return the address of the label `name`
+    pub fn address_of(&self, name: &str) -> Option<u16> {
+        self.get(name).map(|symbol| symbol.address)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Symbol> {
+        self.by_name.get(name).map(|&index| &self.symbols[index])
+    }
+
+    /// Find the closest label before `address` in the same memory area.
+    /// When `bank` is `None`, the labels of every bank are considered.
+    pub fn locate(&self, bank: Option<u16>, address: u16) -> Option<Location<'_>> {
+        let end = self.symbols.partition_point(|s| s.address <= address);
+
+        self.symbols[..end]
+            .iter()
+            .rev()
+            .take_while(|s| area(s.address) == area(address))
+            .find(|s| bank.is_none() || bank == Some(s.bank))
+            .map(|symbol| Location {
+                symbol,
+                offset: address - symbol.address,
+            })
+    }
+
+    /// Return the labels of `bank` placed in `range`
+    pub fn labels_in(
+        &self,
+        bank: u16,
+        range: RangeInclusive<u16>,
+    ) -> impl Iterator<Item = &Symbol> + '_ {
+        let start = self.symbols.partition_point(|s| s.address < *range.start());
+
+        self.symbols[start..]
+            .iter()
+            .take_while(move |s| s.address <= *range.end())
+            .filter(move |s| s.bank == bank)
+    }
+}
+
+/// Return the bank mapped at `address`, used to look up the labels of the banked areas.
+/// The labels of the areas without banks are in the bank 0.
+pub fn mapped_bank<DBG: DebugOperations>(dbg: &DBG, address: u16) -> u16 {
+    match address {
+        0x0000..=0x3fff => 0,
+        0x4000..=0x7fff => dbg.rom_bank() as u16,
+        0x8000..=0x9fff if dbg.bank_count(BankedArea::Vram) > 1 => u16::from(dbg.read(VBK) & 1),
+        0xa000..=0xbfff => dbg.ram_bank() as u16,
+        0xd000..=0xdfff if dbg.bank_count(BankedArea::Wram) > 2 => {
+            u16::from(dbg.read(SVBK) & 0x07).max(1)
+        }
+        0xd000..=0xdfff => 1,
+        _ => 0,
+    }
+}
+
+/// VRAM bank register of the CGB
+const VBK: u16 = 0xff4f;
+/// WRAM bank register of the CGB
+const SVBK: u16 = 0xff70;
+
+fn parse_line(line: &str) -> Option<Symbol> {
+    let (location, name) = line.split_once(char::is_whitespace)?;
+    let (bank, address) = location.split_once(':')?;
+
+    Some(Symbol {
+        bank: u16::from_str_radix(bank, 16).ok()?,
+        address: u16::from_str_radix(address, 16).ok()?,
+        name: name.trim().to_owned(),
+    })
+}
+
+/// Identify the memory area of an address, a label never span over two areas
+fn area(address: u16) -> u8 {
+    match address {
+        0x0000..=0x3fff => 0,
+        0x4000..=0x7fff => 1,
+        0x8000..=0x9fff => 2,
+        0xa000..=0xbfff => 3,
+        0xc000..=0xcfff => 4,
+        0xd000..=0xdfff => 5,
+        0xe000..=0xff7f => 6,
+        0xff80..=0xffff => 7,
+    }
+}
+
+#[cfg(test)]
+mod test_symbols {
+    use super::SymbolTable;
+
+    const SYM: &str = "; File generated by rgblink
+00:0150 Start
+00:0160 Start.loop
+01:4000 Engine
+02:4000 Music
+02:4010 Music.play
+00:c000 wPlayerHP ; comment
+[labels]
+invalid
+";
+
+    #[test]
+    fn parse() {
+        let table = SymbolTable::parse(SYM.as_bytes()).unwrap();
+
+        assert_eq!(table.len(), 6);
+        assert_eq!(table.address_of("Music.play"), Some(0x4010));
+        assert_eq!(table.address_of("wPlayerHP"), Some(0xc000));
+        assert_eq!(table.address_of("Missing"), None);
+    }
+
+    #[test]
+    fn locate() {
+        let table = SymbolTable::parse(SYM.as_bytes()).unwrap();
+
+        let loc = |bank, addr| table.locate(bank, addr).map(|l| l.to_string());
+        assert_eq!(loc(Some(0), 0x0150), Some("00:Start".to_owned()));
+        assert_eq!(loc(Some(0), 0x0165), Some("00:Start.loop+5".to_owned()));
+        assert_eq!(loc(Some(2), 0x4012), Some("02:Music.play+2".to_owned()));
+        assert_eq!(loc(Some(1), 0x4012), Some("01:Engine+12".to_owned()));
+        assert_eq!(loc(Some(3), 0x4012), None);
+        assert_eq!(loc(Some(0), 0x0100), None);
+        assert_eq!(loc(None, 0xc001), Some("00:wPlayerHP+1".to_owned()));
+        assert_eq!(loc(None, 0xd000), None);
+    }
+
+    #[test]
+    fn labels_in() {
+        let table = SymbolTable::parse(SYM.as_bytes()).unwrap();
+
+        let names = |bank, range| {
+            table
+                .labels_in(bank, range)
+                .map(|s| s.name.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(2, 0x4000..=0x400f), ["Music"]);
+        assert_eq!(names(2, 0x4000..=0x4010), ["Music", "Music.play"]);
+        assert_eq!(names(1, 0x4000..=0x4010), ["Engine"]);
+        assert!(names(0, 0xc001..=0xffff).is_empty());
+    }
+}
@@ -0,0 +1,418 @@
+use gb_bus::{Bus, Source};
+use gb_clock::{Tick, Ticker};
+use std::fmt::{self, Display};
+
+/// Number of cycles needed to draw a scanline in normal speed
+pub const CYCLES_PER_LINE: usize = 114;
+/// Number of scanlines of a frame, including the vblank
+pub const LINES_PER_FRAME: usize = 154;
+
+const LCDC: u16 = 0xff40;
+const STAT: u16 = 0xff41;
+const LY: u16 = 0xff44;
+const KEY1: u16 = 0xff4d;
+const HDMA5: u16 = 0xff55;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interrupt {
+    VBlank,
+    Stat,
+    Timer,
+    Serial,
+    Joypad,
+}
+
+impl Interrupt {
+    pub const ALL: [Interrupt; 5] = [
+        Interrupt::VBlank,
+        Interrupt::Stat,
+        Interrupt::Timer,
+        Interrupt::Serial,
+        Interrupt::Joypad,
+    ];
+
+    /// Return the interrupt handled at `vector`
+    pub fn from_vector(vector: u16) -> Option<Self> {
+        match vector {
+            0x40 => Some(Interrupt::VBlank),
+            0x48 => Some(Interrupt::Stat),
+            0x50 => Some(Interrupt::Timer),
+            0x58 => Some(Interrupt::Serial),
+            0x60 => Some(Interrupt::Joypad),
+            _ => None,
+        }
+    }
+
+    fn mask(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+impl Display for Interrupt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Interrupt::VBlank => "VBlank",
+            Interrupt::Stat => "STAT",
+            Interrupt::Timer => "Timer",
+            Interrupt::Serial => "Serial",
+            Interrupt::Joypad => "Joypad",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Hardware event shown on the timeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// A component requested the interrupt, even if it was already pending
+    Request(Interrupt),
+    /// The cpu jumped to the interrupt vector
+    Dispatch(Interrupt),
+    /// The ppu entered the mode of `STAT`
+    Mode(u8),
+    /// The coincidence flag of `STAT` was set
+    LycMatch,
+    /// `TIMA` overflowed and was reloaded
+    TimerOverflow,
+    OamDma {
+        active: bool,
+    },
+    Hdma {
+        active: bool,
+    },
+    SpeedSwitch {
+        double: bool,
+    },
+}
+
+impl Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let state = |on| if on { "start" } else { "end" };
+        match self {
+            Event::Request(interrupt) => write!(f, "{} requested", interrupt),
+            Event::Dispatch(interrupt) => write!(f, "{} dispatched", interrupt),
+            Event::Mode(mode) => write!(f, "mode {}", mode),
+            Event::LycMatch => write!(f, "LY=LYC"),
+            Event::TimerOverflow => write!(f, "timer overflow"),
+            Event::OamDma { active } => write!(f, "OAM DMA {}", state(*active)),
+            Event::Hdma { active } => write!(f, "HDMA {}", state(*active)),
+            Event::SpeedSwitch { double: true } => write!(f, "double speed"),
+            Event::SpeedSwitch { double: false } => write!(f, "normal speed"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimedEvent {
+    /// Cycle since the start of the frame
+    pub cycle: usize,
+    /// Value of `LY` when the event happened
+    pub line: u8,
+    pub event: Event,
+}
+
+/// Events recorded between two starts of the scanline 0
+#[derive(Debug, Default, Clone)]
+pub struct Frame {
+    /// Index of the frame since the recording started
+    pub number: usize,
+    /// Number of cycles of the cpu during the frame
+    pub cycles: usize,
+    /// Mode of the ppu at the start of the frame
+    pub start_mode: u8,
+    /// Speed of the cpu at the start of the frame
+    pub double_speed: bool,
+    pub events: Vec<TimedEvent>,
+}
+
+impl Frame {
+    /// Return the number of cycles of the cpu during a scanline,
+    /// the cpu runs twice as many cycles in double speed
+    pub fn cycles_per_line(&self) -> usize {
+        if self.double_speed {
+            CYCLES_PER_LINE * 2
+        } else {
+            CYCLES_PER_LINE
+        }
+    }
+
+    /// Return the length of a frame, used to split the timeline while the lcd is off
+    pub fn cycles_per_frame(&self) -> usize {
+        self.cycles_per_line() * LINES_PER_FRAME
+    }
+}
+
+/// State of the hardware observed at the end of a cycle
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Sample {
+    /// Interrupts requested during the cycle
+    requests: u8,
+    stat: u8,
+    ly: u8,
+    lcd_enabled: bool,
+    timer_overflow: bool,
+    hdma: bool,
+    double_speed: bool,
+    oam_dma: bool,
+    dispatched: Option<u16>,
+}
+
+/// Record the hardware events of each frame with their cycle
+///
+/// The bus is observed as a [Ticker] at the end of each cycle of the cpu, the events
+/// not visible on the bus are reported before with [Timeline::observe].
+#[derive(Debug, Default)]
+pub struct Timeline {
+    enabled: bool,
+    previous: Option<Sample>,
+    dispatched: Option<u16>,
+    oam_dma: bool,
+    timer_overflow: bool,
+    requests: u8,
+    current: Frame,
+    last: Option<Frame>,
+}
+
+impl Timeline {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn start(&mut self) {
+        self.enabled = true;
+    }
+
+    pub fn stop(&mut self) {
+        self.enabled = false;
+        self.previous = None;
+        self.current = Frame::default();
+        self.last = None;
+    }
+
+    /// Return the last complete frame
+    pub fn last_frame(&self) -> Option<&Frame> {
+        self.last.as_ref()
+    }
+
+    /// Return the frame being recorded
+    pub fn current_frame(&self) -> &Frame {
+        &self.current
+    }
+
+    /// Report the state of the components that cannot be read on the bus,
+    /// `dispatched` is the vector of the interrupt being dispatched by the cpu
+    /// and `requests` the interrupts requested during the cycle
+    pub fn observe(
+        &mut self,
+        dispatched: Option<u16>,
+        oam_dma: bool,
+        timer_overflow: bool,
+        requests: u8,
+    ) {
+        self.dispatched = dispatched;
+        self.oam_dma = oam_dma;
+        self.timer_overflow = timer_overflow;
+        self.requests = requests;
+    }
+
+    fn sample(&self, adr_bus: &dyn Bus<u8>) -> Sample {
+        let read = |address, default| {
+            adr_bus
+                .read(address, Some(Source::Debugger))
+                .unwrap_or(default)
+        };
+        Sample {
+            requests: self.requests & 0x1f,
+            stat: read(STAT, 0),
+            ly: read(LY, 0),
+            lcd_enabled: read(LCDC, 0) & 0x80 != 0,
+            timer_overflow: self.timer_overflow,
+            // the registers of the color mode are not mapped on the monochrome model
+            hdma: read(HDMA5, 0xff) & 0x80 == 0,
+            double_speed: read(KEY1, 0) & 0x80 != 0,
+            oam_dma: self.oam_dma,
+            dispatched: self.dispatched,
+        }
+    }
+
+    fn record(&mut self, sample: Sample) {
+        let previous = match self.previous.replace(sample) {
+            Some(previous) => previous,
+            None => {
+                self.current.start_mode = sample.stat & 0b11;
+                self.current.double_speed = sample.double_speed;
+                return;
+            }
+        };
+
+        let new_frame = if sample.lcd_enabled {
+            previous.ly != 0 && sample.ly == 0
+        } else {
+            self.current.cycles >= self.current.cycles_per_frame()
+        };
+        if new_frame {
+            let number = self.current.number + 1;
+            let frame = std::mem::take(&mut self.current);
+            self.last = Some(frame);
+            self.current.number = number;
+            self.current.start_mode = previous.stat & 0b11;
+            self.current.double_speed = sample.double_speed;
+        }
+
+        let mut events = Vec::new();
+        for interrupt in Interrupt::ALL {
+            if sample.requests & interrupt.mask() != 0 {
+                events.push(Event::Request(interrupt));
+            }
+        }
+        if previous.dispatched.is_none() {
+            if let Some(interrupt) = sample.dispatched.and_then(Interrupt::from_vector) {
+                events.push(Event::Dispatch(interrupt));
+            }
+        }
+        if sample.stat & 0b11 != previous.stat & 0b11 {
+            events.push(Event::Mode(sample.stat & 0b11));
+        }
+        if sample.stat & 0b100 != 0 && previous.stat & 0b100 == 0 {
+            events.push(Event::LycMatch);
+        }
+        if sample.timer_overflow && !previous.timer_overflow {
+            events.push(Event::TimerOverflow);
+        }
+        if sample.oam_dma != previous.oam_dma {
+            events.push(Event::OamDma {
+                active: sample.oam_dma,
+            });
+        }
+        if sample.hdma != previous.hdma {
+            events.push(Event::Hdma {
+                active: sample.hdma,
+            });
+        }
+        if sample.double_speed != previous.double_speed {
+            events.push(Event::SpeedSwitch {
+                double: sample.double_speed,
+            });
+        }
+
+        let cycle = self.current.cycles;
+        self.current
+            .events
+            .extend(events.into_iter().map(|event| TimedEvent {
+                cycle,
+                line: sample.ly,
+                event,
+            }));
+        self.current.cycles += 1;
+    }
+}
+
+impl Ticker for Timeline {
+    fn cycle_count(&self) -> Tick {
+        Tick::MCycle
+    }
+
+    fn tick(&mut self, adr_bus: &mut dyn Bus<u8>) {
+        if self.enabled {
+            let sample = self.sample(adr_bus);
+            self.record(sample);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_timeline {
+    use super::{Event, Interrupt, Sample, Timeline, CYCLES_PER_LINE, LINES_PER_FRAME};
+
+    fn lcd_on(ly: u8, stat: u8) -> Sample {
+        Sample {
+            ly,
+            stat,
+            lcd_enabled: true,
+            ..Default::default()
+        }
+    }
+
+    fn events(timeline: &Timeline) -> Vec<(usize, Event)> {
+        timeline
+            .current_frame()
+            .events
+            .iter()
+            .map(|e| (e.cycle, e.event))
+            .collect()
+    }
+
+    #[test]
+    fn detect() {
+        let mut timeline = Timeline::default();
+        timeline.record(lcd_on(0, 2));
+        timeline.record(lcd_on(0, 3));
+        timeline.record(Sample {
+            requests: 0b10,
+            ..lcd_on(0, 0b110)
+        });
+        timeline.record(Sample {
+            requests: 0b110,
+            timer_overflow: true,
+            dispatched: Some(0x48),
+            oam_dma: true,
+            ..lcd_on(0, 0b110)
+        });
+
+        assert_eq!(timeline.current_frame().start_mode, 2);
+        assert_eq!(
+            events(&timeline),
+            vec![
+                (0, Event::Mode(3)),
+                (1, Event::Request(Interrupt::Stat)),
+                (1, Event::Mode(2)),
+                (1, Event::LycMatch),
+                (2, Event::Request(Interrupt::Stat)),
+                (2, Event::Request(Interrupt::Timer)),
+                (2, Event::Dispatch(Interrupt::Stat)),
+                (2, Event::TimerOverflow),
+                (2, Event::OamDma { active: true }),
+            ]
+        );
+    }
+
+    #[test]
+    fn frames() {
+        let mut timeline = Timeline::default();
+        timeline.record(lcd_on(153, 1));
+        timeline.record(lcd_on(153, 1));
+        assert!(timeline.last_frame().is_none());
+        timeline.record(lcd_on(0, 2));
+        let last = timeline.last_frame().unwrap();
+        assert_eq!((last.number, last.cycles), (0, 1));
+        assert_eq!(timeline.current_frame().number, 1);
+        assert_eq!(events(&timeline), vec![(0, Event::Mode(2))]);
+
+        for _ in 0..=CYCLES_PER_LINE * LINES_PER_FRAME {
+            timeline.record(Sample::default());
+        }
+        assert_eq!(timeline.current_frame().number, 2);
+    }
+
+    #[test]
+    fn double_speed() {
+        let mut timeline = Timeline::default();
+        let double = Sample {
+            double_speed: true,
+            ..Sample::default()
+        };
+        timeline.record(double);
+        assert_eq!(
+            timeline.current_frame().cycles_per_line(),
+            2 * CYCLES_PER_LINE
+        );
+        for _ in 0..=CYCLES_PER_LINE * LINES_PER_FRAME {
+            timeline.record(double);
+        }
+        assert_eq!(timeline.current_frame().number, 0);
+        for _ in 0..CYCLES_PER_LINE * LINES_PER_FRAME {
+            timeline.record(double);
+        }
+        assert_eq!(timeline.current_frame().number, 1);
+    }
+}
@@ -0,0 +1,398 @@
+use gb_roms::opcode::OpcodeGenerator;
+use std::{
+    collections::VecDeque,
+    fmt::{self, Display},
+    fs::File,
+    io::{self, BufWriter, Write},
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+/// Layout of a line of the trace
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceFormat {
+    /// Format of gameboy-doctor, used to compare the trace with reference emulators:
+    /// `A:01 F:B0 B:00 C:13 D:00 E:D8 H:01 L:4D SP:FFFE PC:0100 PCMEM:00,C3,13,02`
+    Doctor,
+    /// Registers with the state of the timer
+    Registers,
+    /// Decoded instruction followed by the registers
+    Disassembly,
+}
+
+impl TraceFormat {
+    pub const ALL: [TraceFormat; 3] = [
+        TraceFormat::Doctor,
+        TraceFormat::Registers,
+        TraceFormat::Disassembly,
+    ];
+}
+
+impl Display for TraceFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TraceFormat::Doctor => write!(f, "doctor"),
+            TraceFormat::Registers => write!(f, "registers"),
+            TraceFormat::Disassembly => write!(f, "disassembly"),
+        }
+    }
+}
+
+impl FromStr for TraceFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        TraceFormat::ALL
+            .into_iter()
+            .find(|format| format.to_string() == s)
+            .ok_or_else(|| format!("unknown trace format `{}`", s))
+    }
+}
+
+/// State of the cpu before the execution of an instruction
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TraceEntry {
+    pub af: u16,
+    pub bc: u16,
+    pub de: u16,
+    pub hl: u16,
+    pub sp: u16,
+    pub pc: u16,
+    /// ROM bank mapped at `4000-7FFF`
+    pub rom_bank: usize,
+    /// The 4 bytes starting at PC
+    pub pcmem: [u8; 4],
+    pub tima: u8,
+    pub tac: u8,
+    pub clock: u16,
+}
+
+impl TraceEntry {
+    fn write(&self, format: TraceFormat, w: &mut impl Write) -> io::Result<()> {
+        let [a, f] = self.af.to_be_bytes();
+        let [b, c] = self.bc.to_be_bytes();
+        let [d, e] = self.de.to_be_bytes();
+        let [h, l] = self.hl.to_be_bytes();
+        let bank = bank_of(self.pc, self.rom_bank).unwrap_or(0);
+
+        match format {
+            TraceFormat::Doctor => writeln!(
+                w,
+                "A:{:02X} F:{:02X} B:{:02X} C:{:02X} D:{:02X} E:{:02X} H:{:02X} L:{:02X} SP:{:04X} PC:{:04X} PCMEM:{:02X},{:02X},{:02X},{:02X}",
+                a, f, b, c, d, e, h, l, self.sp, self.pc,
+                self.pcmem[0], self.pcmem[1], self.pcmem[2], self.pcmem[3]
+            ),
+            TraceFormat::Registers => writeln!(
+                w,
+                "A: {:02X} F: {:02X} B: {:02X} C: {:02X} D: {:02X} E: {:02X} H: {:02X} L: {:02X} SP: {:04X} PC: {:02X}:{:04X} ({:02X} {:02X} {:02X} {:02X}) TIMA: {:02X} TAC: {:02X} CLK: {:04X}",
+                a, f, b, c, d, e, h, l, self.sp, bank, self.pc,
+                self.pcmem[0], self.pcmem[1], self.pcmem[2], self.pcmem[3],
+                self.tima, self.tac, self.clock
+            ),
+            TraceFormat::Disassembly => {
+                let instruction = match OpcodeGenerator::from(self.pcmem.into_iter()).next() {
+                    Some(Ok((opcode, _))) => opcode.to_string(),
+                    _ => format!("DB {:02X}", self.pcmem[0]),
+                };
+                writeln!(
+                    w,
+                    "{:02X}:{:04X}  {:<20} AF={:04X} BC={:04X} DE={:04X} HL={:04X} SP={:04X}",
+                    bank, self.pc, instruction, self.af, self.bc, self.de, self.hl, self.sp
+                )
+            }
+        }
+    }
+}
+
+/// Select the instructions written to the trace
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TraceFilter {
+    /// Only trace the instructions in this range
+    pub pc_range: Option<RangeInclusive<u16>>,
+    /// Only trace the instructions of this ROM bank
+    pub bank: Option<usize>,
+    /// Only trace once armed by a breakpoint
+    pub armed_only: bool,
+}
+
+impl TraceFilter {
+    fn accept(&self, pc: u16, rom_bank: usize, armed: bool) -> bool {
+        (armed || !self.armed_only)
+            && self
+                .pc_range
+                .as_ref()
+                .map_or(true, |range| range.contains(&pc))
+            && self
+                .bank
+                .map_or(true, |bank| bank_of(pc, rom_bank) == Some(bank))
+    }
+}
+
+/// Return the ROM bank where lie `pc`
+fn bank_of(pc: u16, rom_bank: usize) -> Option<usize> {
+    match pc {
+        0x0000..=0x3fff => Some(0),
+        0x4000..=0x7fff => Some(rom_bank),
+        _ => None,
+    }
+}
+
+#[derive(Debug)]
+enum Sink {
+    /// Nothing is written until the tracer is started
+    Closed,
+    File(BufWriter<File>),
+    /// Keep the last instructions in memory, they are written on dump
+    Ring(VecDeque<TraceEntry>),
+}
+
+/// Write the instructions executed by the cpu, either directly into a file
+/// or into a ring buffer that keep the last instructions until it is dumped.
+///
+/// The ring buffer is dumped when the tracer is dropped,
+/// which give the instructions that lead to a crash of the emulator.
+#[derive(Debug)]
+pub struct Tracer {
+    enabled: bool,
+    pub format: TraceFormat,
+    pub filter: TraceFilter,
+    path: PathBuf,
+    /// Size of the ring buffer, when `None` the instructions are written to the file directly
+    ring_size: Option<usize>,
+    armed: bool,
+    sink: Sink,
+    count: usize,
+}
+
+impl Default for Tracer {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            format: TraceFormat::Doctor,
+            filter: TraceFilter::default(),
+            path: default_path(),
+            ring_size: None,
+            armed: false,
+            sink: Sink::Closed,
+            count: 0,
+        }
+    }
+}
+
+/// Return `$LOG_DIR/trace.log`, or a file of the temporary directory when it is not set
+fn default_path() -> PathBuf {
+    let mut path = std::env::var("LOG_DIR").map_or_else(|_| std::env::temp_dir(), PathBuf::from);
+    path.push("trace.log");
+    path
+}
+
+impl Tracer {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn start(&mut self) {
+        log::info!("start tracing into {}", self.path.display());
+        self.enabled = true;
+    }
+
+    pub fn stop(&mut self) {
+        self.enabled = false;
+        if let Sink::File(file) = &mut self.sink {
+            if let Err(e) = file.flush() {
+                log::error!("cannot write trace to {}: {}", self.path.display(), e);
+            }
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Change the output file, the previous file is closed
+    pub fn set_path<P: Into<PathBuf>>(&mut self, path: P) {
+        self.stop();
+        self.path = path.into();
+        self.sink = Sink::Closed;
+    }
+
+    pub fn ring_size(&self) -> Option<usize> {
+        self.ring_size
+    }
+
+    /// Keep only the last `size` instructions in memory, or write them directly when `None`
+    pub fn set_ring_size(&mut self, size: Option<usize>) {
+        let size = size.filter(|&size| size != 0);
+        if self.ring_size != size {
+            self.stop();
+            self.ring_size = size;
+            self.sink = Sink::Closed;
+        }
+    }
+
+    pub fn is_armed(&self) -> bool {
+        self.armed
+    }
+
+    pub fn arm(&mut self) {
+        if !self.armed {
+            log::debug!("trace armed");
+        }
+        self.armed = true;
+    }
+
+    pub fn disarm(&mut self) {
+        self.armed = false;
+    }
+
+    /// Number of instructions traced since the output was opened
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Trace the instruction at `pc` when it pass the filter,
+    /// `entry` is only built for the traced instructions
+    pub fn trace(&mut self, pc: u16, rom_bank: usize, entry: impl FnOnce() -> TraceEntry) {
+        if !self.enabled || !self.filter.accept(pc, rom_bank, self.armed) {
+            return;
+        }
+        if let Err(e) = self.write(entry()) {
+            log::error!("cannot write trace to {}: {}", self.path.display(), e);
+            self.enabled = false;
+        }
+    }
+
+    fn write(&mut self, entry: TraceEntry) -> io::Result<()> {
+        if matches!(self.sink, Sink::Closed) {
+            self.sink = match self.ring_size {
+                Some(size) => Sink::Ring(VecDeque::with_capacity(size)),
+                None => Sink::File(BufWriter::new(File::create(&self.path)?)),
+            };
+            self.count = 0;
+        }
+        self.count += 1;
+        match &mut self.sink {
+            Sink::File(file) => entry.write(self.format, file),
+            Sink::Ring(ring) => {
+                if ring.len() == self.ring_size.unwrap_or_default() {
+                    ring.pop_front();
+                }
+                ring.push_back(entry);
+                Ok(())
+            }
+            Sink::Closed => Ok(()),
+        }
+    }
+
+    /// Write the content of the ring buffer into the output file, or flush the file
+    pub fn dump(&mut self) -> io::Result<()> {
+        match &mut self.sink {
+            Sink::Ring(ring) => {
+                let mut file = BufWriter::new(File::create(&self.path)?);
+                for entry in ring.iter() {
+                    entry.write(self.format, &mut file)?;
+                }
+                log::info!(
+                    "dumped the last {} instructions into {}",
+                    ring.len(),
+                    self.path.display()
+                );
+                file.flush()
+            }
+            Sink::File(file) => file.flush(),
+            Sink::Closed => Ok(()),
+        }
+    }
+}
+
+impl Drop for Tracer {
+    fn drop(&mut self) {
+        if let Err(e) = self.dump() {
+            log::error!("cannot write trace to {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_trace {
+    use super::{TraceEntry, TraceFilter, TraceFormat, Tracer};
+
+    fn entry(pc: u16) -> TraceEntry {
+        TraceEntry {
+            af: 0x01b0,
+            bc: 0x0013,
+            de: 0x00d8,
+            hl: 0x014d,
+            sp: 0xfffe,
+            pc,
+            rom_bank: 1,
+            pcmem: [0x00, 0xc3, 0x13, 0x02],
+            tima: 0,
+            tac: 0xf8,
+            clock: 0xabcc,
+        }
+    }
+
+    fn line(format: TraceFormat, entry: TraceEntry) -> String {
+        let mut buffer = Vec::new();
+        entry.write(format, &mut buffer).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn formats() {
+        assert_eq!(
+            line(TraceFormat::Doctor, entry(0x100)),
+            "A:01 F:B0 B:00 C:13 D:00 E:D8 H:01 L:4D SP:FFFE PC:0100 PCMEM:00,C3,13,02\n"
+        );
+        assert_eq!(
+            line(TraceFormat::Registers, entry(0x4100)),
+            "A: 01 F: B0 B: 00 C: 13 D: 00 E: D8 H: 01 L: 4D SP: FFFE PC: 01:4100 (00 C3 13 02) TIMA: 00 TAC: F8 CLK: ABCC\n"
+        );
+        assert!(line(TraceFormat::Disassembly, entry(0x100)).starts_with("00:0100  NOP "));
+        assert_eq!("doctor".parse(), Ok(TraceFormat::Doctor));
+        assert!("bgb".parse::<TraceFormat>().is_err());
+    }
+
+    #[test]
+    fn filter() {
+        let filter = TraceFilter {
+            pc_range: Some(0x4000..=0x4fff),
+            bank: Some(2),
+            armed_only: false,
+        };
+        assert!(filter.accept(0x4010, 2, false));
+        assert!(!filter.accept(0x4010, 1, false));
+        assert!(!filter.accept(0x0150, 2, false));
+
+        let filter = TraceFilter {
+            armed_only: true,
+            ..Default::default()
+        };
+        assert!(!filter.accept(0xc000, 1, false));
+        assert!(filter.accept(0xc000, 1, true));
+    }
+
+    #[test]
+    fn ring_buffer() {
+        let path = std::env::temp_dir().join("gbmu_test_trace_ring.log");
+        let mut tracer = Tracer::default();
+        tracer.set_path(&path);
+        tracer.set_ring_size(Some(2));
+        tracer.start();
+        for pc in 0x100..0x104 {
+            tracer.trace(pc, 1, || entry(pc));
+        }
+        assert_eq!(tracer.count(), 4);
+        drop(tracer);
+
+        let trace = std::fs::read_to_string(&path).unwrap();
+        let pcs: Vec<&str> = trace
+            .lines()
+            .filter_map(|line| line.split(' ').nth(9))
+            .collect();
+        assert_eq!(pcs, vec!["PC:0102", "PC:0103"]);
+        std::fs::remove_file(path).unwrap();
+    }
+}
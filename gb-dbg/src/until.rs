@@ -4,5 +4,17 @@ pub enum Until {
     Step(usize),
     Frame(usize),
     Second(usize),
+    /// Step over the `CALL` and `RST` instructions, behave like [Until::Step] otherwise
+    StepOver,
+    /// Run until the current function returns to its caller
+    StepOut,
+    /// Run until PC reach the address
+    RunTo(u16),
+    /// Run until the next interrupt is dispatched
+    Interrupt,
+    /// Run until the start of the next VBlank
+    VBlank,
+    /// Run until LY reach the line
+    Line(u8),
     Null,
 }
@@ -0,0 +1,95 @@
+use std::io::{self, Seek, SeekFrom, Write};
+
+const CHANNELS: u16 = 2;
+const BITS_PER_SAMPLE: u16 = 16;
+/// Bytes of a stereo frame
+const BLOCK_ALIGN: u16 = CHANNELS * BITS_PER_SAMPLE / 8;
+/// Size of the header preceding the samples
+const HEADER_SIZE: u32 = 44;
+
+/// Writer of a 16 bits stereo PCM WAV file.
+///
+/// The sizes of the header are only known once every frame is written,
+/// they are filled by [WavWriter::finish].
+pub struct WavWriter<W: Write + Seek> {
+    writer: W,
+    /// Number of stereo frames written
+    frames: u32,
+}
+
+impl<W: Write + Seek> WavWriter<W> {
+    pub fn new(mut writer: W, sample_rate: u32) -> io::Result<Self> {
+        writer.write_all(b"RIFF")?;
+        writer.write_all(&(HEADER_SIZE - 8).to_le_bytes())?;
+        writer.write_all(b"WAVE")?;
+
+        writer.write_all(b"fmt ")?;
+        writer.write_all(&16_u32.to_le_bytes())?;
+        // PCM
+        writer.write_all(&1_u16.to_le_bytes())?;
+        writer.write_all(&CHANNELS.to_le_bytes())?;
+        writer.write_all(&sample_rate.to_le_bytes())?;
+        writer.write_all(&(sample_rate * u32::from(BLOCK_ALIGN)).to_le_bytes())?;
+        writer.write_all(&BLOCK_ALIGN.to_le_bytes())?;
+        writer.write_all(&BITS_PER_SAMPLE.to_le_bytes())?;
+
+        writer.write_all(b"data")?;
+        writer.write_all(&0_u32.to_le_bytes())?;
+        Ok(Self { writer, frames: 0 })
+    }
+
+    /// Return the number of stereo frames written
+    pub fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    /// Write stereo frames, the left sample first, the samples are clamped between -1 and 1
+    pub fn write_frames(&mut self, frames: &[[f32; 2]]) -> io::Result<()> {
+        for sample in frames.iter().flatten() {
+            let sample = (sample.clamp(-1.0, 1.0) * f32::from(i16::MAX)) as i16;
+            self.writer.write_all(&sample.to_le_bytes())?;
+        }
+        self.frames += frames.len() as u32;
+        Ok(())
+    }
+
+    /// Fill the sizes of the header and return the inner writer
+    pub fn finish(mut self) -> io::Result<W> {
+        let data_size = self.frames * u32::from(BLOCK_ALIGN);
+        self.writer.seek(SeekFrom::Start(4))?;
+        self.writer
+            .write_all(&(HEADER_SIZE - 8 + data_size).to_le_bytes())?;
+        self.writer
+            .seek(SeekFrom::Start(u64::from(HEADER_SIZE) - 4))?;
+        self.writer.write_all(&data_size.to_le_bytes())?;
+        self.writer.seek(SeekFrom::End(0))?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+#[cfg(test)]
+mod test_wav {
+    use super::WavWriter;
+    use std::io::Cursor;
+
+    #[test]
+    fn header_and_samples() {
+        let mut wav = WavWriter::new(Cursor::new(Vec::new()), 44100).unwrap();
+        wav.write_frames(&[[0.0, 1.0], [-1.0, 2.0]]).unwrap();
+        assert_eq!(wav.frames(), 2);
+        let bytes = wav.finish().unwrap().into_inner();
+
+        assert_eq!(bytes.len(), 44 + 8);
+        assert_eq!(&bytes[..4], b"RIFF");
+        assert_eq!(&bytes[4..8], &44_u32.to_le_bytes());
+        assert_eq!(&bytes[8..16], b"WAVEfmt ");
+        assert_eq!(&bytes[24..28], &44100_u32.to_le_bytes());
+        assert_eq!(&bytes[36..40], b"data");
+        assert_eq!(&bytes[40..44], &8_u32.to_le_bytes());
+        assert_eq!(
+            &bytes[44..],
+            &[0x00, 0x00, 0xff, 0x7f, 0x01, 0x80, 0xff, 0x7f]
+        );
+    }
+}
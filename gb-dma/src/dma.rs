@@ -25,6 +25,11 @@ impl Dma {
     pub fn with_state(state: State, ppu_mem: PPUMem) -> Self {
         Self { state, ppu_mem }
     }
+
+    /// Indicate if an OAM DMA transfer is in progress
+    pub fn is_transferring(&self) -> bool {
+        self.state.oam_transfer.is_some()
+    }
 }
 
 impl<A> FileOperation<A, IORegArea> for Dma
@@ -8,7 +8,7 @@ use gb_clock::{Tick, Ticker};
 use std::iter::FromIterator;
 use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 /// Translate events from keyboard input inputs for the gameboy.
 pub struct Joypad {
     config: Rc<RefCell<Config>>,
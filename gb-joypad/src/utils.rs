@@ -1,5 +1,5 @@
 use crate::InputType;
-use gb_bus::Bus;
+use gb_bus::{Bus, Source};
 
 #[derive(Debug, Clone, Copy)]
 #[repr(u8)]
@@ -66,10 +66,7 @@ pub fn trigger_interrupt(addr_bus: &mut dyn Bus<u8>) {
     const INTERRUPT_FLAG: u16 = 0xFF0F;
     const INTERRUPT_BIT: u8 = 0b10000;
 
-    let interrupts_val = addr_bus
-        .read(INTERRUPT_FLAG, None)
-        .expect("Failed to read interrupt value for joypad interrupt");
-    if let Err(err) = addr_bus.write(INTERRUPT_FLAG, interrupts_val | INTERRUPT_BIT, None) {
+    if let Err(err) = addr_bus.write(INTERRUPT_FLAG, INTERRUPT_BIT, Some(Source::Interrupt)) {
         log::error!(
             "Failed to write interrupt value for joypad interrupt: {:?}",
             err
@@ -244,16 +244,14 @@ impl State {
         let update_stat = update_mode || update_lyc_eq;
 
         if update_vblank || update_stat {
-            let mut interrupts_val = adr_bus
-                .read(INTERRUPT_FLAG, Some(Lock::Ppu))
-                .expect("Failed to read interrupt value for ppu interrupt");
+            let mut interrupts_val = 0;
             if update_vblank {
                 interrupts_val |= INTERRUPT_VBLANK_BIT;
             }
             if update_stat {
                 interrupts_val |= INTERRUPT_STAT_BIT;
             }
-            if let Err(err) = adr_bus.write(INTERRUPT_FLAG, interrupts_val, Some(Lock::Ppu)) {
+            if let Err(err) = adr_bus.write(INTERRUPT_FLAG, interrupts_val, Some(Lock::Interrupt)) {
                 log::error!(
                     "Failed to write interrupt value for ppu interrupt: {:?}",
                     err
@@ -12,8 +12,8 @@ mod test_tools;
 use color::Color;
 pub use drawing::Mode;
 pub use memory::PPUMem;
-pub use ppu::{ImageRGB, Ppu};
-pub use registers::PPURegisters;
+pub use ppu::{ImageRGB, Ppu, PpuSnapshot};
+pub use registers::{Control, PPURegisters, Stat};
 use sprite::Sprite;
 
 pub const GB_SCREEN_WIDTH: usize = 160;
@@ -12,6 +12,7 @@ serde_big_array::big_array! { OamDataSize; Oam::SIZE }
     feature = "serialization",
     derive(serde::Deserialize, serde::Serialize)
 )]
+#[derive(Clone)]
 pub struct Oam {
     #[cfg_attr(feature = "serialization", serde(with = "OamDataSize"))]
     data: [u8; Oam::SIZE as usize],
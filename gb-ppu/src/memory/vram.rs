@@ -55,6 +55,7 @@ impl TryFrom<u8> for BankSelector {
     feature = "serialization",
     derive(serde::Deserialize, serde::Serialize)
 )]
+#[derive(Clone)]
 pub struct Vram {
     #[cfg_attr(feature = "serialization", serde(with = "de_ser::data"))]
     data: Vec<[u8; Vram::SIZE as usize]>,
@@ -71,6 +72,16 @@ impl Vram {
         }
     }
 
+    /// Return the number of banks, 2 in CGB mode
+    pub fn bank_count(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Read a byte of the bank `bank`, `None` if the bank doesn't exist
+    pub fn read_bank(&self, bank: usize, addr: usize) -> Option<u8> {
+        self.data.get(bank)?.get(addr).copied()
+    }
+
     pub fn read(&self, addr: usize, bank: Option<BankSelector>) -> PPUResult<u8> {
         let bank_index: usize = bank.unwrap_or_default().into();
         if addr < Self::SIZE {
@@ -100,6 +100,22 @@ impl Ppu {
         PPURegisters::new(Rc::clone(&self.lcd_reg))
     }
 
+    /// Return the number of VRAM banks
+    pub fn vram_bank_count(&self) -> usize {
+        self.vram.borrow().bank_count()
+    }
+
+    /// Read a byte of a VRAM bank regardless of the locks and of VBK,
+    /// `addr` is relative to the start of the VRAM
+    pub fn read_vram_bank(&self, bank: usize, addr: usize) -> Option<u8> {
+        self.vram.borrow().read_bank(bank, addr)
+    }
+
+    /// Read a byte of the OAM regardless of the locks
+    pub fn read_oam(&self, addr: usize) -> Option<u8> {
+        self.oam.borrow().read(addr).ok()
+    }
+
     pub fn pixels(&self) -> &ImageRGB<GB_SCREEN_WIDTH, GB_SCREEN_HEIGHT> {
         &self.pixels
     }
@@ -610,6 +626,54 @@ impl Ppu {
     }
 }
 
+/// Copy of the state of a [Ppu] and of the memory it owns, used to rewind the emulation
+#[derive(Clone)]
+pub struct PpuSnapshot {
+    ppu: Ppu,
+    vram: Vram,
+    oam: Oam,
+    lcd_reg: LcdReg,
+    vbk: u8,
+    opri: u8,
+}
+
+impl Ppu {
+    pub fn snapshot(&self) -> PpuSnapshot {
+        let lcd_reg = self.lcd_reg.borrow();
+        PpuSnapshot {
+            ppu: self.clone(),
+            vram: self.vram.borrow().clone(),
+            oam: self.oam.borrow().clone(),
+            lcd_reg: lcd_reg.clone(),
+            vbk: lcd_reg.vbk.get(),
+            opri: lcd_reg.opri.get(),
+        }
+    }
+
+    /// Restore a snapshot of this ppu, the memory shared with the other components is updated in place
+    pub fn restore(&mut self, snapshot: &PpuSnapshot) {
+        let (vram, oam, lcd_reg) = (
+            Rc::clone(&self.vram),
+            Rc::clone(&self.oam),
+            Rc::clone(&self.lcd_reg),
+        );
+        *self = snapshot.ppu.clone();
+        self.vram = vram;
+        self.oam = oam;
+        self.lcd_reg = lcd_reg;
+
+        *self.vram.borrow_mut() = snapshot.vram.clone();
+        *self.oam.borrow_mut() = snapshot.oam.clone();
+        let mut lcd_reg = self.lcd_reg.borrow_mut();
+        let (vbk, opri) = (Rc::clone(&lcd_reg.vbk), Rc::clone(&lcd_reg.opri));
+        *lcd_reg = snapshot.lcd_reg.clone();
+        lcd_reg.vbk = vbk;
+        lcd_reg.opri = opri;
+        lcd_reg.vbk.set(snapshot.vbk);
+        lcd_reg.opri.set(snapshot.opri);
+    }
+}
+
 impl Default for Ppu {
     fn default() -> Ppu {
         Ppu::new(false)
@@ -652,3 +716,31 @@ impl Ticker for Ppu {
         self.state.update(lcd_reg, adr_bus);
     }
 }
+
+#[cfg(test)]
+mod test_snapshot {
+    use super::Ppu;
+    use std::rc::Rc;
+
+    #[test]
+    fn restore() {
+        let mut ppu = Ppu::new(true);
+        let vram = Rc::clone(&ppu.vram);
+        ppu.vram.borrow_mut().write(0x10, 0x42, None).unwrap();
+        ppu.lcd_reg.borrow_mut().vbk.set(0xff);
+        let snapshot = ppu.snapshot();
+
+        ppu.vram.borrow_mut().write(0x10, 0x24, None).unwrap();
+        ppu.oam.borrow_mut().write(0, 0x11).unwrap();
+        ppu.lcd_reg.borrow_mut().scrolling.scx = 8;
+        ppu.lcd_reg.borrow_mut().vbk.set(0xfe);
+        ppu.restore(&snapshot);
+
+        assert_eq!(ppu.vram.borrow().read(0x10, None).unwrap(), 0x42);
+        assert_eq!(ppu.oam.borrow().read(0).unwrap(), 0);
+        assert_eq!(ppu.lcd_reg.borrow().scrolling.scx, 0);
+        assert_eq!(ppu.lcd_reg.borrow().vbk.get(), 0xff);
+        // the memory shared with the bus is still the one of the ppu
+        assert!(Rc::ptr_eq(&vram, &ppu.vram));
+    }
+}
@@ -2,7 +2,7 @@ mod lcd;
 mod palette;
 mod ppu_registers;
 
-pub use lcd::{LcdReg, PaletteRef, PalettesMono};
+pub use lcd::{Control, LcdReg, PaletteRef, PalettesMono, Stat};
 pub use palette::Palette;
 pub use ppu_registers::PPURegisters;
 
@@ -30,7 +30,7 @@ use gb_bus::io_reg_area::IORegArea::{
     feature = "serialization",
     derive(serde::Deserialize, serde::Serialize)
 )]
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct LcdReg {
     pub control: Control,
     pub stat: Stat,
@@ -46,6 +46,16 @@ impl PalettesCGB {
     const SPEC_AUTO_INCR: u8 = 0b1000_0000;
     const SPEC_DATA_INDEX: u8 = !Self::SPEC_AUTO_INCR;
 
+    /// Return the 64 bytes of the background palettes
+    pub fn background_data(&self) -> &[u8] {
+        &self.bc_values
+    }
+
+    /// Return the 64 bytes of the object palettes
+    pub fn object_data(&self) -> &[u8] {
+        &self.oc_values
+    }
+
     pub fn lock(&mut self) {
         self.locked = true;
     }
@@ -0,0 +1,219 @@
+use std::{error::Error, fmt::Display, str::FromStr};
+
+/// Game Genie code, patch the value read from the ROM at `address`.
+///
+/// When a compare byte is present the patch is only applied if the ROM
+/// contains this byte, which keep the patch to a single bank.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GameGenie {
+    pub address: u16,
+    pub value: u8,
+    pub compare: Option<u8>,
+}
+
+impl GameGenie {
+    /// Return the patched value of a ROM read, `original` being the byte of the ROM
+    pub fn patch(&self, address: u16, original: u8) -> Option<u8> {
+        if address == self.address && self.compare.map_or(true, |compare| compare == original) {
+            Some(self.value)
+        } else {
+            None
+        }
+    }
+}
+
+impl FromStr for GameGenie {
+    type Err = CodeError;
+
+    /// Decode a code of the form `ABC-DEF` or `ABC-DEF-GHI`:
+    /// - `AB` is the new value
+    /// - `FCDE` is the address, `F` being xored with `F`
+    /// - `GI` is the compare byte, rotated right by 2 and xored with `BA`, `H` is unused
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let digits = hex_digits(&s.replace('-', ""))?;
+        if digits.len() != 6 && digits.len() != 9 {
+            return Err(CodeError::Length(digits.len()));
+        }
+
+        let value = digits[0] << 4 | digits[1];
+        let address = u16::from(digits[5] ^ 0xf) << 12
+            | u16::from(digits[2]) << 8
+            | u16::from(digits[3]) << 4
+            | u16::from(digits[4]);
+        if address >= 0x8000 {
+            return Err(CodeError::Address(address));
+        }
+        let compare =
+            (digits.len() == 9).then(|| (digits[6] << 4 | digits[8]).rotate_right(2) ^ 0xba);
+        Ok(Self {
+            address,
+            value,
+            compare,
+        })
+    }
+}
+
+/// GameShark code, write `value` at `address` each frame.
+///
+/// The bank select which memory is written:
+/// - `8x` write to the bank `x` of the cartridge RAM
+/// - `9x` write to the bank `x` of the working RAM
+/// - any other value write to the memory currently mapped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GameShark {
+    pub bank: u8,
+    pub value: u8,
+    pub address: u16,
+}
+
+/// Memory targeted by a [GameShark] code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    /// Bank and offset of the cartridge RAM
+    CartRam(usize, u16),
+    /// Bank and offset of the working RAM
+    WorkingRam(usize, u16),
+    /// Address of the mapped memory
+    Mapped(u16),
+}
+
+impl GameShark {
+    pub fn target(&self) -> Target {
+        match (self.bank, self.address) {
+            (0x80..=0x8f, 0xa000..=0xbfff) => {
+                Target::CartRam((self.bank & 0xf) as usize, self.address - 0xa000)
+            }
+            (0x90..=0x97, 0xd000..=0xdfff) => {
+                // like SVBK, the bank 0 select the bank 1
+                Target::WorkingRam((self.bank & 0x7).max(1) as usize, self.address - 0xd000)
+            }
+            _ => Target::Mapped(self.address),
+        }
+    }
+}
+
+impl FromStr for GameShark {
+    type Err = CodeError;
+
+    /// Decode a code of the form `BBVVLLHH`, `BB` is the bank, `VV` the value
+    /// and `HHLL` the address
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let digits = hex_digits(s)?;
+        if digits.len() != 8 {
+            return Err(CodeError::Length(digits.len()));
+        }
+
+        let byte = |i: usize| digits[i] << 4 | digits[i + 1];
+        Ok(Self {
+            bank: byte(0),
+            value: byte(2),
+            address: u16::from_le_bytes([byte(4), byte(6)]),
+        })
+    }
+}
+
+/// Cheat code of one of the supported devices
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Code {
+    GameGenie(GameGenie),
+    GameShark(GameShark),
+}
+
+impl FromStr for Code {
+    type Err = CodeError;
+
+    /// Game Genie codes are separated by dashes, GameShark codes are not
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.contains('-') {
+            s.parse().map(Code::GameGenie)
+        } else {
+            s.parse().map(Code::GameShark)
+        }
+    }
+}
+
+fn hex_digits(s: &str) -> Result<Vec<u8>, CodeError> {
+    s.chars()
+        .map(|c| {
+            c.to_digit(16)
+                .map(|digit| digit as u8)
+                .ok_or(CodeError::Digit(c))
+        })
+        .collect()
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum CodeError {
+    Digit(char),
+    Length(usize),
+    Address(u16),
+}
+
+impl Error for CodeError {}
+
+impl Display for CodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CodeError::Digit(c) => write!(f, "invalid hexadecimal digit {:?}", c),
+            CodeError::Length(len) => write!(f, "invalid code length of {} digits", len),
+            CodeError::Address(address) => {
+                write!(f, "address {:04X} is outside of the ROM", address)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_cheats {
+    use super::{Code, CodeError, GameGenie, GameShark, Target};
+
+    #[test]
+    fn game_genie() {
+        let code: GameGenie = "00A-17B-C49".parse().unwrap();
+        assert_eq!(code.value, 0x00);
+        assert_eq!(code.address, 0x4a17);
+        // C9 rotated right by 2 is 72
+        assert_eq!(code.compare, Some(0x72 ^ 0xba));
+        assert_eq!(code.patch(0x4a17, 0xc8), Some(0x00));
+        assert_eq!(code.patch(0x4a17, 0x00), None);
+        assert_eq!(code.patch(0x4a18, 0xc8), None);
+
+        let code: GameGenie = "3EF-0AF".parse().unwrap();
+        assert_eq!(code.address, 0x0f0a);
+        assert_eq!(code.compare, None);
+        assert_eq!(code.patch(0x0f0a, 0x12), Some(0x3e));
+
+        assert_eq!(
+            "3EF-0A7".parse::<GameGenie>(),
+            Err(CodeError::Address(0x8f0a))
+        );
+        assert_eq!("3EF-0A".parse::<GameGenie>(), Err(CodeError::Length(5)));
+        assert_eq!("3EF-0AZ".parse::<GameGenie>(), Err(CodeError::Digit('Z')));
+    }
+
+    #[test]
+    fn game_shark() {
+        let code: GameShark = "01FF34C2".parse().unwrap();
+        assert_eq!(
+            code,
+            GameShark {
+                bank: 0x01,
+                value: 0xff,
+                address: 0xc234
+            }
+        );
+        assert_eq!(code.target(), Target::Mapped(0xc234));
+        assert_eq!(
+            "83630AA0".parse::<GameShark>().unwrap().target(),
+            Target::CartRam(3, 0x000a)
+        );
+        assert_eq!(
+            "90630AD1".parse::<GameShark>().unwrap().target(),
+            Target::WorkingRam(1, 0x010a)
+        );
+
+        assert!(matches!("01FF34C2".parse(), Ok(Code::GameShark(_))));
+        assert!(matches!(" 3EF-0AF ".parse(), Ok(Code::GameGenie(_))));
+    }
+}
@@ -0,0 +1,130 @@
+use std::cell::{Cell, Ref, RefCell};
+
+/// Usage of each byte of the ROM recorded while the game runs, one byte of flags per ROM byte.
+///
+/// The flags are internal to the emulator, they are described by the constants.
+/// The log is exported in the CDL format of FCEUX with [CodeDataLog::to_cdl].
+#[derive(Debug, Clone)]
+pub struct CodeDataLog {
+    flags: Vec<Cell<u8>>,
+    recording: bool,
+    /// Offsets of the bytes each time they gained a flag, in order,
+    /// used to apply only the changes of the log
+    changed: RefCell<Vec<usize>>,
+}
+
+impl CodeDataLog {
+    /// Byte executed, either as opcode or as operand
+    pub const CODE: u8 = 0x01;
+    /// Byte read by the cpu outside of the instruction fetches
+    pub const DATA: u8 = 0x02;
+    /// First byte of an executed instruction
+    pub const OPCODE: u8 = 0x04;
+    /// Byte following the first byte of an executed instruction
+    pub const OPERAND: u8 = 0x08;
+    /// Byte used as source by the OAM DMA or the HDMA
+    pub const DMA: u8 = 0x40;
+
+    pub fn new(rom_size: usize) -> Self {
+        Self {
+            flags: vec![Cell::new(0); rom_size],
+            recording: true,
+            changed: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording
+    }
+
+    pub fn set_recording(&mut self, recording: bool) {
+        self.recording = recording;
+    }
+
+    /// Return the size of the logged ROM
+    pub fn rom_size(&self) -> usize {
+        self.flags.len()
+    }
+
+    /// Return the flags of the byte at `offset` of the ROM
+    pub fn flags(&self, offset: usize) -> u8 {
+        self.flags.get(offset).map_or(0, Cell::get)
+    }
+
+    /// Return the number of times a byte gained a flag
+    pub fn changes(&self) -> usize {
+        self.changed.borrow().len()
+    }
+
+    /// Return the offsets of the bytes that gained a flag after the first `since` changes
+    pub fn changed_since(&self, since: usize) -> Ref<'_, [usize]> {
+        Ref::map(self.changed.borrow(), |changed| {
+            &changed[since.min(changed.len())..]
+        })
+    }
+
+    /// Add `flags` to the byte at `offset` of the ROM
+    pub fn mark(&self, offset: usize, flags: u8) {
+        if !self.recording {
+            return;
+        }
+        if let Some(cell) = self.flags.get(offset) {
+            let previous = cell.get();
+            if previous | flags != previous {
+                cell.set(previous | flags);
+                self.changed.borrow_mut().push(offset);
+            }
+        }
+    }
+
+    /// Return the number of bytes having one of `flags`
+    pub fn count(&self, flags: u8) -> usize {
+        self.flags
+            .iter()
+            .filter(|cell| cell.get() & flags != 0)
+            .count()
+    }
+
+    /// Return the flags of the whole ROM in the CDL format of FCEUX:
+    /// the bit 0 marks the code and the bit 1 the data, the DMA sources included.
+    ///
+    /// The other bits are left clear, FCEUX uses them for the PRG bank
+    /// and the sound samples of the NES
+    pub fn to_cdl(&self) -> Vec<u8> {
+        self.flags
+            .iter()
+            .map(|cell| {
+                let flags = cell.get();
+                let mut cdl = 0;
+                if flags & Self::CODE != 0 {
+                    cdl |= 0x01;
+                }
+                if flags & (Self::DATA | Self::DMA) != 0 {
+                    cdl |= 0x02;
+                }
+                cdl
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test_code_data_log {
+    use super::CodeDataLog;
+
+    #[test]
+    fn mark() {
+        let mut log = CodeDataLog::new(4);
+        log.mark(0, CodeDataLog::CODE | CodeDataLog::OPCODE);
+        log.mark(1, CodeDataLog::DATA);
+        log.mark(1, CodeDataLog::DATA);
+        log.mark(8, CodeDataLog::DATA);
+        assert_eq!(log.changes(), 2);
+        assert_eq!(&*log.changed_since(1), &[1]);
+        assert_eq!(log.count(CodeDataLog::CODE | CodeDataLog::DATA), 2);
+
+        log.set_recording(false);
+        log.mark(2, CodeDataLog::DMA);
+        assert_eq!(log.to_cdl(), vec![0x01, 0x02, 0x00, 0x00]);
+    }
+}
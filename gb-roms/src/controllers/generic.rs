@@ -1,16 +1,23 @@
 use gb_bus::{Address, Area, Error, FileOperation, Source};
 use serde::{Deserialize, Serialize};
+use std::cell::Cell;
 use std::io::{self, Read};
 
-use crate::Header;
+use crate::{cheats::GameGenie, code_data_log::CodeDataLog, Header};
 
 use super::save::StateError;
-use super::{new_controller_from_header, Controller, Full, Partial};
+use super::{new_controller_from_header, Controller, Full, Partial, RAM_BANK_SIZE, ROM_BANK_SIZE};
 
 pub struct Generic {
     controller: Box<dyn Controller>,
     rom: Vec<u8>,
     ram: Option<Vec<u8>>,
+    /// Game Genie codes applied to the ROM reads
+    patches: Vec<GameGenie>,
+    /// Usage of the ROM bytes, recorded once the logging is started
+    log: Option<CodeDataLog>,
+    /// Address and length of the instruction about to be fetched by the cpu
+    instruction: Cell<Option<(u16, u16)>>,
 }
 
 macro_rules! ram_op {
@@ -39,6 +46,9 @@ impl Generic {
             rom: ctl.create_rom(),
             ram: ctl.create_ram(),
             controller: ctl,
+            patches: Vec::new(),
+            log: None,
+            instruction: Cell::new(None),
         }
     }
 
@@ -74,9 +84,127 @@ impl Generic {
         self.controller.load_partial(state.controller)
     }
 
-    fn read_rom(&self, addr: u16) -> Result<u8, Error> {
-        let addr = self.controller.offset_rom_addr(addr);
-        Ok(self.rom[addr])
+    /// Return the ROM bank currently mapped at `4000-7FFF`
+    pub fn rom_bank(&self) -> usize {
+        self.controller.offset_rom_addr(0x4000) / ROM_BANK_SIZE
+    }
+
+    /// Return the external RAM bank currently mapped at `A000-BFFF`
+    pub fn ram_bank(&self) -> usize {
+        self.ram
+            .as_ref()
+            .map_or(0, |_| self.controller.offset_ram_addr(0) / RAM_BANK_SIZE)
+    }
+
+    /// Return the number of ROM banks of the cartridge
+    pub fn rom_bank_count(&self) -> usize {
+        (self.rom.len() / ROM_BANK_SIZE).max(1)
+    }
+
+    /// Read a byte of the ROM bank `bank` without going through the MBC,
+    /// `offset` is relative to the start of the bank
+    pub fn read_rom_bank(&self, bank: usize, offset: u16) -> Option<u8> {
+        self.rom
+            .get(bank * ROM_BANK_SIZE + (offset as usize & (ROM_BANK_SIZE - 1)))
+            .copied()
+    }
+
+    /// Return the number of external RAM banks of the cartridge
+    pub fn ram_bank_count(&self) -> usize {
+        self.ram
+            .as_ref()
+            .map_or(0, |ram| (ram.len() / RAM_BANK_SIZE).max(1))
+    }
+
+    /// Read a byte of the RAM bank `bank` without going through the MBC,
+    /// `offset` is relative to the start of the bank
+    pub fn read_ram_bank(&self, bank: usize, offset: u16) -> Option<u8> {
+        self.ram
+            .as_ref()?
+            .get(bank * RAM_BANK_SIZE + (offset as usize & (RAM_BANK_SIZE - 1)))
+            .copied()
+    }
+
+    /// Write a byte of the RAM bank `bank` without going through the MBC,
+    /// `offset` is relative to the start of the bank.
+    ///
+    /// Return `false` when the cartridge doesn't have this bank
+    pub fn write_ram_bank(&mut self, bank: usize, offset: u16, v: u8) -> bool {
+        let index = bank * RAM_BANK_SIZE + (offset as usize & (RAM_BANK_SIZE - 1));
+        match self.ram.as_mut().and_then(|ram| ram.get_mut(index)) {
+            Some(byte) => {
+                *byte = v;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Replace the Game Genie codes patching the ROM
+    pub fn set_patches(&mut self, patches: Vec<GameGenie>) {
+        self.patches = patches;
+    }
+
+    /// Start recording the usage of the ROM bytes, keeping what was already logged
+    pub fn start_logging(&mut self) {
+        let size = self.rom.len();
+        self.log
+            .get_or_insert_with(|| CodeDataLog::new(size))
+            .set_recording(true);
+    }
+
+    pub fn stop_logging(&mut self) {
+        if let Some(log) = self.log.as_mut() {
+            log.set_recording(false);
+        }
+    }
+
+    /// Forget the usage of the ROM bytes logged so far
+    pub fn clear_log(&mut self) {
+        if let Some(log) = self.log.as_mut() {
+            let recording = log.is_recording();
+            *log = CodeDataLog::new(self.rom.len());
+            log.set_recording(recording);
+        }
+    }
+
+    pub fn code_data_log(&self) -> Option<&CodeDataLog> {
+        self.log.as_ref()
+    }
+
+    /// Announce the address and length of the instruction about to be fetched by the cpu,
+    /// its bytes are logged as code instead of data.
+    ///
+    /// The fetches from the boot ROM don't reach the cartridge and aren't logged
+    pub fn fetch_instruction(&self, instruction: Option<(u16, u16)>) {
+        self.instruction.set(instruction);
+    }
+
+    fn cpu_read_flags(&self, addr: u16) -> u8 {
+        match self.instruction.get() {
+            Some((start, _)) if addr == start => CodeDataLog::CODE | CodeDataLog::OPCODE,
+            Some((start, len)) if addr.wrapping_sub(start) < len => {
+                CodeDataLog::CODE | CodeDataLog::OPERAND
+            }
+            _ => CodeDataLog::DATA,
+        }
+    }
+
+    fn read_rom(&self, addr: u16, source: Option<Source>) -> Result<u8, Error> {
+        let offset = self.controller.offset_rom_addr(addr);
+        if let Some(log) = self.log.as_ref() {
+            match source {
+                None | Some(Source::Cpu) => log.mark(offset, self.cpu_read_flags(addr)),
+                Some(Source::Dma) => log.mark(offset, CodeDataLog::DMA),
+                Some(Source::Ppu | Source::Interrupt | Source::Debugger) => {}
+            }
+        }
+        let original = self.rom[offset];
+        Ok(self
+            .patches
+            .iter()
+            .find_map(|patch| patch.patch(addr, original))
+            .unwrap_or(original))
     }
 
     fn write_rom(&mut self, v: u8, addr: u16) -> Result<(), Error> {
@@ -126,9 +254,9 @@ where
     u16: From<A>,
     A: Address<Area>,
 {
-    fn read(&self, addr: A, _source: Option<Source>) -> Result<u8, Error> {
+    fn read(&self, addr: A, source: Option<Source>) -> Result<u8, Error> {
         match addr.area_type() {
-            Area::Rom => self.read_rom(u16::from(addr)),
+            Area::Rom => self.read_rom(u16::from(addr), source),
             Area::ExtRam => self.read_ram(u16::from(addr)),
             _ => Err(Error::bus_error(u16::from(addr))),
         }
@@ -143,7 +271,7 @@ where
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GenericState<CTL> {
     pub controller: CTL,
     pub ram: Option<Vec<u8>>,
@@ -0,0 +1,267 @@
+use gb_bus::{Address, Area, Error, FileOperation, Source};
+use std::fmt::Display;
+
+/// Size of the header preceding the code of a GBS file
+pub const HEADER_SIZE: usize = 0x70;
+/// Address of the loop where the cpu waits between two calls to the routines of the file
+pub const IDLE_ADDRESS: u16 = 0x0100;
+const BANK_SIZE: usize = 0x4000;
+/// Lowest address where the code of a file can be loaded, below are the vectors
+const MIN_LOAD_ADDRESS: u16 = 0x0400;
+
+/// Header of a GBS (Game Boy Sound) file, the music driver of a game with its data.
+///
+/// The `init` routine is called with the track number in `A`, then the `play`
+/// routine is called at each VBlank or at each timer interrupt when the timer is used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GbsHeader {
+    pub version: u8,
+    pub song_count: u8,
+    /// First song to play, from 1
+    pub first_song: u8,
+    pub load_address: u16,
+    pub init_address: u16,
+    pub play_address: u16,
+    pub stack_pointer: u16,
+    pub timer_modulo: u8,
+    pub timer_control: u8,
+    pub title: String,
+    pub author: String,
+    pub copyright: String,
+}
+
+impl GbsHeader {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, GbsError> {
+        if bytes.len() < HEADER_SIZE {
+            return Err(GbsError::Truncated(bytes.len()));
+        }
+        if &bytes[..3] != b"GBS" {
+            return Err(GbsError::Signature);
+        }
+        let word = |offset: usize| u16::from_le_bytes([bytes[offset], bytes[offset + 1]]);
+        let text = |offset: usize| {
+            let field = &bytes[offset..offset + 0x20];
+            let end = field.iter().position(|&c| c == 0).unwrap_or(field.len());
+            String::from_utf8_lossy(&field[..end]).trim().to_owned()
+        };
+        let header = Self {
+            version: bytes[3],
+            song_count: bytes[4],
+            first_song: bytes[5],
+            load_address: word(0x06),
+            init_address: word(0x08),
+            play_address: word(0x0a),
+            stack_pointer: word(0x0c),
+            timer_modulo: bytes[0x0e],
+            timer_control: bytes[0x0f],
+            title: text(0x10),
+            author: text(0x30),
+            copyright: text(0x50),
+        };
+        if header.version != 1 {
+            Err(GbsError::Version(header.version))
+        } else if header.load_address < MIN_LOAD_ADDRESS || header.load_address >= 0x8000 {
+            Err(GbsError::LoadAddress(header.load_address))
+        } else {
+            Ok(header)
+        }
+    }
+
+    /// Return true when the play routine is called by the timer interrupt instead of the VBlank
+    pub fn timer_driven(&self) -> bool {
+        self.timer_control & 0b100 != 0
+    }
+
+    /// Return true when the cpu runs in double speed, only used by the timer driven files
+    pub fn double_speed(&self) -> bool {
+        self.timer_driven() && self.timer_control & 0x80 != 0
+    }
+
+    /// Return the first track to play, from 0
+    pub fn first_track(&self) -> u8 {
+        self.first_song
+            .saturating_sub(1)
+            .min(self.song_count.saturating_sub(1))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum GbsError {
+    Truncated(usize),
+    Signature,
+    Version(u8),
+    LoadAddress(u16),
+}
+
+impl std::error::Error for GbsError {}
+
+impl Display for GbsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GbsError::Truncated(len) => write!(f, "file too short for a GBS header: {} bytes", len),
+            GbsError::Signature => write!(f, "missing GBS signature"),
+            GbsError::Version(version) => write!(f, "unsupported GBS version {}", version),
+            GbsError::LoadAddress(address) => {
+                write!(f, "invalid load address {:04X}", address)
+            }
+        }
+    }
+}
+
+/// ROM of a GBS file: its code placed at the load address with a ROM only controller
+/// that can switch the bank mapped at `4000-7FFF` by writing to `2000-3FFF`.
+///
+/// The vectors below the load address are filled to run the file outside of a game:
+/// - the `RST` jump to the same offset from the load address
+/// - the interrupts return immediately, the player calls the play routine itself
+/// - [IDLE_ADDRESS] loops forever, it's the return address of the routines
+pub struct GbsRom {
+    rom: Vec<u8>,
+    bank: usize,
+}
+
+impl GbsRom {
+    /// Build the ROM from the content of a GBS file, header included
+    pub fn new(header: &GbsHeader, file: &[u8]) -> Self {
+        let load_address = usize::from(header.load_address);
+        let code = &file[HEADER_SIZE.min(file.len())..];
+        let size = (load_address + code.len()).max(2 * BANK_SIZE);
+        let mut rom = vec![0; (size + BANK_SIZE - 1) / BANK_SIZE * BANK_SIZE];
+        rom[load_address..load_address + code.len()].copy_from_slice(code);
+
+        for vector in (0x00..=0x38).step_by(8) {
+            let [low, high] = header.load_address.wrapping_add(vector).to_le_bytes();
+            // JP load_address + vector
+            rom[usize::from(vector)..usize::from(vector) + 3].copy_from_slice(&[0xc3, low, high]);
+        }
+        for interrupt in (0x40..=0x60).step_by(8) {
+            // RETI
+            rom[interrupt] = 0xd9;
+        }
+        let idle = usize::from(IDLE_ADDRESS);
+        // JR -2
+        rom[idle..idle + 2].copy_from_slice(&[0x18, 0xfe]);
+        Self { rom, bank: 1 }
+    }
+
+    fn bank_count(&self) -> usize {
+        self.rom.len() / BANK_SIZE
+    }
+}
+
+impl<A> FileOperation<A, Area> for GbsRom
+where
+    u16: From<A>,
+    A: Address<Area>,
+{
+    fn read(&self, addr: A, _source: Option<Source>) -> Result<u8, Error> {
+        match addr.area_type() {
+            Area::Rom => {
+                let address = usize::from(u16::from(addr));
+                let offset = if address < BANK_SIZE {
+                    address
+                } else {
+                    self.bank * BANK_SIZE + address - BANK_SIZE
+                };
+                Ok(self.rom[offset])
+            }
+            _ => Err(Error::bus_error(addr.into())),
+        }
+    }
+
+    fn write(&mut self, v: u8, addr: A, _source: Option<Source>) -> Result<(), Error> {
+        match addr.area_type() {
+            Area::Rom => {
+                if (0x2000..0x4000).contains(&u16::from(addr)) {
+                    self.bank = (usize::from(v) % self.bank_count()).max(1);
+                }
+                Ok(())
+            }
+            _ => Err(Error::bus_error(addr.into())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_gbs {
+    use super::{GbsError, GbsHeader, GbsRom, HEADER_SIZE};
+    use gb_bus::{Addr, Area, FileOperation};
+
+    fn file(load_address: u16, code: &[u8]) -> Vec<u8> {
+        let mut file = vec![0; HEADER_SIZE];
+        file[..4].copy_from_slice(b"GBS\x01");
+        file[4] = 12;
+        file[5] = 3;
+        file[6..8].copy_from_slice(&load_address.to_le_bytes());
+        file[8..10].copy_from_slice(&0x0480_u16.to_le_bytes());
+        file[10..12].copy_from_slice(&0x0490_u16.to_le_bytes());
+        file[12..14].copy_from_slice(&0xdfff_u16.to_le_bytes());
+        file[0x0e] = 0xc0;
+        file[0x0f] = 0x84;
+        file[0x10..0x15].copy_from_slice(b"Title");
+        file[0x30..0x36].copy_from_slice(b"Author");
+        file.extend_from_slice(code);
+        file
+    }
+
+    fn read(rom: &GbsRom, address: u16) -> u8 {
+        rom.read(Addr::from_offset(Area::Rom, address, 0), None)
+            .unwrap()
+    }
+
+    #[test]
+    fn header() {
+        let header = GbsHeader::from_bytes(&file(0x0400, &[])).unwrap();
+        assert_eq!(header.song_count, 12);
+        assert_eq!(header.first_track(), 2);
+        assert_eq!(header.init_address, 0x0480);
+        assert_eq!(header.play_address, 0x0490);
+        assert_eq!(header.stack_pointer, 0xdfff);
+        assert_eq!(header.title, "Title");
+        assert_eq!(header.author, "Author");
+        assert_eq!(header.copyright, "");
+        assert!(header.timer_driven());
+        assert!(header.double_speed());
+
+        assert_eq!(
+            GbsHeader::from_bytes(&file(0x0200, &[])),
+            Err(GbsError::LoadAddress(0x0200))
+        );
+        assert_eq!(GbsHeader::from_bytes(b"GBS"), Err(GbsError::Truncated(3)));
+        let mut wrong = file(0x0400, &[]);
+        wrong[0] = b'N';
+        assert_eq!(GbsHeader::from_bytes(&wrong), Err(GbsError::Signature));
+    }
+
+    #[test]
+    fn mapping() {
+        let mut code = vec![0x11; 0x8000];
+        code[0x4000 - 0x0400] = 0x22;
+        code.push(0x33);
+        let file = file(0x0400, &code);
+        let header = GbsHeader::from_bytes(&file).unwrap();
+        let mut rom = GbsRom::new(&header, &file);
+
+        assert_eq!(rom.bank_count(), 3);
+        assert_eq!(
+            [read(&rom, 0x38), read(&rom, 0x39), read(&rom, 0x3a)],
+            [0xc3, 0x38, 0x04]
+        );
+        assert_eq!(read(&rom, 0x50), 0xd9);
+        assert_eq!([read(&rom, 0x100), read(&rom, 0x101)], [0x18, 0xfe]);
+        assert_eq!(read(&rom, 0x0400), 0x11);
+        assert_eq!(read(&rom, 0x4000), 0x22);
+
+        rom.write(2, Addr::from_offset(Area::Rom, 0x2000, 0), None)
+            .unwrap();
+        assert_eq!(read(&rom, 0x4000 + 0x3ff), 0x11);
+        assert_eq!(read(&rom, 0x4000 + 0x400), 0x33);
+        rom.write(0, Addr::from_offset(Area::Rom, 0x2000, 0), None)
+            .unwrap();
+        assert_eq!(read(&rom, 0x4000), 0x22);
+        // the bank number wraps on the bank count, never mapping the bank 0
+        rom.write(3, Addr::from_offset(Area::Rom, 0x2000, 0), None)
+            .unwrap();
+        assert_eq!(read(&rom, 0x4000), 0x22);
+    }
+}
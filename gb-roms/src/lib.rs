@@ -1,4 +1,7 @@
+pub mod cheats;
+pub mod code_data_log;
 pub mod controllers;
+pub mod gbs;
 pub mod header;
 pub mod opcode;
 
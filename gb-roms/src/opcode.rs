@@ -4,7 +4,7 @@ mod register;
 mod condition;
 pub mod list;
 mod store;
-mod value;
+pub mod value;
 
 use condition::Condition::{Carry, NotCarry, NotZero, Zero};
 use error::Error;
@@ -30,6 +30,11 @@ impl Timer {
         self.system_clock.to_le_bytes()[1]
     }
 
+    /// Indicate if `TIMA` overflowed and waits to be reloaded with `TMA`
+    pub fn is_overflowing(&self) -> bool {
+        self.tima_overflow
+    }
+
     fn edge_detector_timer(&self) -> bool {
         let mask: u16 = match self.tac & 0b11 {
             0b00 => 1 << 9,
@@ -56,11 +61,7 @@ impl Timer {
     }
 
     fn reload_tima(&mut self, addr_bus: &mut dyn Bus<u8>) {
-        let int_flag = addr_bus.read(IF, None).unwrap_or_else(|e| {
-            log::error!("cannot read IF register: {}", e);
-            0
-        });
-        if let Err(e) = addr_bus.write(IF, int_flag | Timer::TIMER_INT_MASK, None) {
+        if let Err(e) = addr_bus.write(IF, Timer::TIMER_INT_MASK, Some(Source::Interrupt)) {
             log::error!("failed to update IF: {}", e);
         }
         self.tima_overflow = false;
@@ -0,0 +1,75 @@
+use gb_roms::cheats::{Code, CodeError};
+use serde::{Deserialize, Serialize};
+
+use crate::path::{create_root_config_path, game_cheats_path};
+
+/// Cheat code entered by the user
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cheat {
+    pub name: String,
+    pub code: String,
+    pub enabled: bool,
+}
+
+/// Cheats of a game, saved in the config directory
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Cheats {
+    pub list: Vec<Cheat>,
+}
+
+impl Cheats {
+    /// Load the cheats of a rom, an empty list is returned if there is none
+    pub fn load(romname: &str) -> Self {
+        let path = game_cheats_path(romname);
+        if !path.exists() {
+            return Self::default();
+        }
+        match std::fs::File::open(&path) {
+            Ok(file) => serde_yaml::from_reader(file).unwrap_or_else(|e| {
+                log::error!("failed to parse the cheats at {:?}: {}", path, e);
+                Self::default()
+            }),
+            Err(e) => {
+                log::error!("cannot open the cheats at {:?}: {}", path, e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self, romname: &str) {
+        let path = game_cheats_path(romname);
+        if let Err(e) = create_root_config_path()
+            .and_then(|_| std::fs::File::create(&path))
+            .map_err(anyhow::Error::from)
+            .and_then(|file| serde_yaml::to_writer(file, self).map_err(anyhow::Error::from))
+        {
+            log::error!("failed to save the cheats at {:?}: {}", path, e);
+        }
+    }
+
+    /// Add an enabled cheat, the code is rejected if it cannot be decoded
+    pub fn add(&mut self, name: String, code: &str) -> Result<(), CodeError> {
+        let code = code.trim().to_uppercase();
+        code.parse::<Code>()?;
+        self.list.push(Cheat {
+            name,
+            code,
+            enabled: true,
+        });
+        Ok(())
+    }
+
+    /// Decode the enabled cheats
+    pub fn codes(&self) -> impl Iterator<Item = Code> + '_ {
+        self.list
+            .iter()
+            .filter(|cheat| cheat.enabled)
+            .filter_map(|cheat| match cheat.code.parse() {
+                Ok(code) => Some(code),
+                Err(e) => {
+                    log::warn!("ignoring the cheat {:?}: {}", cheat.name, e);
+                    None
+                }
+            })
+    }
+}
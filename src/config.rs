@@ -1,5 +1,11 @@
 use clap::Parser;
-use std::fmt::{self, Display};
+use gb_dbg::trace::{TraceFormat, Tracer};
+use std::{
+    fmt::{self, Display},
+    path::PathBuf,
+};
+
+use crate::gbs::GbsPlayer;
 
 #[derive(Parser, Debug)]
 #[clap(version, author, about)]
@@ -17,7 +23,13 @@ pub struct Config {
         short = 'b',
         help = "create and enable breakpoints at the start of the rom\n\
         breakpoints must be specified in the following format:\n\
-        ./gbmu -b \"PC == 0050\" -b \"AF == 0010\" ...",
+        ./gbmu -b \"PC == 0050\" -b \"AF == 0010\" ...\n\
+        expressions can use registers (A, HL, ZF...), IO registers (LY, LCDC...),\n\
+        BANK, RAMBANK, hits, memory reads (*C000, *w C000) and arithmetic:\n\
+        ./gbmu -b \"PC == 4000 && BANK == 3 && LY > 90\"\n\
+        labels of the rom's .sym file can be used in place of addresses,\n\
+        a label that reads as an hexadecimal value is written with @, like @Face:\n\
+        ./gbmu -b \"PC == Main.loop\" -b \"*wPlayerHP < 10\"",
         multiple_occurrences = true,
         multiple_values = false,
         requires = "rom"
@@ -31,6 +43,17 @@ pub struct Config {
     )]
     pub debug: bool,
 
+    #[clap(
+        long = "script",
+        help = "run the debugger commands of a file, one per line, the debugger is opened\n\
+        a command is run once the emulation is stopped by the previous one:\n\
+        break PC == 0150\n\
+        continue\n\
+        x/16 C000",
+        requires = "rom"
+    )]
+    pub script: Option<PathBuf>,
+
     #[clap(
         arg_enum,
         short = 'm',
@@ -38,6 +61,142 @@ pub struct Config {
         help = "force gameboy mode between color and mono"
     )]
     pub mode: Option<Mode>,
+
+    #[clap(
+        long = "trace",
+        help = "trace the executed instructions from the start of the rom\n\
+        the doctor format can be compared with the logs of gameboy-doctor",
+        possible_values = &["doctor", "registers", "disassembly"],
+        requires = "rom"
+    )]
+    pub trace: Option<TraceFormat>,
+
+    #[clap(
+        long = "trace-file",
+        help = "file where the trace is written, default to $LOG_DIR/trace.log",
+        requires = "trace"
+    )]
+    pub trace_file: Option<PathBuf>,
+
+    #[clap(
+        long = "trace-ring",
+        help = "only keep the last N instructions, they are written when the emulator exit",
+        requires = "trace"
+    )]
+    pub trace_ring: Option<usize>,
+
+    #[clap(
+        long = "gdb",
+        help = "listen for gdb on a local port, the rom wait for gdb to attach\n\
+        gdb connect with `target remote :PORT`",
+        requires = "rom"
+    )]
+    pub gdb_port: Option<u16>,
+
+    #[clap(
+        long = "record",
+        help = "record the screen and the sound in a directory from the start of the rom\n\
+        the frames are written as png images next to an audio.wav file",
+        requires = "rom"
+    )]
+    pub record: Option<PathBuf>,
+
+    #[clap(
+        long = "vgm",
+        help = "log the writes to the sound registers from the start of the rom\n\
+        the log is saved as a VGM file when the emulator exit",
+        requires = "rom"
+    )]
+    pub vgm: Option<PathBuf>,
+
+    #[clap(
+        long = "track",
+        help = "track of a GBS file to play, from 1, default to the first track of the file",
+        requires = "rom"
+    )]
+    pub track: Option<u8>,
+
+    #[clap(
+        long = "duration",
+        help = "duration of the tracks of a GBS file before their fade-out, in seconds",
+        requires = "rom"
+    )]
+    pub duration: Option<u32>,
+
+    #[clap(
+        long = "fade",
+        help = "duration of the fade-out at the end of the tracks of a GBS file, in seconds",
+        requires = "rom"
+    )]
+    pub fade: Option<u32>,
+
+    #[clap(
+        long = "render",
+        help = "render the track of a GBS file in a WAV file and exit, without opening a window\n\
+        ./gbmu music.gbs --track 3 --duration 90 --render track3.wav",
+        requires = "rom"
+    )]
+    pub render: Option<PathBuf>,
+}
+
+impl Config {
+    pub fn trace_options(&self) -> Option<TraceOptions> {
+        self.trace.map(|format| TraceOptions {
+            format,
+            file: self.trace_file.clone(),
+            ring_size: self.trace_ring,
+        })
+    }
+
+    pub fn gbs_options(&self) -> GbsOptions {
+        GbsOptions {
+            track: self.track.map(|track| track.saturating_sub(1)),
+            duration: self.duration,
+            fade: self.fade,
+        }
+    }
+}
+
+/// Execution trace requested on the command line
+#[derive(Debug, Clone)]
+pub struct TraceOptions {
+    pub format: TraceFormat,
+    pub file: Option<PathBuf>,
+    pub ring_size: Option<usize>,
+}
+
+impl TraceOptions {
+    pub fn apply(&self, tracer: &mut Tracer) {
+        tracer.format = self.format;
+        if let Some(file) = &self.file {
+            tracer.set_path(file);
+        }
+        tracer.set_ring_size(self.ring_size);
+        tracer.start();
+    }
+}
+
+/// Playback of a GBS file requested on the command line
+#[derive(Debug, Clone, Default)]
+pub struct GbsOptions {
+    /// Track to play, from 0
+    pub track: Option<u8>,
+    pub duration: Option<u32>,
+    pub fade: Option<u32>,
+}
+
+impl GbsOptions {
+    pub fn apply(&self, player: &mut GbsPlayer) {
+        if let Some(duration) = self.duration {
+            player.durations.fill(duration);
+        }
+        if let Some(fade) = self.fade {
+            player.fade = fade;
+        }
+        if let Some(track) = self.track {
+            player.select_track(track);
+        }
+    }
 }
 
 #[derive(Debug, clap::ArgEnum, Clone, Copy, PartialEq, Eq)]
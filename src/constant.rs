@@ -2,17 +2,22 @@
 pub const APP_NAME: &str = "gbmu";
 /// Name of the organization
 // pub const ORG_NAME: &str = "";
-// #[cfg(feature = "save_state")]
-// /// File extension for a file that should contain a `save state` save
-// pub const SAVE_STATE_EXT: &str = "savepack";
+#[cfg(feature = "save_state")]
+/// File extension for a file that should contain a `save state` save
+pub const SAVE_STATE_EXT: &str = "savepack";
 /// File extension for a file that should contain a `game save` save
 pub const GAME_SAVE_EXT: &str = "gamepack";
+/// File extension for the file listing the cheats of a game
+pub const CHEATS_EXT: &str = "cheats.yaml";
 /// List of preferred extensions for ROM file
 pub const PREFERRED_ROM_EXTS: [&str; 3] = ["rom", "gb", "gbc"];
+/// File extension of the GBS files, the music of a game played without its ROM
+pub const GBS_EXT: &str = "gbs";
 // #[cfg(feature = "save_state")]
 // /// List of preferred extensions for `save state` file
 // pub const PREFERRED_SAVE_STATE_EXT: [&str; 1] = [SAVE_STATE_EXT];
 
 pub const MENU_BAR_SIZE: f32 = 30.;
 
+/// Stereo frames buffered for the audio output
 pub const AUDIO_BUFFER_SIZE: usize = 2048;
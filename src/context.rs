@@ -10,6 +10,8 @@ use winit::{
     window::{WindowBuilder, WindowId},
 };
 
+use gb_bus::watchpoint::Owner;
+use gb_dbg::gdb::GdbServer;
 use gb_lcd::{DrawEgui, GBPixels, GBWindow, PseudoPixels, PseudoWindow};
 use gb_ppu::{GB_SCREEN_HEIGHT, GB_SCREEN_WIDTH};
 use gb_ppu::{
@@ -20,7 +22,12 @@ use crate::constant::MENU_BAR_SIZE;
 #[cfg(feature = "fps")]
 use crate::time_frame::TimeStat;
 use crate::{
-    config::Config, custom_event::CustomEvent, game::Game, image::load_image_to_frame,
+    config::{Config, GbsOptions},
+    custom_event::CustomEvent,
+    frame_limiter::FrameLimiter,
+    game::Game,
+    gbs::GbsPlayer,
+    image::load_image_to_frame,
     windows::WindowType,
 };
 
@@ -45,6 +52,10 @@ pub struct Context {
     pub internal_config: InternalConfig,
     pub event_proxy: EventLoopProxy<CustomEvent>,
     pub game: Option<Game>,
+    /// Player of the loaded GBS file, in place of a game
+    pub gbs: Option<GbsPlayer>,
+    /// Pace of the game or the GBS file when there is no audio output
+    pub frame_limiter: FrameLimiter,
     #[cfg(feature = "fps")]
     pub time_frame: TimeStat,
     #[cfg(feature = "fps")]
@@ -57,12 +68,22 @@ pub struct Context {
     pub spritesheet_ctx:
         Option<ppu_tool::Context<PPU_SPRITE_RENDER_WIDTH, PPU_SPRITE_RENDER_HEIGHT, MENU_BAR>>,
     pub config: Configuration,
+    pub gdb: Option<GdbServer>,
 }
 
 #[derive(Default)]
 pub struct InternalConfig {
     pub mode: Option<crate::config::Mode>,
     pub rom_file: Option<PathBuf>,
+    pub trace: Option<crate::config::TraceOptions>,
+    /// Directory where the loaded games are recorded
+    pub record: Option<PathBuf>,
+    /// VGM file where the writes to the sound registers of the loaded game are logged
+    pub vgm: Option<PathBuf>,
+    /// Commands run by the debugger once it is opened
+    pub script: Vec<String>,
+    /// Track and durations of the loaded GBS file
+    pub gbs: GbsOptions,
 }
 
 impl Context {
@@ -77,6 +98,8 @@ impl Context {
             internal_config: InternalConfig::default(),
             event_proxy,
             game: None,
+            gbs: None,
+            frame_limiter: FrameLimiter::default(),
             #[cfg(feature = "fps")]
             time_frame: TimeStat::default(),
             #[cfg(feature = "fps")]
@@ -87,6 +110,7 @@ impl Context {
             tilemap_ctx: None,
             spritesheet_ctx: None,
             config,
+            gdb: None,
         }
     }
 
@@ -94,12 +118,30 @@ impl Context {
         let config_file = config.rom.map(PathBuf::from);
         let reload_mode = self.internal_config.mode != config.mode;
         let reload_file = self.internal_config.rom_file != config_file;
-        let open_debugger = config.debug;
+        let open_debugger = config.debug || config.script.is_some();
+        if let Some(script) = &config.script {
+            match std::fs::read_to_string(script) {
+                Ok(content) => {
+                    self.internal_config.script = content.lines().map(str::to_owned).collect()
+                }
+                Err(e) => log::error!("cannot read script {}: {}", script.display(), e),
+            }
+        }
+        self.internal_config.trace = config.trace_options();
+        self.internal_config.record = config.record;
+        self.internal_config.vgm = config.vgm;
+        self.internal_config.gbs = config.gbs_options();
+        if let Some(port) = config.gdb_port {
+            match GdbServer::bind(port) {
+                Ok(server) => self.gdb = Some(server),
+                Err(e) => log::error!("cannot start the gdb server on port {}: {}", port, e),
+            }
+        }
 
         if reload_mode || reload_file {
             self.internal_config.mode = config.mode;
             if let Some(file) = config_file.or_else(|| self.internal_config.rom_file.clone()) {
-                self.load(file, open_debugger);
+                self.load(file, open_debugger || self.gdb.is_some());
             } else {
                 log::warn!("Oh, I was expecting a file or something");
             }
@@ -134,10 +176,14 @@ impl Context {
                             .build(event_loop)
                             .expect("cannot build debugger window")
                     };
+                    if let Some(ref mut game) = self.game {
+                        game.set_call_stack_tracking(true);
+                    }
                     self.debugger_ctx.replace(debugger::Context::new(
                         GBWindow::new(window),
                         self.event_proxy.clone(),
                         breakpoints,
+                        std::mem::take(&mut self.internal_config.script),
                     ));
                 }
             }
@@ -227,7 +273,13 @@ impl Context {
 
     pub fn close_window(&mut self, window_type: WindowType) {
         match window_type {
-            WindowType::Debugger(_) => self.debugger_ctx = None,
+            WindowType::Debugger(_) => {
+                self.debugger_ctx = None;
+                if let Some(ref mut game) = self.game {
+                    game.set_call_stack_tracking(false);
+                    game.addr_bus.watchpoints.set(Owner::Debugger, Vec::new());
+                }
+            }
             WindowType::Keybindings => self.keybindings_ctx = None,
             WindowType::Tilesheet => self.tilesheet_ctx = None,
             WindowType::Tilemap => self.tilemap_ctx = None,
@@ -316,8 +368,27 @@ impl Context {
 impl Context {
     pub fn load(&mut self, file: PathBuf, stopped: bool) {
         drop(self.game.take());
+        drop(self.gbs.take());
+        if file.extension().map_or(false, |ext| {
+            ext.eq_ignore_ascii_case(crate::constant::GBS_EXT)
+        }) {
+            self.load_gbs(file);
+            return;
+        }
         match Game::new(&file, stopped, self.internal_config.mode, &self.config) {
-            Ok(game) => {
+            Ok(mut game) => {
+                if let Some(trace) = &self.internal_config.trace {
+                    trace.apply(&mut game.tracer);
+                }
+                if let Some(directory) = &self.internal_config.record {
+                    if let Err(e) = game.start_recording(directory) {
+                        log::error!("{}", e);
+                    }
+                }
+                if let Some(vgm) = &self.internal_config.vgm {
+                    game.start_audio_log(vgm);
+                }
+                game.set_call_stack_tracking(self.debugger_ctx.is_some());
                 self.game.replace(game);
                 self.internal_config.rom_file.replace(file);
             }
@@ -331,14 +402,53 @@ impl Context {
         };
     }
 
+    /// Load a GBS file, its music is played in place of a game
+    fn load_gbs(&mut self, file: PathBuf) {
+        match GbsPlayer::load(&file) {
+            Ok(mut player) => {
+                self.internal_config.gbs.apply(&mut player);
+                if let Some(vgm) = &self.internal_config.vgm {
+                    player.start_audio_log(vgm);
+                }
+                log::info!(
+                    "playing \"{}\" by {}, {} tracks",
+                    player.header.title,
+                    player.header.author,
+                    player.header.song_count
+                );
+                self.gbs.replace(player);
+                self.internal_config.rom_file.replace(file);
+            }
+            Err(err) => {
+                log::error!(
+                    "Failed to load gbs file \"{}\": {}",
+                    file.to_string_lossy(),
+                    err
+                );
+            }
+        }
+    }
+
     /// Reset the game context allowing the restart a game
     pub fn reset_game(&mut self, wanted_mode: Option<crate::config::Mode>) {
+        if let Some(player) = &mut self.gbs {
+            player.select_track(player.track());
+            return;
+        }
         if let Some(ref rom_file) = self.internal_config.rom_file {
             let selected_mode = wanted_mode.or(self.internal_config.mode);
 
-            drop(self.game.take());
+            // keep the trace going through the reset
+            let tracer = self
+                .game
+                .take()
+                .map(|mut game| std::mem::take(&mut game.tracer));
             match Game::new(rom_file, false, selected_mode, &self.config) {
-                Ok(game) => {
+                Ok(mut game) => {
+                    if let Some(tracer) = tracer {
+                        game.tracer = tracer;
+                    }
+                    game.set_call_stack_tracking(self.debugger_ctx.is_some());
                     self.game.replace(game);
                 }
                 Err(err) => {
@@ -416,7 +526,7 @@ impl Context {
                 let pressed = input.state == ElementState::Pressed;
                 let key = KeyEntry::from(input);
                 if let Some(ref mut game) = self.game {
-                    game.joypad.borrow_mut().on_key_event(key, pressed);
+                    game.on_key_event(key, pressed);
                 }
             }
             _ => {}
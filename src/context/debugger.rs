@@ -1,4 +1,4 @@
-use gb_dbg::debugger::{options::DebuggerOptions, Debugger, DebuggerBuilder};
+use gb_dbg::debugger::{options::DebuggerOptions, Debugger, DebuggerBuilder, StateRequest};
 use gb_lcd::GBWindow;
 use winit::{event::WindowEvent, event_loop::EventLoopProxy};
 
@@ -16,12 +16,14 @@ impl Context {
         window: GBWindow,
         event_proxy: EventLoopProxy<CustomEvent>,
         breakpoints: Option<Vec<String>>,
+        script: Vec<String>,
     ) -> Self {
         let mut builder = DebuggerBuilder::new();
 
-        if let Some(breakpoints) = breakpoints {
+        if breakpoints.is_some() || !script.is_empty() {
             let options = DebuggerOptions {
-                breakpoints,
+                breakpoints: breakpoints.unwrap_or_default(),
+                script,
                 ..Default::default()
             };
             builder = builder.with_options(options);
@@ -57,9 +59,35 @@ impl Context {
                 .expect("cannot send reset game event");
             self.debugger.reset()
         }
+        if let Some(request) = self.debugger.state_request.take() {
+            self.send_state_request(request, game);
+        }
         Ok(())
     }
 
+    #[cfg(feature = "save_state")]
+    fn send_state_request(&self, request: StateRequest, game: &Game) {
+        let event = match request {
+            StateRequest::Save(path) => CustomEvent::SaveState(
+                path.unwrap_or_else(|| crate::path::game_state_path(&game.romname)),
+            ),
+            StateRequest::Load(path) => CustomEvent::LoadState(
+                path.unwrap_or_else(|| crate::path::game_state_path(&game.romname)),
+            ),
+        };
+        self.event_proxy
+            .send_event(event)
+            .expect("cannot send save state event");
+    }
+
+    #[cfg(not(feature = "save_state"))]
+    fn send_state_request(&self, request: StateRequest, _game: &Game) {
+        log::error!(
+            "cannot handle {:?}, the emulator is built without the `save_state` feature",
+            request
+        );
+    }
+
     pub(crate) fn process_window_event(&mut self, event: WindowEvent) {
         let debugger_window = &mut self.window;
         if debugger_window.context.on_event(&event) {
@@ -22,6 +22,14 @@ pub enum CustomEvent {
     /// Exit the emulator
     Quit,
 
+    /// Record the screen and the sound of the game in a directory
+    StartRecording(PathBuf),
+    StopRecording,
+
+    /// Log the writes to the sound registers in a VGM file
+    StartAudioLog(PathBuf),
+    StopAudioLog,
+
     /// Reset the game context, those restarting the loaded game
     ResetGame,
 }
@@ -0,0 +1,38 @@
+use std::time::{Duration, Instant};
+
+use gb_clock::Clock;
+
+/// Pace the frames on the wall clock, used when no audio stream regulates the emulation
+#[derive(Debug)]
+pub struct FrameLimiter {
+    /// When the next frame is due
+    next: Instant,
+}
+
+impl Default for FrameLimiter {
+    fn default() -> Self {
+        Self {
+            next: Instant::now(),
+        }
+    }
+}
+
+impl FrameLimiter {
+    /// Duration of a frame on the hardware
+    const FRAME_DURATION: Duration = Duration::from_nanos(
+        Clock::CYCLES_PER_FRAME as u64 * 1_000_000_000 / Clock::CYCLES_PER_SECOND as u64,
+    );
+
+    /// Sleep until the next frame is due.
+    ///
+    /// A late frame is not caught up, the following frames are paced from now
+    pub fn wait(&mut self) {
+        let now = Instant::now();
+        if self.next > now {
+            std::thread::sleep(self.next - now);
+            self.next += Self::FRAME_DURATION;
+        } else {
+            self.next = now + Self::FRAME_DURATION;
+        }
+    }
+}
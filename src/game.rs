@@ -1,23 +1,43 @@
-#[cfg(feature = "registers_logs")]
-use std::io::BufWriter;
+use std::collections::VecDeque;
 use std::sync::{Arc, Mutex};
-use std::{cell::RefCell, fs::File, ops::DerefMut, path::Path, rc::Rc};
+use std::{
+    cell::{Ref, RefCell},
+    fs::File,
+    ops::DerefMut,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
 
-use gb_apu::apu::Apu;
+use gb_apu::{apu::Apu, OUTPUT_CHANNELS, SAMPLE_RATES, SOUND_CHANNELS};
 use gb_bus::{
     generic::{CharDevice, SimpleRW},
-    AddressBus, Bus, IORegArea, IORegBus, Source, WorkingRam,
+    watchpoint::{Access, Owner},
+    AddressBus, Bus, IORegArea, IORegBus, Source, Watchpoint, WatchpointHit, WorkingRam,
 };
 use gb_clock::{counted_cycles, not_counted_cycles, Clock};
-use gb_cpu::{cpu::Cpu, new_cpu, registers::Registers};
+use gb_cpu::{
+    cpu::Cpu,
+    microcode::{call_stack::CallStack, controller::Mode as CpuMode},
+    new_cpu,
+    registers::Registers,
+};
+use gb_dbg::audio::{ChannelState, ScopePoint};
 use gb_dbg::dbg_interfaces::{
-    AudioRegs, CpuRegs, DebugOperations, IORegs, MemoryDebugOperations, PpuRegs,
-    RegisterDebugOperations, RegisterMap, RegisterValue,
+    AudioRegs, BankedArea, CpuRegs, DebugOperations, IORegs, MemoryDebugOperations, PpuRegs,
+    RegisterDebugOperations, RegisterMap, RegisterValue, Screen, WatchpointDebugOperations,
 };
+use gb_dbg::history::History;
+use gb_dbg::profiler::{Hotspot, Profiler, Routine};
+use gb_dbg::recording::Recorder;
+use gb_dbg::symbols::SymbolTable;
+use gb_dbg::timeline::Timeline;
+use gb_dbg::trace::{TraceEntry, Tracer};
 use gb_dbg::until::Until;
 use gb_dma::{dma::Dma, hdma::Hdma};
-use gb_joypad::Joypad;
-use gb_ppu::Ppu;
+use gb_joypad::{Joypad, KeyEntry};
+use gb_ppu::{Ppu, GB_SCREEN_HEIGHT, GB_SCREEN_WIDTH};
+use gb_roms::cheats::{Code, GameShark, Target};
+use gb_roms::code_data_log::CodeDataLog;
 use gb_roms::controllers::bios::BiosType;
 use gb_roms::controllers::Bios;
 #[cfg(feature = "save_state")]
@@ -26,17 +46,26 @@ use gb_roms::{controllers::Generic, header::AutoSave, Header};
 use gb_timer::Timer;
 #[cfg(feature = "save_state")]
 use save_state::SaveState;
-use utils::mbc_with_save_state;
+use snapshot::Snapshot;
+use utils::{channel_state, mbc_with_save_state};
 
 use crate::{
-    config::Mode, constant::AUDIO_BUFFER_SIZE, context::configuration::Configuration,
-    path::game_save_path,
+    cheats::Cheats, config::Mode, constant::AUDIO_BUFFER_SIZE,
+    context::configuration::Configuration, path::game_save_path,
 };
 
 #[cfg(feature = "save_state")]
 mod save_state;
+mod snapshot;
 mod utils;
 
+/// Address of the LY register
+const LY_ADDRESS: u16 = 0xff44;
+/// First line of the vertical blank
+const VBLANK_LINE: u8 = 144;
+/// Number of cycles between two snapshots of the history
+const SNAPSHOT_INTERVAL: usize = 10 * Clock::CYCLES_PER_FRAME;
+
 macro_rules! cell {
     ($e:expr) => {
         Rc::new(RefCell::new($e))
@@ -59,15 +88,38 @@ pub struct Game {
 
     pub apu: Rc<RefCell<Apu>>,
     pub addr_bus: AddressBus,
+    /// Labels of the `.sym` file found next to the rom
+    pub symbols: SymbolTable,
+    /// Trace of the executed instructions, disabled by default
+    pub tracer: Tracer,
+    pub timeline: Timeline,
+    pub profiler: Profiler,
+    /// Recording of the screen and of the sound, the apu records from the next frame
+    recorder: Option<Recorder>,
+    /// VGM file where the writes to the sound registers are saved once the log stops
+    audio_log: Option<PathBuf>,
     scheduled_stop: Option<ScheduledStop>,
     emulation_stopped: bool,
     cycle_count: usize,
-    #[cfg(feature = "save_state")]
+    /// Number of instructions started since the power on
+    instruction_count: u64,
+    /// Snapshots used to rewind the emulation
+    history: History<Snapshot>,
+    /// Cycle after which the next snapshot is taken
+    next_snapshot: usize,
+    /// Key events sent to the joypad with their cycle, replayed after a rewind
+    inputs: VecDeque<(usize, KeyEntry, bool)>,
+    /// Set after a rewind, the snapshots and inputs of the discarded future
+    /// are dropped once the emulation diverges from them
+    rewound: bool,
+    /// Bytes written at the end of each frame by the freeze cheats
+    frozen: Vec<(u16, u8)>,
+    /// Cheats of the game, saved in the config directory
+    pub cheats: Cheats,
+    /// GameShark codes written at each VBlank
+    game_shark: Vec<GameShark>,
     hram: Rc<RefCell<SimpleRW<0x80>>>,
-    #[cfg(feature = "save_state")]
     wram: Rc<RefCell<WorkingRam>>,
-    #[cfg(feature = "registers_logs")]
-    logs_file: BufWriter<File>,
     pub cgb_mode: bool,
 }
 
@@ -81,6 +133,16 @@ enum ScheduledStop {
     Frame(usize),
     /// Schedule a stop after `time` delay
     Timeout(std::time::Instant, std::time::Duration),
+    /// Schedule a stop when the execution is back at `return_address` with the same stack
+    StepOver { return_address: u16, sp: u16 },
+    /// Schedule a stop after a return that pop the stack above `sp`
+    StepOut { sp: u16, returning: bool },
+    /// Schedule a stop when PC reach the address
+    RunTo(u16),
+    /// Schedule a stop after the dispatch of the next interrupt
+    Interrupt,
+    /// Schedule a stop when LY change to `line`
+    Line { line: u8, previous: u8 },
 }
 
 impl Game {
@@ -214,10 +276,17 @@ impl Game {
         let serial = cell!(gb_bus::Serial::new(cgb_mode));
         io_bus.with_serial(serial);
 
-        let buffer: Arc<Mutex<Vec<f32>>> =
-            Arc::new(Mutex::new(Vec::with_capacity(AUDIO_BUFFER_SIZE)));
-        let (stream, sample_rate) = Apu::init_audio_output(buffer.clone());
-        let apu = cell!(Apu::new(buffer, Some(stream), sample_rate));
+        let buffer: Arc<Mutex<Vec<f32>>> = Arc::new(Mutex::new(Vec::with_capacity(
+            AUDIO_BUFFER_SIZE * OUTPUT_CHANNELS,
+        )));
+        let (stream, sample_rate) = match Apu::init_audio_output(buffer.clone()) {
+            Ok((stream, sample_rate)) => (Some(stream), sample_rate),
+            Err(e) => {
+                log::warn!("no audio output, the game will be silent: {}", e);
+                (None, SAMPLE_RATES[0])
+            }
+        };
+        let apu = cell!(Apu::new(buffer, stream, sample_rate, cgb_mode));
         io_bus.with_sound(apu.clone());
 
         let joypad = cell!(Joypad::from_config(configuration.input.clone(),));
@@ -231,19 +300,19 @@ impl Game {
                 .with_ppu_cgb(ppu_reg)
                 .with_area(IORegArea::Key1, cpu_io_reg)
                 .with_area(IORegArea::RP, cell!(CharDevice(0)))
-                .with_area(IORegArea::Svbk, wram);
+                .with_area(IORegArea::Svbk, wram.clone());
         }
 
         let hram = cell!(SimpleRW::<0x80>::default());
-        bus.with_hram(hram);
+        bus.with_hram(hram.clone());
 
         let io_bus = cell!(io_bus);
         bus.with_io_reg(io_bus.clone());
 
-        #[cfg(feature = "registers_logs")]
-        let logs_file = Game::create_new_file().unwrap();
+        let symbols = Game::load_symbols(rom_path.as_ref());
+        let cheats = Cheats::load(&romname);
 
-        Ok(Self {
+        let mut game = Self {
             romname,
             header: header.clone(),
             auto_save: header.cartridge_type.auto_save_type(),
@@ -259,63 +328,454 @@ impl Game {
 
             apu,
             addr_bus: bus,
+            symbols,
+            tracer: Tracer::default(),
+            timeline: Timeline::default(),
+            profiler: Profiler::default(),
+            recorder: None,
+            audio_log: None,
             scheduled_stop: None,
             emulation_stopped: stopped,
             cycle_count: 0,
-            #[cfg(feature = "save_state")]
+            instruction_count: 0,
+            history: History::default(),
+            next_snapshot: 0,
+            inputs: VecDeque::new(),
+            rewound: false,
+            frozen: Vec::new(),
+            cheats,
+            game_shark: Vec::new(),
             hram,
-            #[cfg(feature = "save_state")]
             wram,
-            #[cfg(feature = "registers_logs")]
-            logs_file,
             cgb_mode,
-        })
+        };
+        game.enable_cheats();
+        Ok(game)
+    }
+
+    /// Load the symbol file with the same name as the rom, if any
+    fn load_symbols(rom_path: &Path) -> SymbolTable {
+        let sym_path = rom_path.with_extension("sym");
+        if !sym_path.exists() {
+            return SymbolTable::default();
+        }
+        match SymbolTable::load(&sym_path) {
+            Ok(symbols) => {
+                log::info!(
+                    "loaded {} symbols from {}",
+                    symbols.len(),
+                    sym_path.display()
+                );
+                symbols
+            }
+            Err(e) => {
+                log::warn!("cannot load symbols from {}: {}", sym_path.display(), e);
+                SymbolTable::default()
+            }
+        }
     }
 
     pub fn cycle(&mut self) -> bool {
         if !self.emulation_stopped {
-            #[cfg(feature = "registers_logs")]
-            if self.cpu.controller.is_instruction_finished {
-                self.log_registers_to_file().unwrap_or_default();
+            self.discard_future();
+            self.run_cycle(false)
+        } else {
+            false
+        }
+    }
+
+    /// Execute a cycle, `replay` is set when the cycle is executed again after a rewind
+    fn run_cycle(&mut self, replay: bool) -> bool {
+        if replay {
+            self.replay_inputs();
+        }
+        if self.cpu.controller.is_instruction_finished {
+            if !replay && self.cycle_count >= self.next_snapshot {
+                self.take_snapshot();
+            }
+            self.instruction_count += 1;
+            if !replay && self.tracer.is_enabled() {
+                self.trace_instruction();
+            }
+            self.notify_execute_watchpoints();
+            if self.cpu.controller.call_stack.is_tracking() {
+                let mbc = &self.mbc;
+                self.cpu
+                    .controller
+                    .call_stack
+                    .fill_banks(|| mbc.borrow().rom_bank());
+            }
+            if !replay && self.profiler.is_enabled() {
+                self.profile_instruction();
+            }
+            // the replayed instructions mark the same bytes, but the fetched instruction
+            // must stay current for the reads of the replayed cycles
+            self.log_instruction();
+        }
+        self.hdma
+            .borrow_mut()
+            .check_hdma_state(&mut self.cpu, &self.ppu);
+        let profiled = !replay && self.profiler.is_enabled();
+        if profiled {
+            self.profiler.tick(self.is_cpu_halted());
+        }
+        let line =
+            (!self.game_shark.is_empty()).then(|| Game::read_debug(&self.addr_bus, LY_ADDRESS));
+        // the replayed cycles were already heard, the apu only updates its channels
+        self.apu.borrow_mut().set_replaying(replay);
+        let frame_not_finished = counted_cycles!(
+            self.clock,
+            &mut self.addr_bus,
+            self.timer.borrow_mut().deref_mut(),
+            &mut self.ppu,
+            self.joypad.borrow_mut().deref_mut(),
+            self.dma.borrow_mut().deref_mut(),
+            &mut self.cpu,
+            self.hdma.borrow_mut().deref_mut(),
+            self.apu.borrow_mut().deref_mut()
+        );
+        self.record_timeline();
+
+        if line.map_or(false, |line| {
+            line != VBLANK_LINE && Game::read_debug(&self.addr_bus, LY_ADDRESS) == VBLANK_LINE
+        }) {
+            self.apply_game_shark();
+        }
+        if !frame_not_finished {
+            if !replay {
+                self.record_frame();
+            }
+            self.apply_frozen();
+            if profiled {
+                self.profiler.end_frame();
             }
-            self.hdma
-                .borrow_mut()
-                .check_hdma_state(&mut self.cpu, &self.ppu);
+        }
 
-            let frame_not_finished = counted_cycles!(
+        self.check_scheduled_stop(!frame_not_finished);
+        if self.cpu.io_regs.borrow().fast_mode() {
+            if profiled {
+                self.profiler.tick(self.is_cpu_halted());
+            }
+            not_counted_cycles!(
                 self.clock,
                 &mut self.addr_bus,
-                self.timer.borrow_mut().deref_mut(),
-                &mut self.ppu,
-                self.joypad.borrow_mut().deref_mut(),
-                self.dma.borrow_mut().deref_mut(),
                 &mut self.cpu,
-                self.hdma.borrow_mut().deref_mut(),
-                self.apu.borrow_mut().deref_mut()
+                self.timer.borrow_mut().deref_mut(),
+                self.dma.borrow_mut().deref_mut()
             );
-
+            self.record_timeline();
             self.check_scheduled_stop(!frame_not_finished);
-            if self.cpu.io_regs.borrow().fast_mode() {
-                not_counted_cycles!(
-                    self.clock,
-                    &mut self.addr_bus,
-                    &mut self.cpu,
-                    self.timer.borrow_mut().deref_mut(),
-                    self.dma.borrow_mut().deref_mut()
-                );
-                self.check_scheduled_stop(!frame_not_finished);
+        }
+
+        self.cycle_count += 1;
+        frame_not_finished
+    }
+
+    /// Decode the enabled cheats, the Game Genie codes patch the ROM
+    /// and the GameShark codes are written at each VBlank
+    fn enable_cheats(&mut self) {
+        let mut patches = Vec::new();
+        self.game_shark.clear();
+        for code in self.cheats.codes() {
+            match code {
+                Code::GameGenie(patch) => patches.push(patch),
+                Code::GameShark(write) => self.game_shark.push(write),
             }
+        }
+        self.mbc.borrow_mut().set_patches(patches);
+    }
 
-            self.cycle_count += 1;
-            frame_not_finished
-        } else {
-            false
+    /// Apply the changes made to the cheats and save them
+    pub fn update_cheats(&mut self) {
+        self.enable_cheats();
+        self.cheats.save(&self.romname);
+        // the frames already executed were run with the previous cheats
+        self.forget_history();
+    }
+
+    fn apply_game_shark(&mut self) {
+        for code in &self.game_shark {
+            let written = match code.target() {
+                Target::CartRam(bank, offset) => self
+                    .mbc
+                    .borrow_mut()
+                    .write_ram_bank(bank, offset, code.value),
+                Target::WorkingRam(bank, offset) => {
+                    self.wram.borrow_mut().write_bank(bank, offset, code.value)
+                }
+                Target::Mapped(address) => self
+                    .addr_bus
+                    .write(address, code.value, Some(Source::Debugger))
+                    .is_ok(),
+            };
+            if !written {
+                log::trace!("cannot apply the GameShark code {:?}", code);
+            }
+        }
+    }
+
+    fn apply_frozen(&mut self) {
+        for (address, value) in &self.frozen {
+            if let Err(err) = self
+                .addr_bus
+                .write(*address, *value, Some(Source::Debugger))
+            {
+                log::trace!("cannot freeze {:04X}: {:?}", address, err);
+            }
+        }
+    }
+
+    /// Track the calls made by the cpu, used by the debugger to show the backtrace
+    /// and by the profiler to attribute the cycles to the callers
+    pub fn set_call_stack_tracking(&mut self, tracking: bool) {
+        self.cpu.controller.call_stack.set_tracking(tracking);
+    }
+
+    /// Record the state of the hardware at the end of a cycle of the cpu on the timeline
+    fn record_timeline(&mut self) {
+        // the requests are taken even when not recording, they would be reported late otherwise
+        let requests = self.cpu.io_regs.borrow_mut().take_requests();
+        if self.timeline.is_enabled() {
+            self.timeline.observe(
+                self.cpu.controller.interrupt_dispatched,
+                self.dma.borrow().is_transferring(),
+                self.timer.borrow().is_overflowing(),
+                requests,
+            );
+            gb_clock::cycle(&mut self.timeline, &mut self.addr_bus);
+        }
+    }
+
+    /// Report the instruction about to be executed to the execute watchpoints,
+    /// the cpu stalled by an HDMA transfer is still waiting to execute the same instruction
+    fn notify_execute_watchpoints(&self) {
+        let pc = self.cpu.registers.pc;
+        if !self.cpu.halted_dma && self.addr_bus.watchpoints.is_watching(pc, Access::Execute) {
+            let opcode = self
+                .addr_bus
+                .read(pc, Some(Source::Debugger))
+                .unwrap_or(0xff);
+            self.addr_bus.watchpoints.on_execute(pc, opcode);
         }
     }
+
+    fn take_snapshot(&mut self) {
+        let snapshot = Snapshot::from(&*self);
+        self.history.push(self.instruction_count, snapshot);
+        self.next_snapshot = self.cycle_count + SNAPSHOT_INTERVAL;
+        // the inputs older than the history cannot be replayed anymore
+        if let Some((_, oldest)) = self.history.oldest() {
+            let oldest = oldest.cycle_count();
+            while self
+                .inputs
+                .front()
+                .map_or(false, |(cycle, ..)| *cycle < oldest)
+            {
+                self.inputs.pop_front();
+            }
+        }
+    }
+
+    /// Send the key events recorded at the current cycle to the joypad
+    fn replay_inputs(&self) {
+        let start = self
+            .inputs
+            .partition_point(|(cycle, ..)| *cycle < self.cycle_count);
+        for (_, key, pressed) in self
+            .inputs
+            .range(start..)
+            .take_while(|(cycle, ..)| *cycle == self.cycle_count)
+        {
+            self.joypad.borrow_mut().on_key_event(*key, *pressed);
+        }
+    }
+
+    /// Forward a key event to the joypad and record it to replay it after a rewind
+    pub fn on_key_event(&mut self, key: KeyEntry, pressed: bool) {
+        if self.joypad.borrow_mut().on_key_event(key, pressed) {
+            self.discard_future();
+            self.inputs.push_back((self.cycle_count, key, pressed));
+        }
+    }
+
+    /// Drop the snapshots and the inputs recorded after the current cycle once the
+    /// emulation is resumed after a rewind, they describe a future that may not happen
+    fn discard_future(&mut self) {
+        if !self.rewound {
+            return;
+        }
+        self.rewound = false;
+        self.history.truncate(self.instruction_count + 1);
+        while self
+            .inputs
+            .back()
+            .map_or(false, |(cycle, ..)| *cycle > self.cycle_count)
+        {
+            self.inputs.pop_back();
+        }
+        self.next_snapshot = self
+            .history
+            .latest(self.instruction_count)
+            .map_or(self.cycle_count, |(_, snapshot)| {
+                snapshot.cycle_count() + SNAPSHOT_INTERVAL
+            });
+    }
+
+    /// Drop the whole history, the past cannot be replayed anymore
+    fn forget_history(&mut self) {
+        self.history.clear();
+        self.inputs.clear();
+        self.next_snapshot = self.cycle_count;
+    }
+
+    /// Forget the history after a change made by the debugger, the snapshots
+    /// taken since the start of the current instruction would not lead to the new state
+    fn state_modified(&mut self) {
+        self.rewound = true;
+        self.discard_future();
+        self.history.truncate(self.instruction_count);
+        self.next_snapshot = self.cycle_count;
+    }
+
+    /// Restore the machine at the end of the instruction `target`,
+    /// the nearest snapshot is restored then the emulation is executed again up to `target`.
+    ///
+    /// Return `false` when `target` is older than the history.
+    pub fn rewind(&mut self, target: u64) -> bool {
+        let history = std::mem::take(&mut self.history);
+        let restored = match history.latest(target) {
+            Some((instruction, snapshot)) => {
+                self.restore(snapshot);
+                self.instruction_count = *instruction;
+                true
+            }
+            None => false,
+        };
+        self.history = history;
+        if !restored {
+            log::warn!("instruction {} is older than the history", target);
+            return false;
+        }
+        // the frames after the rewind were already recorded
+        if self.is_recording() {
+            log::info!("the rewind stops the recording");
+            self.stop_recording();
+        }
+
+        self.emulation_stopped = true;
+        self.scheduled_stop = None;
+        while self.instruction_count < target || !self.cpu.controller.is_instruction_finished {
+            self.run_cycle(true);
+        }
+        self.replay_inputs();
+        self.rewound = true;
+        // the watchpoints were already reported the first time
+        self.addr_bus.watchpoints.clear_hits();
+        if self.timeline.is_enabled() {
+            self.timeline.stop();
+            self.timeline.start();
+        }
+        true
+    }
+
+    /// Execute the next instruction while the emulation is paused, as a replay of the history
+    pub fn step_instruction(&mut self) {
+        self.emulation_stopped = true;
+        self.scheduled_stop = None;
+        self.run_cycle(true);
+        while !self.cpu.controller.is_instruction_finished {
+            self.run_cycle(true);
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    /// Record the screen and the sound in `directory`, from the end of the current frame.
+    /// A rewind stops the recording, the frames after it were already recorded
+    pub fn start_recording(&mut self, directory: &Path) -> anyhow::Result<()> {
+        self.stop_recording();
+        let sample_rate = self.apu.borrow().sample_rate().0;
+        let recorder = Recorder::start(directory, sample_rate)
+            .map_err(|e| anyhow::anyhow!("cannot record in {}: {}", directory.display(), e))?;
+        log::info!("recording in {}", directory.display());
+        self.recorder = Some(recorder);
+        Ok(())
+    }
+
+    /// Stop the recording, the sound of the unfinished frame is dropped
+    pub fn stop_recording(&mut self) {
+        self.apu.borrow_mut().set_recording(false);
+        if let Some(recorder) = self.recorder.take() {
+            let directory = recorder.directory().to_path_buf();
+            let frames = recorder.frames();
+            match recorder.finish() {
+                Ok(()) => log::info!("recorded {} frames in {}", frames, directory.display()),
+                Err(e) => log::error!(
+                    "cannot complete the recording in {}: {}",
+                    directory.display(),
+                    e
+                ),
+            }
+        }
+    }
+
+    /// Write the frame that just ended, or start to record the sound on the first frame
+    fn record_frame(&mut self) {
+        let recorder = match self.recorder.as_mut() {
+            Some(recorder) => recorder,
+            None => return,
+        };
+        let mut apu = self.apu.borrow_mut();
+        if !apu.is_recording() {
+            apu.set_recording(true);
+            return;
+        }
+        let samples = apu.take_recorded();
+        drop(apu);
+        if let Err(e) = recorder.push_frame(self.ppu.pixels(), &samples) {
+            log::error!("cannot record the frame {}: {}", recorder.frames(), e);
+            self.stop_recording();
+        }
+    }
+
     pub fn is_audio_buffer_full(&self) -> bool {
         (*self.apu.borrow()).is_buffer_full()
     }
 
+    /// Return true when the audio output paces the emulation
+    pub fn has_audio_output(&self) -> bool {
+        self.apu.borrow().has_output()
+    }
+
+    pub fn is_logging_audio(&self) -> bool {
+        self.audio_log.is_some()
+    }
+
+    /// Log the writes to the sound registers, they are saved as a VGM file at `path`
+    pub fn start_audio_log(&mut self, path: &Path) {
+        self.stop_audio_log();
+        self.apu.borrow_mut().start_write_log();
+        self.audio_log = Some(path.to_path_buf());
+        log::info!("logging the writes to the sound registers");
+    }
+
+    /// Stop the log of the sound registers and save it
+    pub fn stop_audio_log(&mut self) {
+        let writes = self.apu.borrow_mut().stop_write_log();
+        if let (Some(path), Some(writes)) = (self.audio_log.take(), writes) {
+            match writes.save(&path) {
+                Ok(()) => log::info!(
+                    "saved {} writes to the sound registers in {}",
+                    writes.writes().len(),
+                    path.display()
+                ),
+                Err(e) => log::error!("cannot save the audio log in {}: {}", path.display(), e),
+            }
+        }
+    }
+
     fn check_scheduled_stop(&mut self, frame_ended: bool) {
         if let Some(ref mut scheduled) = self.scheduled_stop {
             log::trace!(
@@ -356,6 +816,51 @@ impl Game {
                         self.finish_instruction();
                     }
                 }
+                ScheduledStop::StepOver { return_address, sp } => {
+                    let regs = &self.cpu.registers;
+                    if self.cpu.controller.is_instruction_finished
+                        && regs.pc == *return_address
+                        && regs.sp >= *sp
+                    {
+                        self.emulation_stopped = true;
+                        self.scheduled_stop = None;
+                    }
+                }
+                ScheduledStop::StepOut { sp, returning } => {
+                    if self.cpu.controller.is_instruction_finished {
+                        if *returning && self.cpu.registers.sp > *sp {
+                            self.emulation_stopped = true;
+                            self.scheduled_stop = None;
+                        } else {
+                            // RET, RETI and RET cc
+                            *returning = matches!(
+                                Game::read_debug(&self.addr_bus, self.cpu.registers.pc),
+                                0xc9 | 0xd9 | 0xc0 | 0xc8 | 0xd0 | 0xd8
+                            );
+                        }
+                    }
+                }
+                ScheduledStop::RunTo(address) => {
+                    if self.cpu.controller.is_instruction_finished
+                        && self.cpu.registers.pc == *address
+                    {
+                        self.emulation_stopped = true;
+                        self.scheduled_stop = None;
+                    }
+                }
+                ScheduledStop::Interrupt => {
+                    if self.cpu.controller.interrupt_dispatched.is_some() {
+                        self.finish_instruction();
+                    }
+                }
+                ScheduledStop::Line { line, previous } => {
+                    let ly = Game::read_debug(&self.addr_bus, LY_ADDRESS);
+                    if ly == *line && *previous != *line {
+                        self.finish_instruction();
+                    } else {
+                        *previous = ly;
+                    }
+                }
             }
         }
     }
@@ -369,6 +874,26 @@ impl Game {
         }
     }
 
+    /// Read a byte on the bus without triggering the watchpoints
+    fn read_debug(bus: &AddressBus, address: u16) -> u8 {
+        bus.read(address, Some(Source::Debugger)).unwrap_or(0xff)
+    }
+
+    /// Return the address following the `CALL` or `RST` instruction at PC
+    fn step_over_return_address(&self) -> Option<u16> {
+        use gb_roms::opcode::{list::Opcode, OpcodeGenerator};
+
+        let pc = self.cpu.registers.pc;
+        let bytes = (0..3).map(|offset| Game::read_debug(&self.addr_bus, pc.wrapping_add(offset)));
+        match OpcodeGenerator::from(bytes).next() {
+            Some(Ok((
+                Opcode::Call(_) | Opcode::CallConditional(_, _) | Opcode::Restart(_),
+                bytes,
+            ))) => Some(pc.wrapping_add(bytes.len() as u16)),
+            _ => None,
+        }
+    }
+
     pub fn update_scheduled_stop(&mut self, flow: std::ops::ControlFlow<Until>) {
         use std::ops::ControlFlow::{Break, Continue};
         match flow {
@@ -402,6 +927,42 @@ impl Game {
                     })),
                 ));
             }
+            Break(Until::StepOver) => {
+                self.emulation_stopped = false;
+                self.scheduled_stop = Some(match self.step_over_return_address() {
+                    Some(return_address) => ScheduledStop::StepOver {
+                        return_address,
+                        sp: self.cpu.registers.sp,
+                    },
+                    None => ScheduledStop::Step(1),
+                });
+            }
+            Break(Until::StepOut) => {
+                self.emulation_stopped = false;
+                self.scheduled_stop = Some(ScheduledStop::StepOut {
+                    sp: self.cpu.registers.sp,
+                    returning: false,
+                });
+            }
+            Break(Until::RunTo(address)) => {
+                self.emulation_stopped = false;
+                self.scheduled_stop = Some(ScheduledStop::RunTo(address));
+            }
+            Break(Until::Interrupt) => {
+                self.emulation_stopped = false;
+                self.cpu.controller.interrupt_dispatched = None;
+                self.scheduled_stop = Some(ScheduledStop::Interrupt);
+            }
+            Break(Until::VBlank) => {
+                self.update_scheduled_stop(Break(Until::Line(GB_SCREEN_HEIGHT as u8)));
+            }
+            Break(Until::Line(line)) => {
+                self.emulation_stopped = false;
+                self.scheduled_stop = Some(ScheduledStop::Line {
+                    line,
+                    previous: Game::read_debug(&self.addr_bus, LY_ADDRESS),
+                });
+            }
         }
     }
 
@@ -468,6 +1029,7 @@ impl Game {
         self.load_hdma(state.hdma)?;
 
         self.mbc.borrow_mut().load(state.mbcs)?;
+        self.forget_history();
         Ok(())
     }
 
@@ -530,56 +1092,87 @@ impl Game {
         Ok(())
     }
 
-    #[cfg(feature = "registers_logs")]
-    fn log_registers_to_file(&mut self) -> std::io::Result<()> {
-        use std::io::Write;
-        let file = &mut self.logs_file;
-        let timer_borrow = self.timer.borrow();
-
-        if let Err(e) = writeln!(
-            file,
-            "{} ({:02X} {:02X} {:02X} {:02X}) TIMA: {:02X} TAC: {:02X} CLK: {:04X}",
-            self.cpu.registers,
-            <AddressBus as Bus<u8>>::read(&self.addr_bus, self.cpu.registers.pc, None)
-                .unwrap_or(0xff),
-            <AddressBus as Bus<u8>>::read(&self.addr_bus, self.cpu.registers.pc + 1, None)
-                .unwrap_or(0xff),
-            <AddressBus as Bus<u8>>::read(&self.addr_bus, self.cpu.registers.pc + 2, None)
-                .unwrap_or(0xff),
-            <AddressBus as Bus<u8>>::read(&self.addr_bus, self.cpu.registers.pc + 3, None)
-                .unwrap_or(0xff),
-            timer_borrow.tima,
-            <AddressBus as Bus<u8>>::read(&self.addr_bus, 0xff07, None).unwrap_or(0xff),
-            timer_borrow.system_clock
-        ) {
-            log::error!("Couldn't write to file: {}", e);
-        }
-        Ok(())
+    fn is_cpu_halted(&self) -> bool {
+        matches!(self.cpu.controller.mode, CpuMode::Halt | CpuMode::Stop)
     }
 
-    #[cfg(feature = "registers_logs")]
-    fn create_new_file() -> std::io::Result<BufWriter<File>> {
-        use std::{env, fs::OpenOptions};
-
-        let registers_logs = {
-            use env::{temp_dir, var};
-            let mut project_path =
-                var("LOG_DIR").map_or_else(|_| temp_dir(), std::path::PathBuf::from);
-            project_path.push("registers.log");
-            project_path
+    /// Set the routines executing the instruction about to be executed,
+    /// from the entry point of the rom to the routine containing PC
+    fn profile_instruction(&mut self) {
+        let rom_bank = self.mbc.borrow().rom_bank();
+        let pc = self.cpu.registers.pc;
+        let bank = if (0x4000..0x8000).contains(&pc) {
+            rom_bank
+        } else {
+            0
         };
+        let symbols = &self.symbols;
+        let callers = self
+            .cpu
+            .controller
+            .call_stack
+            .frames()
+            .iter()
+            .map(|frame| Routine::from_frame(symbols, frame, rom_bank));
+        self.profiler.enter(
+            Hotspot { bank, address: pc },
+            std::iter::once(Routine::ENTRY_POINT)
+                .chain(callers)
+                .chain(Routine::from_label(symbols, bank, pc)),
+        );
+    }
 
-        log::info!("opening registers log at {}", registers_logs.display());
-        let file = OpenOptions::new()
-            .write(true)
-            .create(true)
-            .open(registers_logs)?;
-        Ok(BufWriter::new(file))
+    /// Mark the bytes of the instruction about to be executed in the code/data log
+    fn log_instruction(&self) {
+        use gb_roms::OpcodeGenerator;
+
+        let mbc = self.mbc.borrow();
+        if !mbc.code_data_log().map_or(false, CodeDataLog::is_recording) {
+            return;
+        }
+        let pc = self.cpu.registers.pc;
+        let instruction = (pc < 0x8000).then(|| {
+            let bytes =
+                (0..3).map(|offset| Game::read_debug(&self.addr_bus, pc.wrapping_add(offset)));
+            match OpcodeGenerator::from(bytes).next() {
+                Some(Ok((_, bytes))) => (pc, bytes.len() as u16),
+                _ => (pc, 1),
+            }
+        });
+        mbc.fetch_instruction(instruction);
+    }
+
+    /// Trace the instruction about to be executed
+    fn trace_instruction(&mut self) {
+        let regs = self.cpu.registers;
+        let rom_bank = self.mbc.borrow().rom_bank();
+        let bus = &self.addr_bus;
+        let timer = &self.timer;
+
+        self.tracer.trace(regs.pc, rom_bank, || {
+            let timer = timer.borrow();
+            TraceEntry {
+                af: regs.af,
+                bc: regs.bc,
+                de: regs.de,
+                hl: regs.hl,
+                sp: regs.sp,
+                pc: regs.pc,
+                rom_bank,
+                pcmem: [0, 1, 2, 3]
+                    .map(|offset| Game::read_debug(bus, regs.pc.wrapping_add(offset))),
+                tima: timer.tima,
+                tac: Game::read_debug(bus, 0xff07),
+                clock: timer.system_clock,
+            }
+        });
     }
 }
 
 impl Drop for Game {
     fn drop(&mut self) {
+        self.stop_recording();
+        self.stop_audio_log();
         if self.auto_save == Some(AutoSave::Ram) || self.auto_save == Some(AutoSave::RamTimer) {
             use anyhow::Error;
             use rmp_serde::encode::write_named;
@@ -614,6 +1207,185 @@ impl DebugOperations for Game {
     fn cycle(&self) -> usize {
         self.cycle_count
     }
+
+    fn is_stopped(&self) -> bool {
+        self.emulation_stopped
+    }
+
+    fn rom_bank(&self) -> usize {
+        self.mbc.borrow().rom_bank()
+    }
+
+    fn ram_bank(&self) -> usize {
+        self.mbc.borrow().ram_bank()
+    }
+
+    fn rom_bank_count(&self) -> usize {
+        self.mbc.borrow().rom_bank_count()
+    }
+
+    fn read_rom(&self, bank: usize, offset: u16) -> u8 {
+        self.mbc
+            .borrow()
+            .read_rom_bank(bank, offset)
+            .unwrap_or(0xff)
+    }
+
+    fn bank_count(&self, area: BankedArea) -> usize {
+        match area {
+            BankedArea::Rom => self.mbc.borrow().rom_bank_count(),
+            BankedArea::CartRam => self.mbc.borrow().ram_bank_count(),
+            BankedArea::Vram => self.ppu.vram_bank_count(),
+            BankedArea::Wram => self.wram.borrow().bank_count(),
+            BankedArea::Oam | BankedArea::WaveRam => 1,
+            BankedArea::BgPalettes | BankedArea::ObjPalettes => usize::from(self.cgb_mode),
+        }
+    }
+
+    fn read_bank(&self, area: BankedArea, bank: usize, offset: u16) -> u8 {
+        let value = match area {
+            BankedArea::Rom => self.mbc.borrow().read_rom_bank(bank, offset),
+            BankedArea::CartRam => self.mbc.borrow().read_ram_bank(bank, offset),
+            BankedArea::Vram => self.ppu.read_vram_bank(bank, offset as usize),
+            BankedArea::Wram => self.wram.borrow().read_bank(bank, offset),
+            BankedArea::Oam => self.ppu.read_oam(offset as usize),
+            BankedArea::WaveRam => self
+                .addr_bus
+                .read(area.start(bank) + offset, Some(Source::Debugger))
+                .ok(),
+            BankedArea::BgPalettes => {
+                let lcd_reg = self.ppu.lcd_reg.borrow();
+                lcd_reg
+                    .pal_cgb
+                    .background_data()
+                    .get(offset as usize)
+                    .copied()
+            }
+            BankedArea::ObjPalettes => {
+                let lcd_reg = self.ppu.lcd_reg.borrow();
+                lcd_reg.pal_cgb.object_data().get(offset as usize).copied()
+            }
+        };
+        value.unwrap_or(0xff)
+    }
+
+    fn screen(&self) -> Screen {
+        Screen {
+            width: GB_SCREEN_WIDTH,
+            height: GB_SCREEN_HEIGHT,
+            pixels: self.ppu.pixels().iter().flatten().copied().collect(),
+        }
+    }
+
+    fn symbols(&self) -> &SymbolTable {
+        &self.symbols
+    }
+
+    fn call_stack(&self) -> &CallStack {
+        &self.cpu.controller.call_stack
+    }
+
+    fn tracer(&mut self) -> &mut Tracer {
+        &mut self.tracer
+    }
+
+    fn timeline(&mut self) -> &mut Timeline {
+        &mut self.timeline
+    }
+
+    fn profiler(&mut self) -> &mut Profiler {
+        &mut self.profiler
+    }
+
+    fn instruction_count(&self) -> u64 {
+        self.instruction_count
+    }
+
+    fn snapshot_before(&self, instruction: u64) -> Option<u64> {
+        instruction
+            .checked_sub(1)
+            .and_then(|instruction| self.history.latest(instruction))
+            .map(|(taken, _)| *taken)
+    }
+
+    fn rewind(&mut self, instruction: u64) -> bool {
+        Game::rewind(self, instruction)
+    }
+
+    fn step_instruction(&mut self) {
+        Game::step_instruction(self)
+    }
+
+    fn frozen(&self) -> &[(u16, u8)] {
+        &self.frozen
+    }
+
+    fn set_frozen(&mut self, frozen: Vec<(u16, u8)>) {
+        self.frozen = frozen;
+        // the frames already executed were not frozen the same way
+        self.forget_history();
+    }
+
+    fn code_data_log(&self) -> Option<Ref<'_, CodeDataLog>> {
+        let mbc = self.mbc.borrow();
+        mbc.code_data_log()?;
+        Some(Ref::map(mbc, |mbc| {
+            mbc.code_data_log()
+                .expect("the code/data log was just checked")
+        }))
+    }
+
+    fn set_code_data_logging(&mut self, recording: bool) {
+        if recording {
+            self.mbc.borrow_mut().start_logging();
+        } else {
+            self.mbc.borrow_mut().stop_logging();
+        }
+    }
+
+    fn clear_code_data_log(&mut self) {
+        self.mbc.borrow_mut().clear_log();
+    }
+
+    fn audio_channels(&self) -> Vec<ChannelState> {
+        let apu = self.apu.borrow();
+        (0..SOUND_CHANNELS)
+            .map(|index| channel_state(&apu, index))
+            .collect()
+    }
+
+    fn set_channel_muted(&mut self, channel: usize, muted: bool) {
+        self.apu.borrow_mut().set_muted(channel, muted);
+    }
+
+    fn set_channel_soloed(&mut self, channel: usize, soloed: bool) {
+        self.apu.borrow_mut().set_soloed(channel, soloed);
+    }
+
+    fn set_audio_scope(&mut self, enabled: bool) {
+        self.apu.borrow_mut().set_scope(enabled);
+    }
+
+    fn audio_scope(&self) -> Vec<ScopePoint> {
+        self.apu
+            .borrow()
+            .scope()
+            .map_or_else(Vec::new, |scope| scope.points().copied().collect())
+    }
+}
+
+impl WatchpointDebugOperations for Game {
+    fn watchpoints(&self, owner: Owner) -> &[Watchpoint] {
+        self.addr_bus.watchpoints.list(owner)
+    }
+
+    fn set_watchpoints(&mut self, owner: Owner, watchpoints: Vec<Watchpoint>) {
+        self.addr_bus.watchpoints.set(owner, watchpoints);
+    }
+
+    fn take_watchpoint_hits(&mut self, owner: Owner) -> Vec<WatchpointHit> {
+        self.addr_bus.watchpoints.take_hits(owner)
+    }
 }
 
 impl MemoryDebugOperations for Game {
@@ -625,6 +1397,13 @@ impl MemoryDebugOperations for Game {
                 0xff
             })
     }
+
+    fn write(&mut self, index: u16, value: u8) {
+        if let Err(err) = self.addr_bus.write(index, value, Some(Source::Debugger)) {
+            log::trace!("[DBG-OPS] bus write error at {}: {:?}", index, err);
+        }
+        self.state_modified();
+    }
 }
 
 macro_rules! read_bus_reg {
@@ -651,6 +1430,19 @@ impl RegisterDebugOperations for Game {
         }
     }
 
+    fn cpu_set(&mut self, key: CpuRegs, value: u16) {
+        let registers = &mut self.cpu.registers;
+        match key {
+            CpuRegs::AF => registers.af = value & 0xfff0,
+            CpuRegs::BC => registers.bc = value,
+            CpuRegs::DE => registers.de = value,
+            CpuRegs::HL => registers.hl = value,
+            CpuRegs::SP => registers.sp = value,
+            CpuRegs::PC => registers.pc = value,
+        }
+        self.state_modified();
+    }
+
     fn ppu_get(&self, key: PpuRegs) -> RegisterValue {
         use gb_bus::io_reg_area::IORegArea::{
             Bgp, Dma, LcdControl, LcdStat, Ly, Lyc, Obp0, Obp1, Scx, Scy, Wx, Wy,
@@ -0,0 +1,89 @@
+use gb_apu::apu::ApuSnapshot;
+use gb_bus::{generic::SimpleRW, WorkingRam};
+use gb_cpu::{
+    io_registers::IORegisters, microcode::controller::MicrocodeController, registers::Registers,
+};
+use gb_dma::{dma, hdma::Hdma};
+use gb_joypad::Joypad;
+use gb_ppu::PpuSnapshot;
+use gb_roms::controllers::{Full, GenericState};
+use gb_timer::Timer;
+
+use crate::game::Game;
+
+/// Copy of the machine kept in memory to rewind the emulation.
+///
+/// The snapshot is taken between two instructions. The output of the APU, the serial port
+/// and the mapping of the boot ROM are not part of it: they are left as is when rewinding.
+pub(super) struct Snapshot {
+    cycle_count: usize,
+    frame_cycle: usize,
+    registers: Registers,
+    controller: MicrocodeController,
+    io_regs: IORegisters,
+    halted_dma: bool,
+    ppu: PpuSnapshot,
+    timer: Timer,
+    apu: ApuSnapshot,
+    dma: dma::State,
+    hdma: Hdma,
+    joypad: Joypad,
+    mbc: GenericState<Full>,
+    wram: WorkingRam,
+    hram: SimpleRW<0x80>,
+}
+
+impl Snapshot {
+    pub fn cycle_count(&self) -> usize {
+        self.cycle_count
+    }
+}
+
+impl From<&Game> for Snapshot {
+    fn from(game: &Game) -> Self {
+        Self {
+            cycle_count: game.cycle_count,
+            frame_cycle: game.clock.curr_frame_cycle,
+            registers: game.cpu.registers,
+            controller: game.cpu.controller.clone(),
+            io_regs: *game.cpu.io_regs.borrow(),
+            halted_dma: game.cpu.halted_dma,
+            ppu: game.ppu.snapshot(),
+            timer: *game.timer.borrow(),
+            apu: game.apu.borrow().snapshot(),
+            dma: game.dma.borrow().state,
+            hdma: *game.hdma.borrow(),
+            joypad: game.joypad.borrow().clone(),
+            mbc: game.mbc.borrow().save(),
+            wram: game.wram.borrow().clone(),
+            hram: game.hram.borrow().clone(),
+        }
+    }
+}
+
+impl Game {
+    /// Put the machine back in the state of the snapshot, the components shared
+    /// through the buses are updated in place
+    pub(super) fn restore(&mut self, snapshot: &Snapshot) {
+        self.cycle_count = snapshot.cycle_count;
+        self.clock.curr_frame_cycle = snapshot.frame_cycle;
+        self.cpu.registers = snapshot.registers;
+        // the call stack is tracked since the debugger was opened, not since the snapshot
+        let tracking = self.cpu.controller.call_stack.is_tracking();
+        self.cpu.controller = snapshot.controller.clone();
+        self.cpu.controller.call_stack.set_tracking(tracking);
+        *self.cpu.io_regs.borrow_mut() = snapshot.io_regs;
+        self.cpu.halted_dma = snapshot.halted_dma;
+        self.ppu.restore(&snapshot.ppu);
+        *self.timer.borrow_mut() = snapshot.timer;
+        self.apu.borrow_mut().restore(&snapshot.apu);
+        self.dma.borrow_mut().state = snapshot.dma;
+        *self.hdma.borrow_mut() = snapshot.hdma;
+        *self.joypad.borrow_mut() = snapshot.joypad.clone();
+        if let Err(e) = self.mbc.borrow_mut().load(snapshot.mbc.clone()) {
+            log::error!("cannot restore the state of the mbc: {}", e);
+        }
+        *self.wram.borrow_mut() = snapshot.wram.clone();
+        *self.hram.borrow_mut() = snapshot.hram.clone();
+    }
+}
@@ -1,3 +1,9 @@
+use gb_apu::{
+    apu::Apu,
+    channel::{lfsr::WidthMode, volume_envelope::Direction},
+    ChannelType,
+};
+use gb_dbg::audio::{ChannelState, Envelope, Sweep};
 use gb_roms::{
     controllers::{generate_rom_controller, Generic, GenericState, Partial},
     Header,
@@ -36,3 +42,50 @@ pub(crate) fn mbc_with_save_state(
 
     Ok(mbc)
 }
+
+/// Decode the state of the sound channel `index` for the audio debugger
+pub(crate) fn channel_state(apu: &Apu, index: usize) -> ChannelState {
+    let channel = &apu.channels()[index];
+    let has_period = channel.channel_type != ChannelType::Noise;
+    let length = channel.length_counter();
+    let wave = channel.programmable_wave();
+    let wave_ram = wave.map(|wave| {
+        let mut ram = [0; 16];
+        for (i, byte) in ram.iter_mut().enumerate() {
+            *byte = wave.get_samples_at_index(i * 2);
+        }
+        ram
+    });
+    ChannelState {
+        enabled: channel.enabled,
+        dac_enabled: channel.dac_enabled,
+        muted: apu.is_muted(index),
+        soloed: apu.is_soloed(index),
+        frequency: channel.tone_frequency(),
+        period: has_period.then(|| channel.timer().frequency),
+        duty: channel.duty().map(|duty| duty.pattern_index),
+        envelope: channel.volume_envelope().map(|envelope| Envelope {
+            volume: envelope.volume,
+            initial_volume: envelope.initial_volume,
+            increase: envelope.envelope_direction == Direction::Inc,
+            pace: envelope.period,
+        }),
+        length: if length.enabled {
+            Some(length.counter)
+        } else {
+            None
+        },
+        sweep: channel.sweep().map(|sweep| Sweep {
+            enabled: sweep.enabled,
+            pace: sweep.period,
+            decrease: sweep.direction == Direction::Dec,
+            shift: sweep.shift_nb,
+            shadow_period: sweep.shadow_frequency,
+        }),
+        wave_level: wave.map(|wave| (wave.bits >> 5) & 0b11),
+        wave_ram,
+        short_lfsr: channel
+            .lfsr()
+            .map(|lfsr| lfsr.width_mode == WidthMode::Width7Bits),
+    }
+}
@@ -0,0 +1,392 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::{cell::RefCell, fs::File, io::BufWriter, ops::DerefMut, rc::Rc};
+
+use gb_apu::{apu::Apu, OUTPUT_CHANNELS, SAMPLE_RATES};
+use gb_bus::{generic::SimpleRW, AddressBus, Bus, IORegArea, IORegBus, Source, WorkingRam};
+use gb_clock::{counted_cycles, not_counted_cycles, Clock};
+use gb_cpu::{cpu::Cpu, microcode::controller::Mode as CpuMode, new_cpu, registers::Registers};
+use gb_dbg::wav::WavWriter;
+use gb_roms::gbs::{GbsHeader, GbsRom, IDLE_ADDRESS};
+use gb_timer::Timer;
+
+use crate::config::GbsOptions;
+use crate::constant::AUDIO_BUFFER_SIZE;
+
+/// Duration of a track before its fade-out when the file doesn't tell, in seconds
+pub const DEFAULT_DURATION: u32 = 150;
+/// Duration of the fade-out at the end of a track, in seconds
+pub const DEFAULT_FADE: u32 = 8;
+
+/// Address of the IF register
+const IF_ADDRESS: u16 = 0xff0f;
+const TIMER_INTERRUPT: u8 = 0b100;
+
+macro_rules! cell {
+    ($e:expr) => {
+        Rc::new(RefCell::new($e))
+    };
+}
+
+/// Player of a GBS file, it runs the music driver of the file on a cpu, a timer
+/// and an apu, the play routine is called at each frame or at each timer interrupt.
+pub struct GbsPlayer {
+    pub header: GbsHeader,
+    file: Vec<u8>,
+    pub apu: Rc<RefCell<Apu>>,
+    system: System,
+    track: u8,
+    /// Duration of each track before its fade-out, in seconds
+    pub durations: Vec<u32>,
+    /// Duration of the fade-out at the end of the tracks, in seconds
+    pub fade: u32,
+    /// Output volume chosen by the user, lowered during the fade-out
+    pub volume: f32,
+    /// Start the next track once the current one is over
+    pub autoplay: bool,
+    pub paused: bool,
+    /// Cycles executed since the start of the track
+    elapsed: usize,
+    /// Set when the play routine has to be called once the cpu is idle
+    play_pending: bool,
+    /// VGM file where the writes to the sound registers are saved once the log stops
+    audio_log: Option<PathBuf>,
+}
+
+/// Hardware running the routines of the file, rebuilt at the start of each track
+struct System {
+    cpu: Cpu,
+    clock: Clock,
+    timer: Rc<RefCell<Timer>>,
+    addr_bus: AddressBus,
+}
+
+impl GbsPlayer {
+    /// Load a GBS file and play it on the audio output
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let buffer: Arc<Mutex<Vec<f32>>> = Arc::new(Mutex::new(Vec::with_capacity(
+            AUDIO_BUFFER_SIZE * OUTPUT_CHANNELS,
+        )));
+        let (stream, sample_rate) = match Apu::init_audio_output(buffer.clone()) {
+            Ok((stream, sample_rate)) => (Some(stream), sample_rate),
+            Err(e) => {
+                log::warn!("no audio output, the music will be silent: {}", e);
+                (None, SAMPLE_RATES[0])
+            }
+        };
+        let apu = Apu::new(buffer, stream, sample_rate, false);
+        GbsPlayer::new(path, apu)
+    }
+
+    /// Load a GBS file without audio output, its tracks can only be rendered
+    pub fn headless(path: &Path) -> anyhow::Result<Self> {
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+        GbsPlayer::new(path, Apu::new(buffer, None, SAMPLE_RATES[0], false))
+    }
+
+    fn new(path: &Path, apu: Apu) -> anyhow::Result<Self> {
+        let file = std::fs::read(path)?;
+        let header = GbsHeader::from_bytes(&file)?;
+        log::debug!("gbs header: {:?}", header);
+        let apu = cell!(apu);
+        let track = header.first_track();
+        let system = System::new(&header, &file, &apu, track);
+
+        Ok(Self {
+            durations: vec![DEFAULT_DURATION; usize::from(header.song_count)],
+            header,
+            file,
+            apu,
+            system,
+            track,
+            fade: DEFAULT_FADE,
+            volume: 0.7,
+            autoplay: true,
+            paused: false,
+            elapsed: 0,
+            play_pending: false,
+            audio_log: None,
+        })
+    }
+
+    /// Return the track being played, from 0
+    pub fn track(&self) -> u8 {
+        self.track
+    }
+
+    /// Restart the player on `track`, from 0
+    pub fn select_track(&mut self, track: u8) {
+        self.track = track.min(self.header.song_count.saturating_sub(1));
+        self.system = System::new(&self.header, &self.file, &self.apu, self.track);
+        self.elapsed = 0;
+        self.play_pending = false;
+        self.paused = false;
+    }
+
+    /// Return the duration of `track` with its fade-out, in seconds
+    pub fn track_length(&self, track: u8) -> u32 {
+        self.duration(track) + self.fade
+    }
+
+    fn duration(&self, track: u8) -> u32 {
+        self.durations
+            .get(usize::from(track))
+            .copied()
+            .unwrap_or(DEFAULT_DURATION)
+    }
+
+    /// Return the time since the start of the track, in seconds
+    pub fn elapsed(&self) -> f32 {
+        self.elapsed as f32 / Clock::CYCLES_PER_SECOND as f32
+    }
+
+    pub fn is_track_over(&self) -> bool {
+        self.elapsed >= self.track_length(self.track) as usize * Clock::CYCLES_PER_SECOND
+    }
+
+    /// Return the gain of the fade-out, 1 before it starts and 0 at the end of the track
+    fn gain(&self) -> f32 {
+        let faded = self.elapsed() - self.duration(self.track) as f32;
+        if faded <= 0.0 {
+            1.0
+        } else if self.fade == 0 {
+            0.0
+        } else {
+            (1.0 - faded / self.fade as f32).max(0.0)
+        }
+    }
+
+    pub fn is_audio_buffer_full(&self) -> bool {
+        self.apu.borrow().is_buffer_full()
+    }
+
+    /// Return true when the audio output paces the player
+    pub fn has_audio_output(&self) -> bool {
+        self.apu.borrow().has_output()
+    }
+
+    /// Execute a cycle, return false at the end of a frame or when the player is paused
+    pub fn cycle(&mut self) -> bool {
+        if self.paused {
+            return false;
+        }
+        let frame_not_finished = self.run_cycle();
+        if !frame_not_finished {
+            *self.apu.borrow_mut().output_volume() = self.volume * self.gain();
+            if self.is_track_over() {
+                if self.autoplay && self.track + 1 < self.header.song_count {
+                    self.select_track(self.track + 1);
+                } else {
+                    self.paused = true;
+                }
+            }
+        }
+        frame_not_finished
+    }
+
+    fn run_cycle(&mut self) -> bool {
+        let system = &mut self.system;
+        // the interrupts are never dispatched even when the routines enable them,
+        // the dispatch would clear the timer request the play routine is called on
+        system.cpu.io_regs.borrow_mut().master_enable = false;
+        if self.play_pending && system.is_idle() {
+            system.call(self.header.play_address);
+            self.play_pending = false;
+        }
+
+        let frame_not_finished = counted_cycles!(
+            system.clock,
+            &mut system.addr_bus,
+            system.timer.borrow_mut().deref_mut(),
+            &mut system.cpu,
+            self.apu.borrow_mut().deref_mut()
+        );
+        if self.header.double_speed() {
+            not_counted_cycles!(
+                system.clock,
+                &mut system.addr_bus,
+                &mut system.cpu,
+                system.timer.borrow_mut().deref_mut()
+            );
+        }
+
+        if self.header.timer_driven() {
+            // the interrupt isn't dispatched, the play routine is called in its place
+            let interrupts = system.read(IF_ADDRESS);
+            if interrupts & TIMER_INTERRUPT != 0 {
+                system.write(IF_ADDRESS, interrupts & !TIMER_INTERRUPT);
+                self.play_pending = true;
+            }
+        } else if !frame_not_finished {
+            self.play_pending = true;
+        }
+        self.elapsed += 1;
+        frame_not_finished
+    }
+
+    pub fn is_logging_audio(&self) -> bool {
+        self.audio_log.is_some()
+    }
+
+    /// Log the writes to the sound registers, they are saved as a VGM file at `path`
+    pub fn start_audio_log(&mut self, path: &Path) {
+        self.stop_audio_log();
+        self.apu.borrow_mut().start_write_log();
+        self.audio_log = Some(path.to_path_buf());
+    }
+
+    /// Stop the log of the sound registers and save it
+    pub fn stop_audio_log(&mut self) {
+        let writes = self.apu.borrow_mut().stop_write_log();
+        if let (Some(path), Some(writes)) = (self.audio_log.take(), writes) {
+            match writes.save(&path) {
+                Ok(()) => log::info!(
+                    "saved {} writes to the sound registers in {}",
+                    writes.writes().len(),
+                    path.display()
+                ),
+                Err(e) => log::error!("cannot save the audio log in {}: {}", path.display(), e),
+            }
+        }
+    }
+
+    /// Render `track` with its fade-out in a WAV file
+    pub fn render(&mut self, track: u8, path: &Path) -> anyhow::Result<()> {
+        self.select_track(track);
+        let sample_rate = self.apu.borrow().sample_rate().0;
+        let file = File::create(path)
+            .map_err(|e| anyhow::anyhow!("cannot create {}: {}", path.display(), e))?;
+        let mut wav = WavWriter::new(BufWriter::new(file), sample_rate)?;
+
+        self.apu.borrow_mut().set_recording(true);
+        while !self.is_track_over() {
+            if !self.run_cycle() {
+                self.write_recorded(&mut wav)?;
+            }
+        }
+        // the samples of the last frame, cut by the end of the track
+        self.write_recorded(&mut wav)?;
+        self.apu.borrow_mut().set_recording(false);
+        wav.finish()?;
+        Ok(())
+    }
+
+    /// Write the samples recorded by the apu, with the gain of the fade-out
+    fn write_recorded(&self, wav: &mut WavWriter<BufWriter<File>>) -> std::io::Result<()> {
+        let gain = self.gain();
+        let samples: Vec<[f32; OUTPUT_CHANNELS]> = self
+            .apu
+            .borrow_mut()
+            .take_recorded()
+            .into_iter()
+            .map(|frame| frame.map(|sample| sample * gain))
+            .collect();
+        wav.write_frames(&samples)
+    }
+}
+
+impl System {
+    fn new(header: &GbsHeader, file: &[u8], apu: &Rc<RefCell<Apu>>, track: u8) -> Self {
+        let mut io_bus = IORegBus::default();
+        let mut bus = AddressBus::default();
+
+        bus.with_rom(cell!(GbsRom::new(header, file)));
+        bus.with_vram(cell!(SimpleRW::<0x2000>::default()));
+        bus.with_ext_ram(cell!(SimpleRW::<0x2000>::default()));
+        bus.with_ram(cell!(WorkingRam::new(false)));
+        bus.with_oam(cell!(SimpleRW::<0xa0>::default()));
+        bus.with_hram(cell!(SimpleRW::<0x80>::default()));
+
+        let (mut cpu, cpu_io_reg) = new_cpu(false);
+        cpu.set_registers(Registers::DMG);
+        io_bus.with_area(IORegArea::IF, cpu_io_reg.clone());
+        bus.with_ie_reg(cpu_io_reg);
+
+        let timer = cell!(Timer::default());
+        io_bus.with_timer(timer.clone());
+        io_bus.with_sound(apu.clone());
+        bus.with_io_reg(cell!(io_bus));
+
+        let mut system = Self {
+            cpu,
+            clock: Clock::default(),
+            timer,
+            addr_bus: bus,
+        };
+        // restart the apu from its power on state
+        for (address, value) in [
+            (0xff26, 0x00),
+            (0xff26, 0x80),
+            (0xff25, 0xff),
+            (0xff24, 0x77),
+            (0xff06, header.timer_modulo),
+            (0xff07, header.timer_control),
+        ] {
+            system.write(address, value);
+        }
+        system.cpu.registers.sp = header.stack_pointer;
+        system.cpu.registers.af = u16::from(track) << 8;
+        system.call(header.init_address);
+        system
+    }
+
+    /// Return true when the cpu waits in the idle loop, the last routine returned
+    fn is_idle(&self) -> bool {
+        self.cpu.controller.is_instruction_finished && self.cpu.registers.pc == IDLE_ADDRESS
+    }
+
+    /// Call the routine at `address`, it returns to the idle loop
+    fn call(&mut self, address: u16) {
+        let sp = self.cpu.registers.sp.wrapping_sub(2);
+        let [low, high] = IDLE_ADDRESS.to_le_bytes();
+        self.write(sp, low);
+        self.write(sp.wrapping_add(1), high);
+        self.cpu.registers.sp = sp;
+        self.cpu.registers.pc = address;
+        self.cpu.controller.mode = CpuMode::Normal;
+    }
+
+    fn read(&self, address: u16) -> u8 {
+        self.addr_bus
+            .read(address, Some(Source::Debugger))
+            .unwrap_or(0xff)
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        if let Err(e) = self.addr_bus.write(address, value, None) {
+            log::error!("cannot write {:02x} at {:04x}: {}", value, address, e);
+        }
+    }
+}
+
+/// Render the track selected by `options` of a GBS file in a WAV file, without opening a window.
+/// The writes to the sound registers are saved in `vgm` when it's set
+pub fn render(
+    path: &Path,
+    options: &GbsOptions,
+    output: &Path,
+    vgm: Option<&Path>,
+) -> anyhow::Result<()> {
+    let mut player = GbsPlayer::headless(path)?;
+    options.apply(&mut player);
+    let track = player.track();
+    log::info!(
+        "rendering the track {} of {} in {} ({} s)",
+        track + 1,
+        path.display(),
+        output.display(),
+        player.track_length(track)
+    );
+    if let Some(vgm) = vgm {
+        player.start_audio_log(vgm);
+    }
+    let rendered = player.render(track, output);
+    player.stop_audio_log();
+    rendered
+}
+
+impl Drop for GbsPlayer {
+    fn drop(&mut self) {
+        self.stop_audio_log();
+    }
+}
@@ -1,9 +1,12 @@
 mod bios_configuration;
+mod cheats;
 mod config;
 mod constant;
 mod context;
 mod custom_event;
+mod frame_limiter;
 mod game;
+mod gbs;
 mod image;
 mod logger;
 mod path;
@@ -37,6 +40,21 @@ const MAIN_WINDOW_SCALE_FACTOR: u32 = 4;
 fn main() -> Result<(), Error> {
     let config: Config = Config::parse();
     init_logger(config.log_level);
+    if let (Some(rom), Some(output)) = (&config.rom, &config.render) {
+        let status = match gbs::render(
+            rom.as_ref(),
+            &config.gbs_options(),
+            output,
+            config.vgm.as_deref(),
+        ) {
+            Ok(()) => 0,
+            Err(e) => {
+                log::error!("cannot render {}: {}", rom, e);
+                1
+            }
+        };
+        std::process::exit(status);
+    }
 
     let (event_loop, main_window) = init::<WIDTH, HEIGHT, MENU_BAR, MAIN_WINDOW_SCALE_FACTOR>()?;
     let event_loop_proxy = event_loop.create_proxy();
@@ -61,10 +79,14 @@ fn main() -> Result<(), Error> {
         }
         Event::LoopDestroyed => {
             drop(context.game.take());
+            drop(context.gbs.take());
             log::info!("bye bye");
         }
         Event::MainEventsCleared => {
             if let Some(ref mut game) = context.game {
+                if !game.has_audio_output() {
+                    context.frame_limiter.wait();
+                }
                 let mut processing_frame = true;
                 while processing_frame {
                     if !game.is_audio_buffer_full() {
@@ -76,6 +98,13 @@ fn main() -> Result<(), Error> {
                         {
                             game.update_scheduled_stop(status);
                         }
+                        if let Some(status) = context
+                            .gdb
+                            .as_mut()
+                            .and_then(|gdb| gdb.updated_flow_status(game))
+                        {
+                            game.update_scheduled_stop(status);
+                        }
                     } else {
                         log::debug!("audio buffer is full");
                     }
@@ -86,6 +115,9 @@ fn main() -> Result<(), Error> {
                         game.update_scheduled_stop(status);
                     }
                 }
+                if let Some(status) = context.gdb.as_mut().and_then(|gdb| gdb.poll(game)) {
+                    game.update_scheduled_stop(status);
+                }
                 if let Some(ref mut ctx) = context.tilesheet_ctx {
                     ctx.window.request_redraw();
                 }
@@ -96,6 +128,17 @@ fn main() -> Result<(), Error> {
                     ctx.window.request_redraw();
                 }
             }
+            if let Some(ref mut player) = context.gbs {
+                if !player.has_audio_output() {
+                    context.frame_limiter.wait();
+                }
+                let mut processing_frame = true;
+                while processing_frame {
+                    if !player.is_audio_buffer_full() {
+                        processing_frame = player.cycle();
+                    }
+                }
+            }
             context.main_window.window.request_redraw();
             if let Some(ref keybindings) = context.keybindings_ctx {
                 keybindings.window.request_redraw();
@@ -154,5 +197,43 @@ fn handle_custom_event(
         CustomEvent::CloseWindow(window_type) => context.close_window(window_type),
         CustomEvent::ChangedMode(mode) => context.reset_game(mode),
         CustomEvent::ResetGame => context.reset_game(None),
+        CustomEvent::StartRecording(directory) => {
+            if let Some(game) = &mut context.game {
+                if let Err(e) = game.start_recording(&directory) {
+                    log::error!("{}", e);
+                }
+            }
+        }
+        CustomEvent::StopRecording => {
+            if let Some(game) = &mut context.game {
+                game.stop_recording();
+            }
+        }
+        CustomEvent::StartAudioLog(file) => {
+            if let Some(game) = &mut context.game {
+                game.start_audio_log(&file);
+            } else if let Some(player) = &mut context.gbs {
+                player.start_audio_log(&file);
+            }
+        }
+        CustomEvent::StopAudioLog => {
+            if let Some(game) = &mut context.game {
+                game.stop_audio_log();
+            } else if let Some(player) = &mut context.gbs {
+                player.stop_audio_log();
+            }
+        }
+        #[cfg(feature = "save_state")]
+        CustomEvent::SaveState(file) => {
+            if let Some(game) = &context.game {
+                game.save_state(&file);
+            }
+        }
+        #[cfg(feature = "save_state")]
+        CustomEvent::LoadState(file) => {
+            if let Some(game) = &mut context.game {
+                game.load_save_file(&file);
+            }
+        }
     }
 }
@@ -10,6 +10,21 @@ pub fn game_save_path(rom_filename: &str) -> PathBuf {
     root
 }
 
+#[cfg(feature = "save_state")]
+/// Return the default path of the `save state` file of a game
+pub fn game_state_path(rom_filename: &str) -> PathBuf {
+    let mut path = game_save_path(rom_filename);
+    path.set_extension(crate::constant::SAVE_STATE_EXT);
+    path
+}
+
+/// Return the path of the file listing the cheats of a game
+pub fn game_cheats_path(rom_filename: &str) -> PathBuf {
+    let mut path = game_save_path(rom_filename);
+    path.set_extension(crate::constant::CHEATS_EXT);
+    path
+}
+
 /// Return the root path of the config folder
 pub fn root_config_path() -> PathBuf {
     let mut path = if let Some(home_dir) = std::env::var_os("HOME") {
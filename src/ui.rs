@@ -8,9 +8,11 @@ use crate::Context;
 #[cfg(feature = "debug_render")]
 use crate::Game;
 
+mod cheats;
 mod file;
 #[cfg(feature = "fps")]
 mod fps;
+mod gbs;
 mod settings;
 mod tools;
 mod volume;
@@ -84,8 +86,22 @@ pub fn draw_egui(context: &mut Context) {
                     egui::menu::bar(ui, |ui| {
                         ui.set_height(crate::constant::MENU_BAR_SIZE - 1.0);
                         // ui.style_mut().override_text_style = Some(egui::TextStyle::Heading);
-                        file::draw_ui(ui, &context.event_proxy);
+                        let audio_log = match (&context.game, &context.gbs) {
+                            (Some(game), _) => Some(game.is_logging_audio()),
+                            (None, Some(player)) => Some(player.is_logging_audio()),
+                            (None, None) => None,
+                        };
+                        file::draw_ui(
+                            ui,
+                            &context.event_proxy,
+                            context.game.as_ref().map(|game| game.is_recording()),
+                            audio_log,
+                        );
                         tools::draw_ui(ui, &context.event_proxy);
+                        cheats::draw_ui(ui, &mut context.game);
+                        if let Some(player) = &mut context.gbs {
+                            gbs::draw_ui(ui, player);
+                        }
                         settings::draw_ui(
                             ui,
                             &context.event_proxy,
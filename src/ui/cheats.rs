@@ -0,0 +1,85 @@
+use crate::game::Game;
+use egui::{Color32, Id, RichText, Ui};
+
+/// Fields of the cheat being added, kept in the egui memory
+#[derive(Debug, Clone, Default)]
+struct NewCheat {
+    name: String,
+    code: String,
+    error: Option<String>,
+}
+
+pub(crate) fn draw_ui(ui: &mut Ui, game: &mut Option<Game>) {
+    ui.menu_button("Cheats", |ui| {
+        ui.style_mut().override_text_style = None;
+        let game = match game {
+            Some(game) => game,
+            None => {
+                ui.label("no game loaded");
+                return;
+            }
+        };
+
+        let mut modified = false;
+        let mut deletion = None;
+        for (i, cheat) in game.cheats.list.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                modified |= ui.checkbox(&mut cheat.enabled, &cheat.name).changed();
+                ui.label(RichText::new(&cheat.code).monospace());
+                if ui.button(RichText::new("-").color(Color32::RED)).clicked() {
+                    deletion = Some(i);
+                }
+            });
+        }
+        if let Some(i) = deletion {
+            game.cheats.list.remove(i);
+            modified = true;
+        }
+        if game.cheats.list.is_empty() {
+            ui.label("no cheat");
+        }
+
+        ui.separator();
+        let id = Id::new("new_cheat");
+        let mut new = ui
+            .memory()
+            .data
+            .get_temp_mut_or_default::<NewCheat>(id)
+            .clone();
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut new.name)
+                    .desired_width(80.0)
+                    .hint_text("name"),
+            );
+            ui.add(
+                egui::TextEdit::singleline(&mut new.code)
+                    .desired_width(100.0)
+                    .hint_text("ABC-DEF-GHI"),
+            )
+            .on_hover_text("Game Genie (ABC-DEF or ABC-DEF-GHI) or GameShark (01VVLLHH) code");
+            if ui.button("Add").clicked() {
+                let name = if new.name.trim().is_empty() {
+                    new.code.trim().to_string()
+                } else {
+                    new.name.trim().to_string()
+                };
+                match game.cheats.add(name, &new.code) {
+                    Ok(()) => {
+                        new = NewCheat::default();
+                        modified = true;
+                    }
+                    Err(e) => new.error = Some(e.to_string()),
+                }
+            }
+        });
+        if let Some(error) = &new.error {
+            ui.colored_label(Color32::RED, error);
+        }
+        ui.memory().data.insert_temp(id, new);
+
+        if modified {
+            game.update_cheats();
+        }
+    });
+}
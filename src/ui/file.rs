@@ -4,7 +4,14 @@ use winit::event_loop::EventLoopProxy;
 
 use crate::custom_event::CustomEvent;
 
-pub(crate) fn draw_ui(ui: &mut Ui, event_proxy: &EventLoopProxy<CustomEvent>) {
+/// Draw the file menu, `recording` is set when the loaded game is recorded
+/// and `audio_log` when the writes to its sound registers are logged
+pub(crate) fn draw_ui(
+    ui: &mut Ui,
+    event_proxy: &EventLoopProxy<CustomEvent>,
+    recording: Option<bool>,
+    audio_log: Option<bool>,
+) {
     ui.menu_button("File", |ui| {
         ui.style_mut().override_text_style = None;
         if ui.button("Load").clicked() {
@@ -13,6 +20,7 @@ pub(crate) fn draw_ui(ui: &mut Ui, event_proxy: &EventLoopProxy<CustomEvent>) {
                     &std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("/")),
                 )
                 .add_filter("rom", &crate::constant::PREFERRED_ROM_EXTS)
+                .add_filter("gbs", &[crate::constant::GBS_EXT])
                 .show_open_single_file();
             log::debug!("picked rom file {file:?}");
             if let Ok(Some(path)) = file {
@@ -21,6 +29,52 @@ pub(crate) fn draw_ui(ui: &mut Ui, event_proxy: &EventLoopProxy<CustomEvent>) {
                     .expect("cannot send load file event");
             }
         }
+        ui.separator();
+        if recording == Some(true) {
+            if ui.button("Stop recording").clicked() {
+                event_proxy
+                    .send_event(CustomEvent::StopRecording)
+                    .expect("cannot send stop recording event");
+            }
+        } else if ui
+            .add_enabled(recording.is_some(), egui::Button::new("Record"))
+            .clicked()
+        {
+            let directory = FileDialog::new()
+                .set_location(
+                    &std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("/")),
+                )
+                .show_open_single_dir();
+            log::debug!("picked recording directory {directory:?}");
+            if let Ok(Some(path)) = directory {
+                event_proxy
+                    .send_event(CustomEvent::StartRecording(path))
+                    .expect("cannot send start recording event");
+            }
+        }
+        if audio_log == Some(true) {
+            if ui.button("Stop audio log").clicked() {
+                event_proxy
+                    .send_event(CustomEvent::StopAudioLog)
+                    .expect("cannot send stop audio log event");
+            }
+        } else if ui
+            .add_enabled(audio_log.is_some(), egui::Button::new("Log audio (VGM)"))
+            .clicked()
+        {
+            let file = FileDialog::new()
+                .set_location(
+                    &std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("/")),
+                )
+                .add_filter("vgm", &["vgm"])
+                .show_save_single_file();
+            log::debug!("picked audio log file {file:?}");
+            if let Ok(Some(path)) = file {
+                event_proxy
+                    .send_event(CustomEvent::StartAudioLog(path))
+                    .expect("cannot send start audio log event");
+            }
+        }
         #[cfg(feature = "save_state")]
         {
             ui.separator();
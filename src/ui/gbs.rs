@@ -0,0 +1,90 @@
+use egui::{RichText, Ui};
+
+use crate::gbs::GbsPlayer;
+
+/// Draw the menu of the loaded GBS file: its tracks, their durations and the playback
+pub(crate) fn draw_ui(ui: &mut Ui, player: &mut GbsPlayer) {
+    ui.menu_button("GBS", |ui| {
+        ui.style_mut().override_text_style = None;
+        let header = &player.header;
+        ui.heading(&header.title);
+        ui.label(&header.author);
+        ui.label(&header.copyright);
+        ui.separator();
+
+        let track = player.track();
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(track > 0, egui::Button::new("Previous"))
+                .clicked()
+            {
+                player.select_track(track - 1);
+            }
+            if ui
+                .button(if player.paused { "Play" } else { "Pause" })
+                .clicked()
+            {
+                if player.is_track_over() {
+                    player.select_track(track);
+                } else {
+                    player.paused = !player.paused;
+                }
+            }
+            if ui
+                .add_enabled(
+                    track + 1 < player.header.song_count,
+                    egui::Button::new("Next"),
+                )
+                .clicked()
+            {
+                player.select_track(track + 1);
+            }
+            ui.label(
+                RichText::new(format!(
+                    "{} / {}",
+                    minutes(player.elapsed() as u32),
+                    minutes(player.track_length(track))
+                ))
+                .monospace(),
+            );
+        });
+
+        egui::ScrollArea::vertical()
+            .max_height(240.0)
+            .show(ui, |ui| {
+                egui::Grid::new("gbs_tracks").striped(true).show(ui, |ui| {
+                    for index in 0..player.header.song_count {
+                        if ui
+                            .selectable_label(index == track, format!("Track {}", index + 1))
+                            .clicked()
+                        {
+                            player.select_track(index);
+                        }
+                        ui.label(RichText::new(minutes(player.track_length(index))).monospace());
+                        ui.end_row();
+                    }
+                });
+            });
+        ui.separator();
+
+        egui::Grid::new("gbs_settings").show(ui, |ui| {
+            if let Some(duration) = player.durations.get_mut(usize::from(track)) {
+                ui.label("Duration");
+                ui.add(egui::DragValue::new(duration).suffix(" s"));
+                ui.end_row();
+            }
+            ui.label("Fade-out");
+            ui.add(egui::DragValue::new(&mut player.fade).suffix(" s"));
+            ui.end_row();
+            ui.label("Volume");
+            ui.add(egui::Slider::new::<f32>(&mut player.volume, 0.0..=1.0).show_value(false));
+            ui.end_row();
+        });
+        ui.checkbox(&mut player.autoplay, "Play the next track");
+    });
+}
+
+/// Format a duration in seconds as `m:ss`
+fn minutes(seconds: u32) -> String {
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}